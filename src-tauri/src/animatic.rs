@@ -0,0 +1,271 @@
+use crate::models::Storyboard;
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{imageops, Delay, Frame, Rgba, RgbaImage};
+use serde_json::json;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// 镜头没有时长时使用的默认时长（秒）
+pub const DEFAULT_SHOT_DURATION: f64 = 3.0;
+
+/// 动态分镜中的一帧
+pub struct AnimaticFrame<'a> {
+    pub storyboard: &'a Storyboard,
+    /// 帧图片的完整路径，None 表示尚未生成，使用占位图
+    pub image: Option<PathBuf>,
+    pub duration: f64,
+}
+
+/// 动态分镜渲染器
+pub struct AnimaticRenderer {
+    pub width: u32,
+    pub height: u32,
+    /// 烧录字幕用的字体，None 表示不烧录字幕
+    pub font: Option<FontVec>,
+}
+
+impl AnimaticRenderer {
+    /// 渲染单帧：按比例缩放居中（黑边），可选烧录对白字幕
+    pub fn render(&self, frame: &AnimaticFrame) -> RgbaImage {
+        let mut canvas = RgbaImage::from_pixel(self.width, self.height, Rgba([0, 0, 0, 255]));
+
+        let source = frame.image.as_ref().and_then(|path| image::open(path).ok());
+        match source {
+            Some(source) => {
+                let fitted = source.resize(self.width, self.height, imageops::FilterType::Triangle).to_rgba8();
+                let x = (self.width - fitted.width()) / 2;
+                let y = (self.height - fitted.height()) / 2;
+                imageops::overlay(&mut canvas, &fitted, x as i64, y as i64);
+            }
+            None => {
+                // 占位图：深灰底 + 镜号
+                canvas = RgbaImage::from_pixel(self.width, self.height, Rgba([48, 48, 48, 255]));
+                if let Some(font) = &self.font {
+                    let scale = self.height as f32 * 0.12;
+                    draw_text_centered(&mut canvas, font, scale, &frame.storyboard.mirror_id, self.height as f32 / 2.0);
+                }
+            }
+        }
+
+        if let (Some(font), Some(dialogue)) = (&self.font, frame.storyboard.dialogue.as_deref()) {
+            if !dialogue.trim().is_empty() {
+                draw_caption(&mut canvas, font, dialogue);
+            }
+        }
+
+        canvas
+    }
+
+    /// 编码为 GIF
+    pub fn write_gif(&self, frames: &[AnimaticFrame], output: &Path) -> Result<(), String> {
+        let file = File::create(output).map_err(|e| format!("创建文件失败: {}", e))?;
+        let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), 10);
+        encoder.set_repeat(Repeat::Infinite).map_err(|e| format!("写入 GIF 失败: {}", e))?;
+        for frame in frames {
+            let delay = Delay::from_numer_denom_ms((frame.duration * 1000.0).round() as u32, 1);
+            encoder
+                .encode_frame(Frame::from_parts(self.render(frame), 0, 0, delay))
+                .map_err(|e| format!("写入 GIF 失败: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// 编码为 APNG（帧延时以 1/100 秒为单位）
+    pub fn write_apng(&self, frames: &[AnimaticFrame], output: &Path) -> Result<(), String> {
+        let file = File::create(output).map_err(|e| format!("创建文件失败: {}", e))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .set_animated(frames.len() as u32, 0)
+            .map_err(|e| format!("写入 APNG 失败: {}", e))?;
+        let mut writer = encoder.write_header().map_err(|e| format!("写入 APNG 失败: {}", e))?;
+        for frame in frames {
+            let centiseconds = (frame.duration * 100.0).round().clamp(1.0, u16::MAX as f64) as u16;
+            writer
+                .set_frame_delay(centiseconds, 100)
+                .and_then(|_| writer.write_image_data(self.render(frame).as_raw()))
+                .map_err(|e| format!("写入 APNG 失败: {}", e))?;
+        }
+        writer.finish().map_err(|e| format!("写入 APNG 失败: {}", e))
+    }
+
+    /// 输出图片序列：每镜一张 PNG + manifest.json + ffmpeg concat 列表
+    pub fn write_sequence(&self, frames: &[AnimaticFrame], output_dir: &Path) -> Result<(), String> {
+        fs::create_dir_all(output_dir).map_err(|e| format!("创建目录失败: {}", e))?;
+
+        let mut entries = Vec::new();
+        let mut concat = String::from("ffconcat version 1.0\n");
+        let mut start = 0.0;
+        for (index, frame) in frames.iter().enumerate() {
+            let file_name = format!("frame_{:04}_{}.png", index + 1, sanitize_file_name(&frame.storyboard.mirror_id));
+            self.render(frame)
+                .save(output_dir.join(&file_name))
+                .map_err(|e| format!("保存帧图片失败: {}", e))?;
+
+            entries.push(json!({
+                "index": index + 1,
+                "mirror_id": frame.storyboard.mirror_id,
+                "file": file_name,
+                "start": start,
+                "duration": frame.duration,
+                "dialogue": frame.storyboard.dialogue,
+            }));
+            concat.push_str(&format!("file '{}'\nduration {:.3}\n", file_name, frame.duration));
+            start += frame.duration;
+        }
+        // concat 格式要求最后一帧再写一次，否则最后一帧的时长会被忽略
+        if let Some(last) = entries.last() {
+            concat.push_str(&format!("file '{}'\n", last["file"].as_str().unwrap_or_default()));
+        }
+
+        let manifest = json!({
+            "width": self.width,
+            "height": self.height,
+            "total_duration": start,
+            "frames": entries,
+        });
+        fs::write(
+            output_dir.join("manifest.json"),
+            serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?,
+        )
+        .map_err(|e| format!("写入 manifest 失败: {}", e))?;
+        fs::write(output_dir.join("frames.ffconcat"), concat)
+            .map_err(|e| format!("写入帧列表失败: {}", e))
+    }
+}
+
+/// 替换文件名中的非法字符（含在相对 URL 中有特殊含义的 # 与 %）
+pub fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '%') { '_' } else { c })
+        .collect()
+}
+
+/// 按最大宽度折行（中文逐字、英文按字符宽度累计，遇换行符强制换行）
+fn wrap_text(font: &FontVec, scale: f32, text: &str, max_width: f32) -> Vec<String> {
+    let scaled = font.as_scaled(PxScale::from(scale));
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut width = 0.0;
+        for c in paragraph.chars() {
+            let advance = scaled.h_advance(font.glyph_id(c));
+            if width + advance > max_width && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                width = 0.0;
+            }
+            line.push(c);
+            width += advance;
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// 计算一行文字的宽度
+fn text_width(font: &FontVec, scale: f32, text: &str) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(scale));
+    text.chars().map(|c| scaled.h_advance(font.glyph_id(c))).sum()
+}
+
+/// 在指定基线位置绘制一行白色文字
+fn draw_text(canvas: &mut RgbaImage, font: &FontVec, scale: f32, text: &str, x: f32, baseline: f32) {
+    let scaled = font.as_scaled(PxScale::from(scale));
+    let mut cursor = x;
+    for c in text.chars() {
+        let glyph = font.glyph_id(c).with_scale_and_position(scale, point(cursor, baseline));
+        cursor += scaled.h_advance(glyph.id);
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px >= 0 && py >= 0 && (px as u32) < canvas.width() && (py as u32) < canvas.height() {
+                let pixel = canvas.get_pixel_mut(px as u32, py as u32);
+                for channel in 0..3 {
+                    let base = pixel[channel] as f32;
+                    pixel[channel] = (base + (255.0 - base) * coverage).round() as u8;
+                }
+            }
+        });
+    }
+}
+
+/// 水平居中绘制一行文字，center_y 为文字垂直中心
+fn draw_text_centered(canvas: &mut RgbaImage, font: &FontVec, scale: f32, text: &str, center_y: f32) {
+    let width = text_width(font, scale, text);
+    let x = (canvas.width() as f32 - width) / 2.0;
+    draw_text(canvas, font, scale, text, x, center_y + scale * 0.35);
+}
+
+/// 在画面底部绘制字幕（半透明底条，最多 3 行）
+fn draw_caption(canvas: &mut RgbaImage, font: &FontVec, text: &str) {
+    let (width, height) = (canvas.width() as f32, canvas.height() as f32);
+    let scale = (height * 0.05).max(14.0);
+    let line_height = scale * 1.3;
+
+    let mut lines = wrap_text(font, scale, text, width * 0.9);
+    if lines.len() > 3 {
+        lines.truncate(3);
+        if let Some(last) = lines.last_mut() {
+            last.pop();
+            last.push('…');
+        }
+    }
+
+    let box_height = line_height * lines.len() as f32 + scale * 0.6;
+    let box_top = (height - box_height - height * 0.04).max(0.0) as u32;
+    let box_bottom = ((box_top as f32 + box_height) as u32).min(canvas.height());
+    for y in box_top..box_bottom {
+        for x in 0..canvas.width() {
+            let pixel = canvas.get_pixel_mut(x, y);
+            for channel in 0..3 {
+                pixel[channel] = (pixel[channel] as f32 * 0.4) as u8;
+            }
+        }
+    }
+
+    for (index, line) in lines.iter().enumerate() {
+        let center_y = box_top as f32 + scale * 0.3 + line_height * (index as f32 + 0.5);
+        draw_text_centered(canvas, font, scale, line, center_y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_sequence_manifest() {
+        let dir = std::env::temp_dir().join(format!("storyboard_test_animatic_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let storyboard: Storyboard = serde_json::from_value(json!({
+            "sequence_number": 1,
+            "mirror_id": "A1",
+            "duration": 2.5,
+            "dialogue": "你好",
+        })).unwrap();
+        let frames = vec![
+            AnimaticFrame { storyboard: &storyboard, image: None, duration: 2.5 },
+            AnimaticFrame { storyboard: &storyboard, image: None, duration: 1.0 },
+        ];
+        let renderer = AnimaticRenderer { width: 64, height: 36, font: None };
+        renderer.write_sequence(&frames, &dir).unwrap();
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("manifest.json")).unwrap()).unwrap();
+        assert_eq!(manifest["total_duration"], json!(3.5));
+        assert_eq!(manifest["frames"][1]["start"], json!(2.5));
+        assert!(dir.join("frame_0002_A1.png").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
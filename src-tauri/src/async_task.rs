@@ -0,0 +1,245 @@
+use crate::image_api::build_agent;
+use crate::models::{ApiConfig, AsyncTaskConfig};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// 异步任务状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskStatus {
+    /// 排队或生成中（附带厂商返回的原始状态）
+    Running(String),
+    /// 生成完成，附带全部输出地址
+    Succeeded(Vec<String>),
+    /// 生成失败，附带错误信息
+    Failed(String),
+}
+
+impl AsyncTaskConfig {
+    /// 内置的视频任务格式：POST /v1/video/generations，GET /v1/video/generations/{task_id}
+    pub fn default_video() -> Self {
+        AsyncTaskConfig {
+            submit_path: Some("/v1/video/generations".to_string()),
+            poll_path: "/v1/video/generations/{task_id}".to_string(),
+            poll_method: None,
+            task_id_path: "id|task_id|data.id|data.task_id".to_string(),
+            status_path: "data.status|status|data.task_status|task_status".to_string(),
+            output_url_path: "data.video_url|video_url|data.output.video_url|output.video_url|data.content.video_url|content.video_url|data.url|url|data.data[0].url".to_string(),
+            error_path: Some("data.error.message|error.message|data.error|error|data.message|message|data.fail_reason|fail_reason".to_string()),
+            success_values: Vec::new(),
+            failure_values: Vec::new(),
+            poll_interval_secs: None,
+            timeout_secs: None,
+            extra_body: None,
+        }
+    }
+
+    fn is_success(&self, status: &str) -> bool {
+        if self.success_values.is_empty() {
+            matches!(status, "succeeded" | "success" | "successful" | "completed" | "complete" | "done" | "finished")
+        } else {
+            self.success_values.iter().any(|v| v.eq_ignore_ascii_case(status))
+        }
+    }
+
+    fn is_failure(&self, status: &str) -> bool {
+        if self.failure_values.is_empty() {
+            matches!(status, "failed" | "failure" | "fail" | "error" | "cancelled" | "canceled" | "expired")
+        } else {
+            self.failure_values.iter().any(|v| v.eq_ignore_ascii_case(status))
+        }
+    }
+}
+
+/// 按路径读取 JSON 值，支持 a.b[0].c、a.b.0.c 与 $. 前缀；
+/// 用 | 分隔多个候选路径时返回第一个非空结果
+pub fn json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('|').find_map(|candidate| {
+        let candidate = candidate.trim();
+        let candidate = candidate.strip_prefix("$.").or_else(|| candidate.strip_prefix('$')).unwrap_or(candidate);
+        let mut current = value;
+        for segment in candidate.split('.').filter(|s| !s.is_empty()) {
+            let (key, indexes) = match segment.find('[') {
+                Some(pos) => (&segment[..pos], &segment[pos..]),
+                None => (segment, ""),
+            };
+            if !key.is_empty() {
+                current = match key.parse::<usize>() {
+                    Ok(index) if current.is_array() => current.get(index)?,
+                    _ => current.get(key)?,
+                };
+            }
+            for index in indexes.split(['[', ']']).filter(|s| !s.is_empty()) {
+                current = current.get(index.parse::<usize>().ok()?)?;
+            }
+        }
+        (!current.is_null()).then_some(current)
+    })
+}
+
+/// 将 JSON 值读取为字符串（数字等也会转换）
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// 读取输出地址：字符串、字符串数组或 [{ url }] 数组
+fn output_urls(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| value_to_string(item).or_else(|| item.get("url").and_then(value_to_string)))
+            .collect(),
+        other => value_to_string(other).into_iter().collect(),
+    }
+}
+
+/// 拼接接口地址：path 以 http 开头时直接使用，否则拼在 base_url 后
+fn endpoint(api_config: &ApiConfig, path: &str) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") {
+        path.to_string()
+    } else {
+        format!("{}/{}", api_config.base_url.trim_end_matches('/'), path.trim_start_matches('/'))
+    }
+}
+
+/// 读取响应 JSON
+fn read_json(response: ureq::Response) -> Result<Value, String> {
+    let text = response.into_string().map_err(|e| format!("读取任务响应失败: {}", e))?;
+    serde_json::from_str(&text).map_err(|e| format!("解析任务响应失败: {}", e))
+}
+
+/// 提交任务，返回任务 id；extra_body 中的字段会合并进请求体
+pub fn submit_task(
+    api_config: &ApiConfig,
+    task: &AsyncTaskConfig,
+    default_submit_path: &str,
+    mut body: Value,
+) -> Result<String, String> {
+    if let (Some(Value::Object(extra)), Value::Object(fields)) = (&task.extra_body, &mut body) {
+        for (key, value) in extra {
+            fields.insert(key.clone(), value.clone());
+        }
+    }
+
+    let url = endpoint(api_config, task.submit_path.as_deref().unwrap_or(default_submit_path));
+    let response = build_agent(120)
+        .post(&url)
+        .set("Authorization", &format!("Bearer {}", api_config.api_key))
+        .set("Content-Type", "application/json")
+        .send_string(&serde_json::to_string(&body).map_err(|e| e.to_string())?)
+        .map_err(|e| format!("提交任务失败: {}", e))?;
+    let response_json = read_json(response)?;
+
+    json_path(&response_json, &task.task_id_path)
+        .and_then(value_to_string)
+        .ok_or_else(|| format!("无法从响应中提取任务 id: {}", response_json))
+}
+
+/// 查询一次任务状态
+pub fn poll_task(api_config: &ApiConfig, task: &AsyncTaskConfig, task_id: &str) -> Result<TaskStatus, String> {
+    let url = endpoint(api_config, &task.poll_path.replace("{task_id}", task_id));
+    let auth = format!("Bearer {}", api_config.api_key);
+    let agent = build_agent(60);
+    let response = if task.poll_method.as_deref().is_some_and(|m| m.eq_ignore_ascii_case("post")) {
+        agent.post(&url)
+            .set("Authorization", &auth)
+            .set("Content-Type", "application/json")
+            .send_string(&serde_json::json!({ "task_id": task_id }).to_string())
+    } else {
+        agent.get(&url).set("Authorization", &auth).call()
+    }.map_err(|e| format!("查询任务失败: {}", e))?;
+
+    Ok(parse_task_status(task, &read_json(response)?))
+}
+
+/// 按配置解析任务状态响应
+pub fn parse_task_status(task: &AsyncTaskConfig, response_json: &Value) -> TaskStatus {
+    let status = json_path(response_json, &task.status_path)
+        .and_then(value_to_string)
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    if task.is_success(&status) {
+        let urls = json_path(response_json, &task.output_url_path).map(output_urls).unwrap_or_default();
+        if urls.is_empty() {
+            TaskStatus::Failed("任务已完成但响应中没有输出地址".to_string())
+        } else {
+            TaskStatus::Succeeded(urls)
+        }
+    } else if task.is_failure(&status) {
+        TaskStatus::Failed(
+            task.error_path.as_deref()
+                .and_then(|path| json_path(response_json, path))
+                .and_then(value_to_string)
+                .unwrap_or_else(|| format!("任务失败: {}", status)),
+        )
+    } else {
+        TaskStatus::Running(status)
+    }
+}
+
+/// 轮询直到任务完成、失败或超时；每次仍在进行时调用 on_running
+pub fn wait_for_task(
+    api_config: &ApiConfig,
+    task: &AsyncTaskConfig,
+    task_id: &str,
+    default_timeout_secs: u64,
+    mut on_running: impl FnMut(&str),
+) -> Result<TaskStatus, String> {
+    let interval = Duration::from_secs(task.poll_interval_secs.unwrap_or(5).max(1));
+    let timeout = Duration::from_secs(task.timeout_secs.unwrap_or(default_timeout_secs));
+    let started = Instant::now();
+
+    loop {
+        match poll_task(api_config, task, task_id)? {
+            TaskStatus::Running(status) => on_running(&status),
+            finished => return Ok(finished),
+        }
+        if started.elapsed() > timeout {
+            return Err("任务超时".to_string());
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_path() {
+        let v = json!({ "data": { "task_id": 42, "result": { "urls": ["a.png", "b.png"] } } });
+        assert_eq!(json_path(&v, "$.data.task_id"), Some(&json!(42)));
+        assert_eq!(json_path(&v, "data.result.urls[1]"), Some(&json!("b.png")));
+        assert_eq!(json_path(&v, "data.result.urls.0"), Some(&json!("a.png")));
+        assert_eq!(json_path(&v, "id|data.task_id"), Some(&json!(42)));
+        assert_eq!(json_path(&v, "data.missing"), None);
+    }
+
+    #[test]
+    fn test_parse_task_status() {
+        let task = AsyncTaskConfig::default_video();
+        let done = json!({ "data": { "status": "SUCCEEDED", "content": { "video_url": "https://example.com/a.mp4" } } });
+        assert_eq!(
+            parse_task_status(&task, &done),
+            TaskStatus::Succeeded(vec!["https://example.com/a.mp4".to_string()])
+        );
+        let running = json!({ "status": "queued" });
+        assert_eq!(parse_task_status(&task, &running), TaskStatus::Running("queued".to_string()));
+        let failed = json!({ "status": "failed", "error": { "message": "bad prompt" } });
+        assert_eq!(parse_task_status(&task, &failed), TaskStatus::Failed("bad prompt".to_string()));
+
+        let custom = AsyncTaskConfig {
+            status_path: "output.task_status".to_string(),
+            output_url_path: "output.results".to_string(),
+            success_values: vec!["SUCCEEDED".to_string()],
+            ..AsyncTaskConfig::default_video()
+        };
+        let done = json!({ "output": { "task_status": "SUCCEEDED", "results": [{ "url": "x.png" }] } });
+        assert_eq!(parse_task_status(&custom, &done), TaskStatus::Succeeded(vec!["x.png".to_string()]));
+    }
+}
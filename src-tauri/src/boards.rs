@@ -0,0 +1,582 @@
+use crate::commands::unix_timestamp;
+use crate::db::ProjectDatabase;
+use crate::models::{Board, BoardComparison, BoardFieldChange};
+use crate::review::LOCKED;
+use rusqlite::types::{Value, ValueRef};
+use serde_json::{Map, Value as JsonValue};
+use std::collections::{HashMap, HashSet};
+
+/// 随版本切换的表（均以镜号为键）；图片历史与生图任务按镜号在各版本间共享，
+/// 选中的图片版本以当前版本分镜的首帧/尾帧路径为准
+const BOARD_TABLES: [&str; 2] = ["storyboards", "storyboard_sources"];
+
+/// 对比时检查的分镜字段
+const COMPARE_FIELDS: [&str; 13] = [
+    "shot_type", "shot_size", "duration", "dialogue", "description", "notes", "image_first_path",
+    "camera_movement", "camera_direction", "camera_angle", "focal_length", "transition", "review_status",
+];
+
+type Row = Map<String, JsonValue>;
+
+/// 当前版本 ID
+pub fn active_board_id(db: &ProjectDatabase) -> Result<i64, String> {
+    db.get_meta("active_board_id")
+        .and_then(|id| id.parse().ok())
+        .ok_or_else(|| "读取当前版本失败".to_string())
+}
+
+fn table_columns(db: &ProjectDatabase, table: &str) -> Result<Vec<String>, String> {
+    let mut stmt = db.conn().prepare("SELECT name FROM pragma_table_info(?1)")
+        .map_err(|e| format!("读取表结构失败: {}", e))?;
+    let columns = stmt.query_map([table], |row| row.get(0))
+        .map_err(|e| format!("读取表结构失败: {}", e))?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| format!("读取表结构失败: {}", e))?;
+    Ok(columns)
+}
+
+fn to_json(value: ValueRef) -> JsonValue {
+    match value {
+        ValueRef::Null => JsonValue::Null,
+        ValueRef::Integer(i) => JsonValue::from(i),
+        ValueRef::Real(f) => JsonValue::from(f),
+        ValueRef::Text(t) => JsonValue::from(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Blob(b) => JsonValue::from(b.to_vec()),
+    }
+}
+
+fn from_json(value: &JsonValue) -> Value {
+    match value {
+        JsonValue::Null => Value::Null,
+        JsonValue::Bool(b) => Value::Integer(*b as i64),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or(0.0)),
+        },
+        JsonValue::String(s) => Value::Text(s.clone()),
+        JsonValue::Array(items) => Value::Blob(items.iter().filter_map(|i| i.as_u64()).map(|b| b as u8).collect()),
+        JsonValue::Object(_) => Value::Text(value.to_string()),
+    }
+}
+
+/// 读取当前版本各表的全部行（按列名保存，之后新增的列在恢复旧快照时取默认值）
+fn snapshot_live(db: &ProjectDatabase) -> Result<JsonValue, String> {
+    let mut snapshot = Map::new();
+    for table in BOARD_TABLES {
+        let columns = table_columns(db, table)?;
+        let mut stmt = db.conn().prepare(&format!("SELECT * FROM {}", table))
+            .map_err(|e| format!("读取{}失败: {}", table, e))?;
+        let rows = stmt.query_map([], |row| {
+            let mut object = Row::new();
+            for (i, column) in columns.iter().enumerate() {
+                object.insert(column.clone(), to_json(row.get_ref(i)?));
+            }
+            Ok(JsonValue::Object(object))
+        }).map_err(|e| format!("读取{}失败: {}", table, e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("读取{}失败: {}", table, e))?;
+        snapshot.insert(table.to_string(), JsonValue::Array(rows));
+    }
+    Ok(JsonValue::Object(snapshot))
+}
+
+/// 按列名插入一行快照数据，表中已不存在的列忽略
+fn insert_row(db: &ProjectDatabase, table: &str, columns: &HashSet<String>, row: &Row) -> Result<(), String> {
+    let (names, values): (Vec<&String>, Vec<Value>) = row.iter()
+        .filter(|(name, _)| columns.contains(*name))
+        .map(|(name, value)| (name, from_json(value)))
+        .unzip();
+    let placeholders = (1..=names.len()).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(", ");
+    let names = names.iter().map(|n| n.as_str()).collect::<Vec<_>>().join(", ");
+    db.conn().execute(
+        &format!("INSERT INTO {} ({}) VALUES ({})", table, names, placeholders),
+        rusqlite::params_from_iter(values),
+    ).map_err(|e| format!("恢复{}失败: {}", table, e))?;
+    Ok(())
+}
+
+/// 用快照替换当前版本各表的内容
+fn restore_live(db: &ProjectDatabase, snapshot: &JsonValue) -> Result<(), String> {
+    for table in BOARD_TABLES {
+        let columns: HashSet<String> = table_columns(db, table)?.into_iter().collect();
+        db.conn().execute(&format!("DELETE FROM {}", table), [])
+            .map_err(|e| format!("清空{}失败: {}", table, e))?;
+        let rows = snapshot.get(table).and_then(|r| r.as_array()).cloned().unwrap_or_default();
+        for row in rows.iter().filter_map(|r| r.as_object()) {
+            insert_row(db, table, &columns, row)?;
+        }
+    }
+    // 快照中的序列可能已被删除
+    let default_id = db.default_sequence_id().map_err(|e| format!("读取默认序列失败: {}", e))?;
+    db.conn().execute(
+        "UPDATE storyboards SET sequence_id = ?1
+         WHERE sequence_id IS NULL OR sequence_id NOT IN (SELECT id FROM sequences)",
+        [default_id],
+    ).map_err(|e| format!("恢复分镜序列失败: {}", e))?;
+    Ok(())
+}
+
+/// 版本内容：当前版本读取实时数据，其余读取快照
+fn board_snapshot(db: &ProjectDatabase, id: i64) -> Result<JsonValue, String> {
+    if id == active_board_id(db)? {
+        return snapshot_live(db);
+    }
+    let snapshot: String = db.conn().query_row("SELECT snapshot FROM boards WHERE id = ?1", [id], |row| row.get(0))
+        .map_err(|_| format!("版本 {} 不存在", id))?;
+    serde_json::from_str(&snapshot).map_err(|e| format!("解析版本快照失败: {}", e))
+}
+
+/// 快照中的分镜行，按顺序号排列
+fn storyboard_rows(snapshot: &JsonValue) -> Vec<Row> {
+    let mut rows: Vec<Row> = snapshot.get("storyboards")
+        .and_then(|r| r.as_array())
+        .map(|rows| rows.iter().filter_map(|r| r.as_object().cloned()).collect())
+        .unwrap_or_default();
+    rows.sort_by_key(|row| row.get("sequence_number").and_then(|n| n.as_i64()).unwrap_or(0));
+    rows
+}
+
+/// 字段值转为文本，空串与 NULL 视为相同
+fn field_text(row: &Row, field: &str) -> Option<String> {
+    match row.get(field)? {
+        JsonValue::Null => None,
+        JsonValue::String(s) if s.trim().is_empty() => None,
+        JsonValue::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn row_duration(row: &Row) -> f64 {
+    field_text(row, "duration").and_then(|d| d.parse().ok()).unwrap_or(0.0)
+}
+
+fn row_mirror_id(row: &Row) -> String {
+    field_text(row, "mirror_id").unwrap_or_default()
+}
+
+fn load_board(db: &ProjectDatabase, id: i64, active_id: i64) -> Result<Board, String> {
+    let (name, created_at, updated_at): (String, i64, i64) = db.conn().query_row(
+        "SELECT name, created_at, updated_at FROM boards WHERE id = ?1",
+        [id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).map_err(|_| format!("版本 {} 不存在", id))?;
+    let rows = storyboard_rows(&board_snapshot(db, id)?);
+    Ok(Board {
+        id,
+        name,
+        active: id == active_id,
+        storyboard_count: rows.len(),
+        total_duration: rows.iter().map(row_duration).sum(),
+        created_at,
+        updated_at,
+    })
+}
+
+/// 列出项目中的全部版本
+pub fn list_boards(db: &ProjectDatabase) -> Result<Vec<Board>, String> {
+    let active_id = active_board_id(db)?;
+    let mut stmt = db.conn().prepare("SELECT id FROM boards ORDER BY id")
+        .map_err(|e| format!("查询版本失败: {}", e))?;
+    let ids = stmt.query_map([], |row| row.get::<_, i64>(0))
+        .map_err(|e| format!("查询版本失败: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("查询版本失败: {}", e))?;
+    ids.into_iter().map(|id| load_board(db, id, active_id)).collect()
+}
+
+/// 新建版本：指定来源时复制其分镜，否则为空白版本；新版本不会自动切换
+pub fn create_board(db: &ProjectDatabase, name: &str, source_id: Option<i64>) -> Result<Board, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("版本名称不能为空".to_string());
+    }
+    let snapshot = match source_id {
+        Some(id) => board_snapshot(db, id)?,
+        None => JsonValue::Object(Map::new()),
+    };
+    let now = unix_timestamp()?;
+    db.conn().execute(
+        "INSERT INTO boards (name, snapshot, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)",
+        rusqlite::params![name, snapshot.to_string(), now],
+    ).map_err(|e| format!("创建版本失败: {}", e))?;
+    load_board(db, db.conn().last_insert_rowid(), active_board_id(db)?)
+}
+
+/// 切换当前版本：当前分镜存回快照，目标版本的快照载入 storyboards
+/// 已锁定的镜头原样带入目标版本（沿用目标版本中的排序号），切换不会覆盖或丢弃定稿内容
+pub fn switch_board(db: &ProjectDatabase, id: i64) -> Result<Board, String> {
+    let active_id = active_board_id(db)?;
+    if id != active_id {
+        let pending: i64 = db.conn().query_row(
+            "SELECT COUNT(*) FROM image_jobs WHERE status IN ('pending', 'running')",
+            [],
+            |row| row.get(0),
+        ).map_err(|e| format!("查询生图任务失败: {}", e))?;
+        if pending > 0 {
+            return Err("还有未完成的生图任务，请等待完成或取消后再切换版本".to_string());
+        }
+        let target = board_snapshot(db, id)?;
+        let live = snapshot_live(db)?;
+        let now = unix_timestamp()?;
+
+        let tx = db.conn().unchecked_transaction()
+            .map_err(|e| format!("开启事务失败: {}", e))?;
+        db.conn().execute(
+            "UPDATE boards SET snapshot = ?1, updated_at = ?2 WHERE id = ?3",
+            rusqlite::params![live.to_string(), now, active_id],
+        ).map_err(|e| format!("保存当前版本失败: {}", e))?;
+        restore_live(db, &target)?;
+        carry_locked_shots(db, &live, &target)?;
+        sync_selected_images(db)?;
+        db.set_meta("active_board_id", Some(&id.to_string()))
+            .map_err(|e| format!("切换版本失败: {}", e))?;
+        tx.commit().map_err(|e| format!("切换版本失败: {}", e))?;
+    }
+    load_board(db, id, id)
+}
+
+/// 把切换前已锁定的镜头原样写回新的当前版本，排序号沿用目标版本（目标版本中没有时沿用原值）
+fn carry_locked_shots(db: &ProjectDatabase, live: &JsonValue, target: &JsonValue) -> Result<(), String> {
+    let columns: HashSet<String> = table_columns(db, "storyboards")?.into_iter().collect();
+    let target_rows = storyboard_rows(target);
+    for mut row in storyboard_rows(live) {
+        if row.get("review_status").and_then(|s| s.as_str()) != Some(LOCKED) {
+            continue;
+        }
+        let mirror_id = row_mirror_id(&row);
+        if let Some(sequence_number) = target_rows.iter()
+            .find(|t| row_mirror_id(t) == mirror_id)
+            .and_then(|t| t.get("sequence_number").cloned())
+        {
+            row.insert("sequence_number".to_string(), sequence_number);
+        }
+        db.conn().execute("DELETE FROM storyboards WHERE mirror_id = ?1", [&mirror_id])
+            .map_err(|e| format!("保留锁定分镜失败: {}", e))?;
+        insert_row(db, "storyboards", &columns, &row)?;
+    }
+    Ok(())
+}
+
+/// 按当前分镜的首帧/尾帧路径重新标记选中的图片版本
+fn sync_selected_images(db: &ProjectDatabase) -> Result<(), String> {
+    db.conn().execute(
+        "UPDATE storyboard_images SET selected = COALESCE((
+            SELECT CASE storyboard_images.frame WHEN 'last' THEN s.image_last_path ELSE s.image_first_path END
+                = storyboard_images.file_path
+            FROM storyboards s WHERE s.mirror_id = storyboard_images.mirror_id
+         ), 0)",
+        [],
+    ).map_err(|e| format!("同步图片选中状态失败: {}", e))?;
+    Ok(())
+}
+
+/// 是否有版本（含当前版本）的分镜仍在使用该图片文件
+pub fn image_in_use(db: &ProjectDatabase, file_path: &str) -> Result<bool, String> {
+    for id in board_ids(db)? {
+        let in_use = storyboard_rows(&board_snapshot(db, id)?).iter().any(|row| {
+            ["image_first_path", "image_last_path"].iter()
+                .any(|field| field_text(row, field).as_deref() == Some(file_path))
+        });
+        if in_use {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn board_ids(db: &ProjectDatabase) -> Result<Vec<i64>, String> {
+    let mut stmt = db.conn().prepare("SELECT id FROM boards")
+        .map_err(|e| format!("查询版本失败: {}", e))?;
+    let ids = stmt.query_map([], |row| row.get::<_, i64>(0))
+        .map_err(|e| format!("查询版本失败: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("查询版本失败: {}", e))?;
+    Ok(ids)
+}
+
+/// 删除已不在任何版本中的镜头的图片版本记录，返回不再被引用、可以删除的图片文件
+/// 切换版本后不在当前版本中的镜头仍保存在其他版本的快照里，其图片历史保留到该版本被删除为止
+pub fn prune_storyboard_images(db: &ProjectDatabase) -> Result<Vec<String>, String> {
+    let mut mirror_ids = HashSet::new();
+    let mut files_in_use = HashSet::new();
+    for id in board_ids(db)? {
+        for row in storyboard_rows(&board_snapshot(db, id)?) {
+            mirror_ids.insert(row_mirror_id(&row));
+            files_in_use.extend(["image_first_path", "image_last_path"].iter().filter_map(|f| field_text(&row, f)));
+        }
+    }
+
+    let mut stmt = db.conn().prepare("SELECT id, mirror_id, file_path FROM storyboard_images")
+        .map_err(|e| format!("查询图片版本失败: {}", e))?;
+    let images = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+        .map_err(|e| format!("查询图片版本失败: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("查询图片版本失败: {}", e))?;
+
+    let (stale, kept): (Vec<_>, Vec<_>) = images.into_iter().partition(|(_, mirror_id, _)| !mirror_ids.contains(mirror_id));
+    for (id, _, _) in &stale {
+        db.conn().execute("DELETE FROM storyboard_images WHERE id = ?1", [id])
+            .map_err(|e| format!("删除图片版本失败: {}", e))?;
+    }
+    files_in_use.extend(kept.into_iter().map(|(_, _, file)| file));
+    let mut orphaned: Vec<String> = stale.into_iter()
+        .map(|(_, _, file)| file)
+        .filter(|file| !files_in_use.contains(file))
+        .collect();
+    orphaned.sort();
+    orphaned.dedup();
+    Ok(orphaned)
+}
+
+/// 重命名版本
+pub fn rename_board(db: &ProjectDatabase, id: i64, name: &str) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("版本名称不能为空".to_string());
+    }
+    let updated = db.conn().execute("UPDATE boards SET name = ?1 WHERE id = ?2", rusqlite::params![name, id])
+        .map_err(|e| format!("重命名版本失败: {}", e))?;
+    if updated == 0 {
+        return Err(format!("版本 {} 不存在", id));
+    }
+    Ok(())
+}
+
+/// 删除非当前版本
+pub fn delete_board(db: &ProjectDatabase, id: i64) -> Result<(), String> {
+    if id == active_board_id(db)? {
+        return Err("不能删除当前版本，请先切换到其他版本".to_string());
+    }
+    db.conn().execute("DELETE FROM boards WHERE id = ?1", [id])
+        .map_err(|e| format!("删除版本失败: {}", e))?;
+    Ok(())
+}
+
+/// 最长公共子序列之外的镜号即为顺序发生变化的镜头
+fn reordered_ids(a: &[String], b: &[String]) -> Vec<String> {
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut kept = HashSet::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            kept.insert(a[i].clone());
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    a.iter().filter(|id| !kept.contains(*id)).cloned().collect()
+}
+
+/// 对比两个版本：增删的镜号、字段变化与顺序变化
+pub fn compare_boards(db: &ProjectDatabase, a_id: i64, b_id: i64) -> Result<BoardComparison, String> {
+    let active_id = active_board_id(db)?;
+    let rows_a = storyboard_rows(&board_snapshot(db, a_id)?);
+    let rows_b = storyboard_rows(&board_snapshot(db, b_id)?);
+    let by_id_b: HashMap<String, &Row> = rows_b.iter().map(|r| (row_mirror_id(r), r)).collect();
+    let ids_a: HashSet<String> = rows_a.iter().map(row_mirror_id).collect();
+
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+    for row_a in &rows_a {
+        let mirror_id = row_mirror_id(row_a);
+        let Some(row_b) = by_id_b.get(&mirror_id) else { continue };
+        let before = changes.len();
+        for field in COMPARE_FIELDS {
+            let (a, b) = (field_text(row_a, field), field_text(row_b, field));
+            let same = match field {
+                "duration" => row_duration(row_a) == row_duration(row_b),
+                _ => a == b,
+            };
+            if !same {
+                changes.push(BoardFieldChange { mirror_id: mirror_id.clone(), field: field.to_string(), a, b });
+            }
+        }
+        if changes.len() == before {
+            unchanged += 1;
+        }
+    }
+
+    let common_a: Vec<String> = rows_a.iter().map(row_mirror_id).filter(|id| by_id_b.contains_key(id)).collect();
+    let common_b: Vec<String> = rows_b.iter().map(row_mirror_id).filter(|id| ids_a.contains(id)).collect();
+
+    Ok(BoardComparison {
+        board_a: load_board(db, a_id, active_id)?,
+        board_b: load_board(db, b_id, active_id)?,
+        only_in_a: rows_a.iter().map(row_mirror_id).filter(|id| !by_id_b.contains_key(id)).collect(),
+        only_in_b: rows_b.iter().map(row_mirror_id).filter(|id| !ids_a.contains(id)).collect(),
+        changes,
+        reordered: reordered_ids(&common_a, &common_b),
+        unchanged,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::temp_project;
+
+    #[test]
+    fn test_switch_and_compare_boards() {
+        let dir = temp_project("boards");
+        let db = ProjectDatabase::open(&dir).unwrap();
+        db.conn().execute(
+            "INSERT INTO storyboards (mirror_id, sequence_number, shot_size, duration)
+             VALUES ('A1', 1, '全景', 3.0), ('A2', 2, '近景', 2.0), ('A3', 3, '特写', 1.5)",
+            [],
+        ).unwrap();
+        let main_id = active_board_id(&db).unwrap();
+
+        let alt = create_board(&db, "B 版", Some(main_id)).unwrap();
+        assert_eq!(alt.storyboard_count, 3);
+        assert!(!alt.active);
+
+        switch_board(&db, alt.id).unwrap();
+        db.conn().execute("UPDATE storyboards SET shot_size = '中景' WHERE mirror_id = 'A1'", []).unwrap();
+        db.conn().execute("UPDATE storyboards SET sequence_number = 0 WHERE mirror_id = 'A3'", []).unwrap();
+        db.conn().execute("DELETE FROM storyboards WHERE mirror_id = 'A2'", []).unwrap();
+        db.conn().execute("INSERT INTO storyboards (mirror_id, sequence_number) VALUES ('A4', 4)", []).unwrap();
+
+        let comparison = compare_boards(&db, main_id, alt.id).unwrap();
+        assert_eq!(comparison.only_in_a, vec!["A2"]);
+        assert_eq!(comparison.only_in_b, vec!["A4"]);
+        assert_eq!(comparison.changes.len(), 1);
+        assert_eq!(comparison.changes[0].b.as_deref(), Some("中景"));
+        assert_eq!(comparison.reordered.len(), 1);
+
+        // 切回主版本后分镜恢复原状
+        switch_board(&db, main_id).unwrap();
+        let sizes: Vec<String> = db.conn()
+            .prepare("SELECT shot_size FROM storyboards ORDER BY sequence_number").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(sizes, vec!["全景", "近景", "特写"]);
+        assert!(delete_board(&db, main_id).is_err());
+
+        // 锁定的镜头切换后原样保留，B 版中删掉的 A2 也会带过去
+        crate::review::set_review_status(&db, &["A1".to_string(), "A2".to_string()], LOCKED, None, None).unwrap();
+        switch_board(&db, alt.id).unwrap();
+        let rows: Vec<(String, String, String)> = db.conn()
+            .prepare("SELECT mirror_id, shot_size, review_status FROM storyboards WHERE mirror_id IN ('A1', 'A2') ORDER BY mirror_id").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(rows, vec![
+            ("A1".to_string(), "全景".to_string(), LOCKED.to_string()),
+            ("A2".to_string(), "近景".to_string(), LOCKED.to_string()),
+        ]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_image_shared_between_boards() {
+        use crate::commands::{delete_storyboard_image, list_storyboard_images};
+        use crate::db::get_images_dir;
+        use crate::models::StoryboardImage;
+
+        let dir = temp_project("board_images");
+        let folder = dir.to_string_lossy().to_string();
+        let db = ProjectDatabase::open(&dir).unwrap();
+        db.conn().execute("INSERT INTO storyboards (mirror_id, sequence_number) VALUES ('A1', 1)", []).unwrap();
+        let images_dir = get_images_dir(&dir);
+        std::fs::create_dir_all(&images_dir).unwrap();
+        let mut ids = Vec::new();
+        for file in ["A1_first_1.png", "A1_first_2.png"] {
+            std::fs::write(images_dir.join(file), b"png").unwrap();
+            let id = db.insert_storyboard_image(&StoryboardImage {
+                id: None,
+                mirror_id: "A1".to_string(),
+                frame: "first".to_string(),
+                file_path: file.to_string(),
+                prompt: None,
+                provider: None,
+                model: None,
+                seed: None,
+                created_at: ids.len() as i64,
+                selected: false,
+            }).unwrap();
+            ids.push(id);
+        }
+        let main_id = active_board_id(&db).unwrap();
+        db.select_storyboard_image(ids[0]).unwrap();
+
+        // B 版选用第二张图
+        let alt = create_board(&db, "B 版", Some(main_id)).unwrap();
+        switch_board(&db, alt.id).unwrap();
+        db.select_storyboard_image(ids[1]).unwrap();
+
+        // 切回主版本后选中状态跟随主版本的分镜
+        switch_board(&db, main_id).unwrap();
+        let images = list_storyboard_images(folder.clone(), "A1".to_string(), None).unwrap();
+        let selected: Vec<&str> = images.iter().filter(|i| i.selected).map(|i| i.file_path.as_str()).collect();
+        assert_eq!(selected, vec!["A1_first_1.png"]);
+
+        // B 版仍在使用第二张图，删除版本记录时保留文件
+        delete_storyboard_image(folder.clone(), ids[1]).unwrap();
+        assert!(images_dir.join("A1_first_2.png").exists());
+        assert!(image_in_use(&db, "A1_first_2.png").unwrap());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_prune_images_of_removed_shots() {
+        use crate::commands::{delete_board, list_storyboard_images};
+        use crate::db::get_images_dir;
+        use crate::models::StoryboardImage;
+
+        let dir = temp_project("board_prune_images");
+        let folder = dir.to_string_lossy().to_string();
+        let db = ProjectDatabase::open(&dir).unwrap();
+        db.conn().execute("INSERT INTO storyboards (mirror_id, sequence_number) VALUES ('A1', 1), ('A2', 2)", []).unwrap();
+        let images_dir = get_images_dir(&dir);
+        std::fs::create_dir_all(&images_dir).unwrap();
+        for mirror_id in ["A1", "A2"] {
+            let file = format!("{}_first.png", mirror_id);
+            std::fs::write(images_dir.join(&file), b"png").unwrap();
+            db.insert_storyboard_image(&StoryboardImage {
+                id: None,
+                mirror_id: mirror_id.to_string(),
+                frame: "first".to_string(),
+                file_path: file,
+                prompt: None,
+                provider: None,
+                model: None,
+                seed: None,
+                created_at: 0,
+                selected: false,
+            }).unwrap();
+        }
+        let main_id = active_board_id(&db).unwrap();
+        let alt = create_board(&db, "B 版", Some(main_id)).unwrap();
+        switch_board(&db, alt.id).unwrap();
+        db.conn().execute("DELETE FROM storyboards WHERE mirror_id = 'A2'", []).unwrap();
+
+        // B 版去掉了 A2，但主版本仍有 A2，图片历史保留
+        assert!(prune_storyboard_images(&db).unwrap().is_empty());
+        switch_board(&db, main_id).unwrap();
+        assert_eq!(list_storyboard_images(folder.clone(), "A2".to_string(), None).unwrap().len(), 1);
+
+        // 主版本也去掉 A2 后删除 B 版：A2 已不在任何版本中，记录与文件一并清理
+        db.conn().execute("DELETE FROM storyboards WHERE mirror_id = 'A2'", []).unwrap();
+        delete_board(folder.clone(), alt.id).unwrap();
+        assert!(list_storyboard_images(folder.clone(), "A2".to_string(), None).unwrap().is_empty());
+        assert!(!images_dir.join("A2_first.png").exists());
+        assert_eq!(list_storyboard_images(folder.clone(), "A1".to_string(), None).unwrap().len(), 1);
+        assert!(images_dir.join("A1_first.png").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
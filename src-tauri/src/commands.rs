@@ -1,10 +1,34 @@
-use crate::db::{ProjectDatabase, get_config_dir, get_config_path};
+use crate::animatic::{sanitize_file_name, AnimaticFrame, AnimaticRenderer, DEFAULT_SHOT_DURATION};
+use crate::boards;
+use crate::db::{ProjectDatabase, get_config_dir, get_config_path, get_exports_dir, get_images_dir, get_references_dir, get_sources_dir, get_videos_dir};
+use crate::fdx::{parse_fdx, write_fdx};
+use crate::fonts::load_font_data;
+use crate::fountain::parse_fountain;
+use crate::pdf_export::{write_storyboard_pdf, SheetEntry};
+use crate::review::{self, ensure_unlocked, locked_mirror_ids};
+use crate::runtime_stats::compute_runtime_stats;
+use crate::script_ingest::ingest_script as ingest_script_data;
+use crate::screenplay::{chunk_scenes, Screenplay, DEFAULT_CHUNK_CHARS};
+use crate::script_pipeline::{list_chunks, plan_chunks, reset_unfinished_chunks, ScriptPipelineState};
+use crate::source_spans::{align_storyboards, extend_sources, uncovered_ranges};
+use crate::spreadsheet_import::{parse_sheets, preview_sheets, read_tables, ImportedData};
+use crate::subtitles::{build_cues, to_srt, to_vtt, CueOptions};
+use crate::timeline::{to_edl, to_fcpxml, to_otio, FrameRate, TimelineClip};
+use crate::xlsx_export::ProjectWorkbook;
+use crate::html_export::{zip_directory, ReviewPackage};
+use crate::image_api::{fetch_bytes, guess_image_mime, parse_asset_refs, parse_aspect_ratio, request_images, sniff_image_extension, validate_resolution};
+use crate::image_queue::{enqueue_jobs, image_job_from_row, ImageQueueState};
 use crate::models::*;
+use crate::async_task::TaskStatus;
+use crate::vocabulary::{self, Vocabulary};
+use crate::video_api::{submit_video_task, wait_for_video_task, VideoGenerationState, VideoRequest};
 use std::fs;
-use std::path::PathBuf;
-use std::io::Read;
+use std::path::{Path, PathBuf};
 use serde_json::json;
+use ab_glyph::FontVec;
+use base64::{engine::general_purpose, Engine as _};
 use rfd::FileDialog;
+use tauri::{AppHandle, Emitter, State};
 
 /// 获取全局配置
 #[tauri::command]
@@ -206,7 +230,9 @@ pub fn update_project_name(folder_path: String, new_name: String) -> Result<(),
         .map_err(|e| format!("重命名失败: {}", e))
 }
 
-/// 保存生成的数据
+/// 保存生成的数据，返回因已锁定而未修改内容的镜号
+///
+/// 运镜、运镜方向、机位角度与转场字段缺省时保留已有值，传入空字符串则清空；焦距缺省时保留已有值
 #[tauri::command]
 pub fn save_generated_data(
     folder_path: String,
@@ -214,10 +240,13 @@ pub fn save_generated_data(
     characters: Vec<Character>,
     scenes: Vec<Scene>,
     props: Vec<Prop>,
-) -> Result<(), String> {
+    document_id: Option<i64>,
+) -> Result<Vec<String>, String> {
     let path = PathBuf::from(&folder_path);
     let db = ProjectDatabase::open(&path)
         .map_err(|e| format!("打开数据库失败: {}", e))?;
+    // 新镜头可能沿用已删除镜头的镜号，先清理遗留的图片版本
+    prune_storyboard_images(&db, &path)?;
 
     eprintln!("=== 保存数据 ===");
     eprintln!("分镜数量: {}", storyboards.len());
@@ -225,42 +254,132 @@ pub fn save_generated_data(
     eprintln!("场景数量: {}", scenes.len());
     eprintln!("道具数量: {}", props.len());
 
-    // 保存分镜
+    // 镜号只在所属序列内沿用，写入其他序列的新镜头按序列前缀编号（如 SQ2-A1）
+    let default_sequence_id = db.default_sequence_id()
+        .map_err(|e| format!("读取默认序列失败: {}", e))?;
+    let vocabulary = Vocabulary::load(&db)?;
+    let locked = locked_mirror_ids(&db).map_err(|e| format!("查询锁定分镜失败: {}", e))?;
+    let mut storyboards = storyboards;
+    for storyboard in storyboards.iter_mut() {
+        // 已锁定的镜头不改镜号与所属序列
+        if locked.contains(&storyboard.mirror_id) {
+            continue;
+        }
+        // 景别 / 镜头类型统一为词表规范值（如 Medium shot、MS → 中景）
+        storyboard.shot_size = vocabulary.normalize("shot_size", storyboard.shot_size.take());
+        storyboard.shot_type = vocabulary.normalize("shot_type", storyboard.shot_type.take());
+        storyboard.camera_movement = vocabulary.normalize("camera_movement", storyboard.camera_movement.take());
+        storyboard.camera_angle = vocabulary.normalize("camera_angle", storyboard.camera_angle.take());
+        storyboard.transition = vocabulary.normalize("transition", storyboard.transition.take());
+
+        // 未指定序列的已有镜头原样更新，不改变所属序列
+        if storyboard.sequence_id.is_none() && load_storyboard(&db, &storyboard.mirror_id).is_ok() {
+            continue;
+        }
+        let sequence_id = storyboard.sequence_id.unwrap_or(default_sequence_id);
+        db.conn().query_row("SELECT id FROM sequences WHERE id = ?1", [sequence_id], |row| row.get::<_, i64>(0))
+            .map_err(|_| format!("序列 {} 不存在", sequence_id))?;
+        storyboard.mirror_id = db.sequence_mirror_id(&storyboard.mirror_id, sequence_id)
+            .map_err(|e| format!("分配镜号失败: {}", e))?
+            .ok_or_else(|| format!("镜号 {} 已被其他序列使用", storyboard.mirror_id))?;
+    }
+
+    // 在指定的源剧本（未指定时为最近导入的）中定位各镜头
+    let sources = match db.get_source_document(document_id).map_err(|e| format!("读取源剧本失败: {}", e))? {
+        Some(doc) => align_storyboards(&doc.text.lines().collect::<Vec<_>>(), &storyboards, doc.id),
+        None => Vec::new(),
+    };
+
+    // 保存分镜（UPSERT：保留已生成的图片与视频；未返回的运镜参数保留原值）
+    let mut skipped = Vec::new();
     for storyboard in storyboards {
+        // 已锁定的镜头只更新排序，内容保持不变
+        if locked.contains(&storyboard.mirror_id) {
+            db.conn().execute(
+                "UPDATE storyboards SET sequence_number = ?1 WHERE mirror_id = ?2",
+                rusqlite::params![storyboard.sequence_number, storyboard.mirror_id],
+            ).map_err(|e| format!("保存分镜失败: {}", e))?;
+            skipped.push(storyboard.mirror_id);
+            continue;
+        }
         db.conn().execute(
-            "INSERT OR REPLACE INTO storyboards (
+            "INSERT INTO storyboards (
                 mirror_id, sequence_number, shot_type, shot_size, duration,
                 dialogue, description, notes,
                 image_prompt_zh, image_prompt_en,
                 image_prompt_tail_zh, image_prompt_tail_en,
-                video_prompt_zh, video_prompt_en
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
-            [
-                &storyboard.mirror_id,
-                &storyboard.sequence_number.to_string(),
-                &storyboard.shot_type.unwrap_or_default(),
-                &storyboard.shot_size.unwrap_or_default(),
-                &storyboard.duration.map(|d| d.to_string()).unwrap_or_default(),
-                &storyboard.dialogue.unwrap_or_default(),
-                &storyboard.description.unwrap_or_default(),
-                &storyboard.notes.unwrap_or_default(),
-                &storyboard.image_prompt_zh.unwrap_or_default(),
-                &storyboard.image_prompt_en.unwrap_or_default(),
-                &storyboard.image_prompt_tail_zh.unwrap_or_default(),
-                &storyboard.image_prompt_tail_en.unwrap_or_default(),
-                &storyboard.video_prompt_zh.unwrap_or_default(),
-                &storyboard.video_prompt_en.unwrap_or_default(),
+                video_prompt_zh, video_prompt_en, sequence_id,
+                camera_movement, camera_direction, focal_length, camera_angle, transition
+            ) VALUES (
+                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, COALESCE(NULLIF(?15, ''), ?16),
+                NULLIF(?17, ''), NULLIF(?18, ''), ?19, NULLIF(?20, ''), NULLIF(?21, '')
+            )
+            ON CONFLICT(mirror_id) DO UPDATE SET
+                sequence_number = excluded.sequence_number,
+                sequence_id = COALESCE(NULLIF(?15, ''), storyboards.sequence_id),
+                camera_movement = CASE WHEN ?17 IS NULL THEN storyboards.camera_movement ELSE NULLIF(?17, '') END,
+                camera_direction = CASE WHEN ?18 IS NULL THEN storyboards.camera_direction ELSE NULLIF(?18, '') END,
+                focal_length = COALESCE(?19, storyboards.focal_length),
+                camera_angle = CASE WHEN ?20 IS NULL THEN storyboards.camera_angle ELSE NULLIF(?20, '') END,
+                transition = CASE WHEN ?21 IS NULL THEN storyboards.transition ELSE NULLIF(?21, '') END,
+                shot_type = excluded.shot_type,
+                shot_size = excluded.shot_size,
+                duration = excluded.duration,
+                dialogue = excluded.dialogue,
+                description = excluded.description,
+                notes = excluded.notes,
+                image_prompt_zh = excluded.image_prompt_zh,
+                image_prompt_en = excluded.image_prompt_en,
+                image_prompt_tail_zh = excluded.image_prompt_tail_zh,
+                image_prompt_tail_en = excluded.image_prompt_tail_en,
+                video_prompt_zh = excluded.video_prompt_zh,
+                video_prompt_en = excluded.video_prompt_en",
+            rusqlite::params![
+                storyboard.mirror_id,
+                storyboard.sequence_number.to_string(),
+                storyboard.shot_type.unwrap_or_default(),
+                storyboard.shot_size.unwrap_or_default(),
+                storyboard.duration.map(|d| d.to_string()).unwrap_or_default(),
+                storyboard.dialogue.unwrap_or_default(),
+                storyboard.description.unwrap_or_default(),
+                storyboard.notes.unwrap_or_default(),
+                storyboard.image_prompt_zh.unwrap_or_default(),
+                storyboard.image_prompt_en.unwrap_or_default(),
+                storyboard.image_prompt_tail_zh.unwrap_or_default(),
+                storyboard.image_prompt_tail_en.unwrap_or_default(),
+                storyboard.video_prompt_zh.unwrap_or_default(),
+                storyboard.video_prompt_en.unwrap_or_default(),
+                storyboard.sequence_id.map(|id| id.to_string()).unwrap_or_default(),
+                default_sequence_id.to_string(),
+                storyboard.camera_movement,
+                storyboard.camera_direction,
+                storyboard.focal_length,
+                storyboard.camera_angle,
+                storyboard.transition,
             ],
         ).map_err(|e| format!("保存分镜失败: {}", e))?;
     }
 
-    // 保存角色
+    if !skipped.is_empty() {
+        eprintln!("已锁定、未修改的分镜: {}", skipped.join(", "));
+    }
+    for source in sources.iter().filter(|s| !locked.contains(&s.mirror_id)) {
+        db.set_storyboard_source(source)
+            .map_err(|e| format!("保存分镜源位置失败: {}", e))?;
+    }
+
+    // 保存角色（UPSERT：保留已设置的参考图，空字段不覆盖已有内容）
     eprintln!("开始保存 {} 个角色...", characters.len());
     for character in characters {
         eprintln!("  保存角色: {}", character.name);
         db.conn().execute(
-            "INSERT OR REPLACE INTO characters (name, description, image_prompt_zh, image_prompt_en, notes)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO characters (name, description, image_prompt_zh, image_prompt_en, notes)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(name) DO UPDATE SET
+                description = COALESCE(NULLIF(excluded.description, ''), description),
+                image_prompt_zh = COALESCE(NULLIF(excluded.image_prompt_zh, ''), image_prompt_zh),
+                image_prompt_en = COALESCE(NULLIF(excluded.image_prompt_en, ''), image_prompt_en),
+                notes = COALESCE(NULLIF(excluded.notes, ''), notes)",
             [
                 &character.name,
                 &character.description.unwrap_or_default(),
@@ -276,8 +395,13 @@ pub fn save_generated_data(
     for scene in scenes {
         eprintln!("  保存场景: {}", scene.name);
         db.conn().execute(
-            "INSERT OR REPLACE INTO scenes (name, description, image_prompt_zh, image_prompt_en, notes)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO scenes (name, description, image_prompt_zh, image_prompt_en, notes)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(name) DO UPDATE SET
+                description = COALESCE(NULLIF(excluded.description, ''), description),
+                image_prompt_zh = COALESCE(NULLIF(excluded.image_prompt_zh, ''), image_prompt_zh),
+                image_prompt_en = COALESCE(NULLIF(excluded.image_prompt_en, ''), image_prompt_en),
+                notes = COALESCE(NULLIF(excluded.notes, ''), notes)",
             [
                 &scene.name,
                 &scene.description.unwrap_or_default(),
@@ -293,8 +417,13 @@ pub fn save_generated_data(
     for prop in props {
         eprintln!("  保存道具: {}", prop.name);
         db.conn().execute(
-            "INSERT OR REPLACE INTO props (name, description, image_prompt_zh, image_prompt_en, notes)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO props (name, description, image_prompt_zh, image_prompt_en, notes)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(name) DO UPDATE SET
+                description = COALESCE(NULLIF(excluded.description, ''), description),
+                image_prompt_zh = COALESCE(NULLIF(excluded.image_prompt_zh, ''), image_prompt_zh),
+                image_prompt_en = COALESCE(NULLIF(excluded.image_prompt_en, ''), image_prompt_en),
+                notes = COALESCE(NULLIF(excluded.notes, ''), notes)",
             [
                 &prop.name,
                 &prop.description.unwrap_or_default(),
@@ -305,51 +434,483 @@ pub fn save_generated_data(
         ).map_err(|e| format!("保存道具失败: {}", e))?;
     }
 
-    Ok(())
+    Ok(skipped)
 }
 
-/// 获取分镜列表
+/// 获取分镜列表，scope 可限定剧集、序列、景别、镜头类型或审核状态
 #[tauri::command]
-pub fn get_storyboards(folder_path: String) -> Result<Vec<Storyboard>, String> {
+pub fn get_storyboards(folder_path: String, scope: Option<StoryboardScope>) -> Result<Vec<Storyboard>, String> {
     let path = PathBuf::from(&folder_path);
     let db = ProjectDatabase::open(&path)
         .map_err(|e| format!("打开数据库失败: {}", e))?;
 
+    let scope = scope.unwrap_or_default();
+    let vocabulary = Vocabulary::load(&db)?;
+    let shot_size = vocabulary.normalize("shot_size", scope.shot_size).filter(|s| !s.is_empty());
+    let shot_type = vocabulary.normalize("shot_type", scope.shot_type).filter(|s| !s.is_empty());
     let mut stmt = db.conn().prepare(
-        "SELECT sequence_number, mirror_id, shot_type, shot_size, duration,
+        &format!(
+            "SELECT {} FROM storyboards
+             WHERE (?1 IS NULL OR sequence_id IN (SELECT id FROM sequences WHERE episode_id = ?1))
+               AND (?2 IS NULL OR sequence_id = ?2)
+               AND (?3 IS NULL OR shot_size = ?3)
+               AND (?4 IS NULL OR shot_type = ?4)
+               AND (?5 IS NULL OR review_status = ?5)
+             ORDER BY sequence_number",
+            STORYBOARD_COLUMNS
+        )
+    ).map_err(|e| format!("查询分镜失败: {}", e))?;
+
+    let storyboards = stmt.query_map(
+        rusqlite::params![scope.episode_id, scope.sequence_id, shot_size, shot_type, scope.review_status],
+        storyboard_from_row,
+    )
+        .map_err(|e| format!("解析分镜失败: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("收集分镜失败: {}", e))?;
+
+    Ok(storyboards)
+}
+
+/// 分镜查询列（顺序需与 storyboard_from_row 一致）
+const STORYBOARD_COLUMNS: &str = "sequence_number, mirror_id, shot_type, shot_size, duration,
                 dialogue, description, notes,
                 image_prompt_zh, image_prompt_en,
                 image_prompt_tail_zh, image_prompt_tail_en,
                 video_prompt_zh, video_prompt_en,
-                image_first_path, image_last_path, image_status
-         FROM storyboards ORDER BY sequence_number"
-    ).map_err(|e| format!("查询分镜失败: {}", e))?;
+                image_first_path, image_last_path, image_status,
+                video_path, video_status, sequence_id,
+                camera_movement, camera_direction, focal_length, camera_angle, transition,
+                review_status, reviewed_by, reviewed_at";
+
+/// 将查询行映射为分镜条目
+fn storyboard_from_row(row: &rusqlite::Row) -> rusqlite::Result<Storyboard> {
+    Ok(Storyboard {
+        sequence_number: row.get(0)?,
+        mirror_id: row.get(1)?,
+        shot_type: row.get(2)?,
+        shot_size: row.get(3)?,
+        duration: row.get::<_, Option<f64>>(4).ok().flatten(),
+        dialogue: row.get(5)?,
+        description: row.get(6)?,
+        notes: row.get(7)?,
+        image_prompt_zh: row.get(8)?,
+        image_prompt_en: row.get(9)?,
+        image_prompt_tail_zh: row.get(10)?,
+        image_prompt_tail_en: row.get(11)?,
+        video_prompt_zh: row.get(12)?,
+        video_prompt_en: row.get(13)?,
+        image_first_path: row.get(14)?,
+        image_last_path: row.get(15)?,
+        image_status: row.get(16)?,
+        video_path: row.get(17)?,
+        video_status: row.get(18)?,
+        source_text: None,
+        sequence_id: row.get(19)?,
+        camera_movement: row.get(20)?,
+        camera_direction: row.get(21)?,
+        focal_length: row.get(22)?,
+        camera_angle: row.get(23)?,
+        transition: row.get(24)?,
+        review_status: row.get(25)?,
+        reviewed_by: row.get(26)?,
+        reviewed_at: row.get(27)?,
+    })
+}
 
-    let storyboards = stmt.query_map([], |row| {
-        Ok(Storyboard {
-            sequence_number: row.get(0)?,
-            mirror_id: row.get(1)?,
-            shot_type: Some(row.get(2)?),
-            shot_size: Some(row.get(3)?),
-            duration: Some(row.get(4)?),
-            dialogue: Some(row.get(5)?),
-            description: Some(row.get(6)?),
-            notes: Some(row.get(7)?),
-            image_prompt_zh: Some(row.get(8)?),
-            image_prompt_en: Some(row.get(9)?),
-            image_prompt_tail_zh: Some(row.get(10)?),
-            image_prompt_tail_en: Some(row.get(11)?),
-            video_prompt_zh: Some(row.get(12)?),
-            video_prompt_en: Some(row.get(13)?),
-            image_first_path: row.get::<_, Option<String>>(14)?,
-            image_last_path: row.get::<_, Option<String>>(15)?,
-            image_status: row.get::<_, Option<String>>(16)?,
+/// 按镜号读取单个分镜
+fn load_storyboard(db: &ProjectDatabase, mirror_id: &str) -> Result<Storyboard, String> {
+    db.conn().query_row(
+        &format!("SELECT {} FROM storyboards WHERE mirror_id = ?1", STORYBOARD_COLUMNS),
+        [mirror_id],
+        storyboard_from_row,
+    ).map_err(|e| format!("分镜 {} 不存在: {}", mirror_id, e))
+}
+
+/// 获取剧集列表（含各序列及其分镜数）
+#[tauri::command]
+pub fn get_episodes(folder_path: String) -> Result<Vec<Episode>, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    let mut stmt = db.conn().prepare(
+        "SELECT id, name, sort_order FROM episodes ORDER BY sort_order, id"
+    ).map_err(|e| format!("查询剧集失败: {}", e))?;
+    let mut episodes = stmt.query_map([], |row| {
+        Ok(Episode {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            sort_order: row.get(2)?,
+            sequences: Vec::new(),
         })
-    }).map_err(|e| format!("解析分镜失败: {}", e))?
+    }).map_err(|e| format!("解析剧集失败: {}", e))?
         .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("收集分镜失败: {}", e))?;
+        .map_err(|e| format!("收集剧集失败: {}", e))?;
 
-    Ok(storyboards)
+    let mut stmt = db.conn().prepare(
+        "SELECT s.id, s.episode_id, s.name, s.prefix, s.sort_order,
+                (SELECT COUNT(*) FROM storyboards WHERE sequence_id = s.id)
+         FROM sequences s ORDER BY s.sort_order, s.id"
+    ).map_err(|e| format!("查询序列失败: {}", e))?;
+    let sequences = stmt.query_map([], |row| {
+        Ok(Sequence {
+            id: row.get(0)?,
+            episode_id: row.get(1)?,
+            name: row.get(2)?,
+            prefix: row.get(3)?,
+            sort_order: row.get(4)?,
+            storyboard_count: row.get(5)?,
+        })
+    }).map_err(|e| format!("解析序列失败: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("收集序列失败: {}", e))?;
+
+    for sequence in sequences {
+        if let Some(episode) = episodes.iter_mut().find(|e| e.id == sequence.episode_id) {
+            episode.sequences.push(sequence);
+        }
+    }
+    Ok(episodes)
+}
+
+/// 新建或重命名剧集，返回剧集 ID
+#[tauri::command]
+pub fn save_episode(folder_path: String, id: Option<i64>, name: String) -> Result<i64, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("剧集名称不能为空".to_string());
+    }
+    match id {
+        Some(id) => {
+            let updated = db.conn().execute("UPDATE episodes SET name = ?1 WHERE id = ?2", rusqlite::params![name, id])
+                .map_err(|e| format!("保存剧集失败: {}", e))?;
+            if updated == 0 {
+                return Err(format!("剧集 {} 不存在", id));
+            }
+            Ok(id)
+        }
+        None => {
+            db.conn().execute(
+                "INSERT INTO episodes (name, sort_order) VALUES (?1, (SELECT COALESCE(MAX(sort_order), 0) + 1 FROM episodes))",
+                [name],
+            ).map_err(|e| format!("保存剧集失败: {}", e))?;
+            let episode_id = db.conn().last_insert_rowid();
+            // 新剧集自带一个序列，便于直接分配镜头
+            db.conn().execute(
+                "INSERT INTO sequences (episode_id, name, prefix, sort_order) VALUES (?1, '默认序列', '', 0)",
+                [episode_id],
+            ).map_err(|e| format!("创建序列失败: {}", e))?;
+            db.fill_sequence_prefixes().map_err(|e| format!("创建序列失败: {}", e))?;
+            Ok(episode_id)
+        }
+    }
+}
+
+/// 删除剧集（剧集中还有分镜时拒绝删除）
+#[tauri::command]
+pub fn delete_episode(folder_path: String, id: i64) -> Result<(), String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    let count: i64 = db.conn().query_row(
+        "SELECT COUNT(*) FROM storyboards WHERE sequence_id IN (SELECT id FROM sequences WHERE episode_id = ?1)",
+        [id],
+        |row| row.get(0),
+    ).map_err(|e| format!("查询分镜失败: {}", e))?;
+    if count > 0 {
+        return Err(format!("剧集中还有 {} 个分镜，请先移到其他序列", count));
+    }
+    db.conn().execute("DELETE FROM sequences WHERE episode_id = ?1", [id])
+        .map_err(|e| format!("删除序列失败: {}", e))?;
+    db.conn().execute("DELETE FROM episodes WHERE id = ?1", [id])
+        .map_err(|e| format!("删除剧集失败: {}", e))?;
+    // 保证项目至少还有一个默认序列
+    db.default_sequence_id().map_err(|e| format!("创建默认序列失败: {}", e))?;
+    Ok(())
+}
+
+/// 新建或修改序列，返回序列 ID；前缀在项目内唯一，只影响之后生成的镜号，已有镜号不变
+#[tauri::command]
+pub fn save_sequence(
+    folder_path: String,
+    id: Option<i64>,
+    episode_id: i64,
+    name: String,
+    prefix: Option<String>,
+) -> Result<i64, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("序列名称不能为空".to_string());
+    }
+    let prefix = prefix.unwrap_or_default().trim().trim_end_matches('-').to_string();
+    if prefix.chars().any(|c| !c.is_ascii_alphanumeric() && c != '_') {
+        return Err("序列前缀只能包含字母、数字和下划线".to_string());
+    }
+    db.conn().query_row("SELECT id FROM episodes WHERE id = ?1", [episode_id], |row| row.get::<_, i64>(0))
+        .map_err(|_| format!("剧集 {} 不存在", episode_id))?;
+    if db.sequence_prefix_taken(&prefix, id).map_err(|e| format!("查询序列失败: {}", e))? {
+        return Err(format!("序列前缀 {} 已被其他序列使用", prefix));
+    }
+
+    // 项目有多个序列时每个序列都需要前缀，新建序列未填写时自动分配
+    match id {
+        Some(id) => {
+            let others: i64 = db.conn().query_row(
+                "SELECT COUNT(*) FROM sequences WHERE id != ?1", [id], |row| row.get(0),
+            ).map_err(|e| format!("查询序列失败: {}", e))?;
+            if prefix.is_empty() && others > 0 {
+                return Err("项目有多个序列时序列前缀不能为空".to_string());
+            }
+            let updated = db.conn().execute(
+                "UPDATE sequences SET episode_id = ?1, name = ?2, prefix = ?3 WHERE id = ?4",
+                rusqlite::params![episode_id, name, prefix, id],
+            ).map_err(|e| format!("保存序列失败: {}", e))?;
+            if updated == 0 {
+                return Err(format!("序列 {} 不存在", id));
+            }
+            Ok(id)
+        }
+        None => {
+            db.conn().execute(
+                "INSERT INTO sequences (episode_id, name, prefix, sort_order)
+                 VALUES (?1, ?2, ?3, (SELECT COALESCE(MAX(sort_order), 0) + 1 FROM sequences WHERE episode_id = ?1))",
+                rusqlite::params![episode_id, name, prefix],
+            ).map_err(|e| format!("保存序列失败: {}", e))?;
+            let sequence_id = db.conn().last_insert_rowid();
+            db.fill_sequence_prefixes().map_err(|e| format!("保存序列失败: {}", e))?;
+            Ok(sequence_id)
+        }
+    }
+}
+
+/// 删除序列（序列中还有分镜时拒绝删除）
+#[tauri::command]
+pub fn delete_sequence(folder_path: String, id: i64) -> Result<(), String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    let count: i64 = db.conn().query_row(
+        "SELECT COUNT(*) FROM storyboards WHERE sequence_id = ?1",
+        [id],
+        |row| row.get(0),
+    ).map_err(|e| format!("查询分镜失败: {}", e))?;
+    if count > 0 {
+        return Err(format!("序列中还有 {} 个分镜，请先移到其他序列", count));
+    }
+    db.conn().execute("DELETE FROM sequences WHERE id = ?1", [id])
+        .map_err(|e| format!("删除序列失败: {}", e))?;
+    db.default_sequence_id().map_err(|e| format!("创建默认序列失败: {}", e))?;
+    Ok(())
+}
+
+/// 将分镜移入指定序列，返回实际移动的数量（镜号保持不变）
+#[tauri::command]
+pub fn assign_storyboards_to_sequence(
+    folder_path: String,
+    sequence_id: i64,
+    mirror_ids: Vec<String>,
+) -> Result<usize, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    db.conn().query_row("SELECT id FROM sequences WHERE id = ?1", [sequence_id], |row| row.get::<_, i64>(0))
+        .map_err(|_| format!("序列 {} 不存在", sequence_id))?;
+
+    let mut moved = 0;
+    for mirror_id in &mirror_ids {
+        moved += db.conn().execute(
+            "UPDATE storyboards SET sequence_id = ?1 WHERE mirror_id = ?2",
+            rusqlite::params![sequence_id, mirror_id],
+        ).map_err(|e| format!("移动分镜失败: {}", e))?;
+    }
+    Ok(moved)
+}
+
+/// 批量设置分镜审核状态（draft / in_review / changes_requested / approved / locked），返回更新数量
+#[tauri::command]
+pub fn set_review_status(
+    folder_path: String,
+    mirror_ids: Vec<String>,
+    status: String,
+    reviewer: Option<String>,
+    comment: Option<String>,
+) -> Result<usize, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    review::set_review_status(&db, &mirror_ids, &status, reviewer.as_deref(), comment.as_deref())
+}
+
+/// 获取分镜的审核历史
+#[tauri::command]
+pub fn get_review_history(folder_path: String, mirror_id: String) -> Result<Vec<StoryboardReview>, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    review::review_history(&db, &mirror_id)
+}
+
+/// 统计分镜时长：总时长、各序列时长、景别分布、平均镜头时长与对白时长估算
+#[tauri::command]
+pub fn get_runtime_stats(
+    folder_path: String,
+    scope: Option<StoryboardScope>,
+    options: Option<RuntimeOptions>,
+) -> Result<RuntimeStats, String> {
+    let storyboards = get_storyboards(folder_path.clone(), scope)?;
+    let sequences: Vec<(i64, String)> = get_episodes(folder_path.clone())?
+        .into_iter()
+        .flat_map(|episode| {
+            episode.sequences.into_iter()
+                .map(move |sequence| (sequence.id, format!("{} / {}", episode.name, sequence.name)))
+        })
+        .collect();
+
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    let size_order: Vec<String> = vocabulary::list_terms(&db, Some("shot_size"))?
+        .into_iter()
+        .map(|term| term.value)
+        .collect();
+
+    Ok(compute_runtime_stats(&storyboards, &sequences, &size_order, &options.unwrap_or_default()))
+}
+
+/// 获取受控词表，category 为 shot_size / shot_type / camera_movement / camera_angle / transition，为空时返回全部
+#[tauri::command]
+pub fn get_vocabulary(folder_path: String, category: Option<String>) -> Result<Vec<VocabularyTerm>, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    vocabulary::list_terms(&db, category.as_deref())
+}
+
+/// 新增或修改项目词条，返回词条 ID
+#[tauri::command]
+pub fn save_vocabulary_term(folder_path: String, term: VocabularyTerm) -> Result<i64, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    vocabulary::save_term(&db, &term)
+}
+
+/// 删除项目词条
+#[tauri::command]
+pub fn delete_vocabulary_term(folder_path: String, id: i64) -> Result<(), String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    vocabulary::delete_term(&db, id)
+}
+
+/// 按当前词表规范化已有分镜的景别、镜头类型、运镜、角度与转场，返回修改的分镜数
+#[tauri::command]
+pub fn normalize_storyboard_terms(folder_path: String) -> Result<usize, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    vocabulary::normalize_storyboards(&db)
+}
+
+/// 获取项目中的分镜版本列表
+#[tauri::command]
+pub fn list_boards(folder_path: String) -> Result<Vec<Board>, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    boards::list_boards(&db)
+}
+
+/// 新建空白分镜版本（共享角色/场景/道具）
+#[tauri::command]
+pub fn create_board(folder_path: String, name: String) -> Result<Board, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    boards::create_board(&db, &name, None)
+}
+
+/// 复制已有版本为新版本
+#[tauri::command]
+pub fn duplicate_board(folder_path: String, board_id: i64, name: String) -> Result<Board, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    boards::create_board(&db, &name, Some(board_id))
+}
+
+/// 切换当前编辑的分镜版本；分块生成或视频生成进行中时拒绝切换
+#[tauri::command]
+pub fn switch_board(
+    pipeline: State<'_, ScriptPipelineState>,
+    videos: State<'_, VideoGenerationState>,
+    folder_path: String,
+    board_id: i64,
+) -> Result<Board, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    if board_id != boards::active_board_id(&db)? {
+        if pipeline.is_running(&folder_path) {
+            return Err("剧本正在分块生成，请等待完成或停止后再切换版本".to_string());
+        }
+        if videos.is_running(&folder_path) {
+            return Err("还有正在生成的视频，请等待完成后再切换版本".to_string());
+        }
+    }
+    let board = boards::switch_board(&db, board_id)?;
+    prune_storyboard_images(&db, &path)?;
+    Ok(board)
+}
+
+/// 重命名分镜版本
+#[tauri::command]
+pub fn rename_board(folder_path: String, board_id: i64, name: String) -> Result<(), String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    boards::rename_board(&db, board_id, &name)
+}
+
+/// 删除分镜版本（不能删除当前版本），只属于该版本的镜头的图片版本一并清理
+#[tauri::command]
+pub fn delete_board(folder_path: String, board_id: i64) -> Result<(), String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    boards::delete_board(&db, board_id)?;
+    prune_storyboard_images(&db, &path)
+}
+
+/// 清理已不在任何版本中的镜头的图片版本，并删除不再被引用的图片文件
+fn prune_storyboard_images(db: &ProjectDatabase, project_path: &Path) -> Result<(), String> {
+    let images_dir = get_images_dir(project_path);
+    for file in boards::prune_storyboard_images(db)? {
+        let _ = fs::remove_file(images_dir.join(file));
+    }
+    Ok(())
+}
+
+/// 对比两个分镜版本
+#[tauri::command]
+pub fn compare_boards(folder_path: String, board_a: i64, board_b: i64) -> Result<BoardComparison, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    boards::compare_boards(&db, board_a, board_b)
 }
 
 /// 获取角色列表
@@ -360,7 +921,7 @@ pub fn get_characters(folder_path: String) -> Result<Vec<Character>, String> {
         .map_err(|e| format!("打开数据库失败: {}", e))?;
 
     let mut stmt = db.conn().prepare(
-        "SELECT name, description, image_prompt_zh, image_prompt_en, notes FROM characters"
+        "SELECT name, description, image_prompt_zh, image_prompt_en, notes, reference_image_path FROM characters"
     ).map_err(|e| format!("查询角色失败: {}", e))?;
 
     let characters = stmt.query_map([], |row| {
@@ -370,6 +931,7 @@ pub fn get_characters(folder_path: String) -> Result<Vec<Character>, String> {
             image_prompt_zh: Some(row.get(2)?),
             image_prompt_en: Some(row.get(3)?),
             notes: Some(row.get(4)?),
+            reference_image_path: row.get::<_, Option<String>>(5)?,
         })
     }).map_err(|e| format!("解析角色失败: {}", e))?
         .collect::<Result<Vec<_>, _>>()
@@ -387,7 +949,7 @@ pub fn get_scenes(folder_path: String) -> Result<Vec<Scene>, String> {
         .map_err(|e| format!("打开数据库失败: {}", e))?;
 
     let mut stmt = db.conn().prepare(
-        "SELECT name, description, image_prompt_zh, image_prompt_en, notes FROM scenes"
+        "SELECT name, description, image_prompt_zh, image_prompt_en, notes, reference_image_path FROM scenes"
     ).map_err(|e| format!("查询场景失败: {}", e))?;
 
     let scenes = stmt.query_map([], |row| {
@@ -397,6 +959,7 @@ pub fn get_scenes(folder_path: String) -> Result<Vec<Scene>, String> {
             image_prompt_zh: Some(row.get(2)?),
             image_prompt_en: Some(row.get(3)?),
             notes: Some(row.get(4)?),
+            reference_image_path: row.get::<_, Option<String>>(5)?,
         })
     }).map_err(|e| format!("解析场景失败: {}", e))?
         .collect::<Result<Vec<_>, _>>()
@@ -414,7 +977,7 @@ pub fn get_props(folder_path: String) -> Result<Vec<Prop>, String> {
         .map_err(|e| format!("打开数据库失败: {}", e))?;
 
     let mut stmt = db.conn().prepare(
-        "SELECT name, description, image_prompt_zh, image_prompt_en, notes FROM props"
+        "SELECT name, description, image_prompt_zh, image_prompt_en, notes, reference_image_path FROM props"
     ).map_err(|e| format!("查询道具失败: {}", e))?;
 
     let props = stmt.query_map([], |row| {
@@ -424,6 +987,7 @@ pub fn get_props(folder_path: String) -> Result<Vec<Prop>, String> {
             image_prompt_zh: Some(row.get(2)?),
             image_prompt_en: Some(row.get(3)?),
             notes: Some(row.get(4)?),
+            reference_image_path: row.get::<_, Option<String>>(5)?,
         })
     }).map_err(|e| format!("解析道具失败: {}", e))?
         .collect::<Result<Vec<_>, _>>()
@@ -552,6 +1116,17 @@ pub fn call_ai_api_with_custom_system(
 - 第3层（动作分镜层）：你只需填写！格式：景别 + 动作 + 神态 + 位置关系
   示例："Close-up shot, character tilting head slightly, curious expression"
 
+【景别与镜头类型】
+- shot_size 使用：大远景、远景、全景、中全景、中景、中近景、近景、特写、大特写
+- shot_type 使用：单人、双人、群像、过肩、反打、主观、空镜、插入、航拍（只写拍摄对象与构图，不写运镜；推拉摇移等运镜写在 camera_movement）
+
+【运镜与镜头参数】（可选，不确定时留空）
+- camera_movement 运镜：静止、横摇、俯仰、推拉、横移、升降、手持、变焦、跟拍、环绕
+- camera_direction 运镜方向：如 "向左"、"推进"、"拉远"、"上升"
+- focal_length 焦距（毫米，数字），如 24、35、50、85
+- camera_angle 角度：平视、俯拍、仰拍、顶拍、虫视、倾斜
+- transition 到下一镜的转场：切、叠化、淡入、淡出、划像、匹配剪辑、跳切
+
 【重要】
 - 不要在动作分镜层写风格描述（如"皮克斯风格"）或画质关键词（如"8k"）
 - 只描述这个镜头具体发生什么动作、什么神态、什么位置关系
@@ -564,12 +1139,18 @@ pub fn call_ai_api_with_custom_system(
   "storyboards": [
     {
       "mirror_id": "A1",
-      "shot_type": "固定",
+      "shot_type": "单人",
       "shot_size": "中景",
       "duration": 3,
       "dialogue": "对白内容",
       "description": "画面描述",
       "notes": "备注",
+      "camera_movement": "推拉",
+      "camera_direction": "推进",
+      "focal_length": 35,
+      "camera_angle": "平视",
+      "transition": "切",
+      "source_text": "该镜对应的剧本原文（原样摘录一句）",
       "image_prompt_zh": "生图提示词（中文）",
       "image_prompt_en": "生图提示词（英文）",
       "image_prompt_tail_zh": "尾帧提示词（中文）",
@@ -673,101 +1254,1031 @@ pub fn call_ai_api_with_custom_system(
 }
 
 /// 调用图片生成 API
+/// settings 为空时沿用 1024x1024、单张的默认参数；返回第一张图片（URL 或 data URL）
 #[tauri::command]
-pub fn call_image_api(api_config: ApiConfig, prompt: String) -> Result<String, String> {
-    let base_url = api_config.base_url.trim_end_matches('/');
-    let api_key = &api_config.api_key;
-
-    // 根据不同的 API 类型构建不同的请求
-    let url = if base_url.contains("openai") || base_url.contains("chatgpt") {
-        format!("{}/v1/images/generations", base_url)
-    } else {
-        // 默认使用 OpenAI 兼容格式
-        format!("{}/v1/images/generations", base_url)
-    };
-
-    let request_body = json!({
-        "model": api_config.model.as_deref().unwrap_or("dall-e-3"),
-        "prompt": prompt,
-        "n": 1,
-        "size": "1024x1024"
-    });
-
-    let agent = ureq::AgentBuilder::new()
-        .timeout_read(std::time::Duration::from_secs(120))
-        .timeout_write(std::time::Duration::from_secs(10))
-        .build();
-
-    let response = agent.post(&url)
-        .set("Authorization", &format!("Bearer {}", api_key))
-        .set("Content-Type", "application/json")
-        .send_string(
-            &serde_json::to_string(&request_body).map_err(|e| e.to_string())?
-        )
-        .map_err(|e| format!("图片生成请求失败: {}", e))?;
-
-    let response_text = response.into_string()
-        .map_err(|e| format!("读取图片响应失败: {}", e))?;
-
-    let response_json: serde_json::Value = serde_json::from_str(&response_text)
-        .map_err(|e| format!("解析图片响应失败: {}", e))?;
-
-    let image_url = response_json["data"]
-        .get(0)
-        .and_then(|d| d["url"].as_str())
-        .or_else(|| response_json["url"].as_str())
-        .ok_or_else(|| "无法从响应中提取图片 URL".to_string())?;
-
-    Ok(image_url.to_string())
+pub fn call_image_api(
+    api_config: ApiConfig,
+    prompt: String,
+    settings: Option<ImageSettings>,
+) -> Result<String, String> {
+    let settings = ImageSettings { n: Some(1), ..settings.unwrap_or_default() };
+    request_images(&api_config, &prompt, &settings, &[])?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "无法从响应中提取图片 URL".to_string())
 }
 
-/// 下载图片
+/// 获取项目生图参数
 #[tauri::command]
-pub fn download_image(url: String, save_path: String) -> Result<(), String> {
-    let agent = ureq::AgentBuilder::new()
-        .timeout_read(std::time::Duration::from_secs(120))
-        .build();
-
-    let response = agent.get(&url)
-        .call()
-        .map_err(|e| format!("下载图片失败: {}", e))?;
+pub fn get_image_settings(folder_path: String) -> Result<ImageSettings, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
 
-    let mut data = Vec::new();
-    response.into_reader().read_to_end(&mut data)
-        .map_err(|e| format!("读取图片数据失败: {}", e))?;
+    Ok(db.get_image_settings())
+}
 
-    fs::write(&save_path, data)
-        .map_err(|e| format!("保存图片失败: {}", e))?;
+/// 保存项目生图参数
+#[tauri::command]
+pub fn save_image_settings(folder_path: String, settings: ImageSettings) -> Result<(), String> {
+    validate_resolution(settings.resolution.as_deref())?;
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
 
-    Ok(())
+    db.save_image_settings(&settings)
+        .map_err(|e| format!("保存生图参数失败: {}", e))
 }
 
-/// 更新分镜图片路径
+/// 设置资产参考图
+/// 将选中的图片复制到 .storyboard/assets/references/ 下并记录文件名；source_path 为空时清除参考图
 #[tauri::command]
-pub fn update_storyboard_image(
+pub fn set_asset_reference_image(
     folder_path: String,
-    mirror_id: String,
-    image_type: String,
-    image_path: String,
-) -> Result<(), String> {
+    asset_type: String,
+    name: String,
+    source_path: Option<String>,
+) -> Result<Option<String>, String> {
     let path = PathBuf::from(&folder_path);
     let db = ProjectDatabase::open(&path)
         .map_err(|e| format!("打开数据库失败: {}", e))?;
 
-    let column = match image_type.as_str() {
-        "first" => "image_first_path",
-        "last" => "image_last_path",
-        _ => return Err("无效的图片类型".to_string()),
+    let table = match asset_type.as_str() {
+        "character" => "characters",
+        "scene" => "scenes",
+        "prop" => "props",
+        _ => return Err("无效的资产类型".to_string()),
     };
 
-    let sql = format!("UPDATE storyboards SET {} = ?1, image_status = 'generated' WHERE mirror_id = ?2", column);
-
-    db.conn().execute(&sql, [&image_path, &mirror_id])
-        .map_err(|e| format!("更新分镜图片失败: {}", e))?;
+    let previous: Option<String> = db.conn().query_row(
+        &format!("SELECT reference_image_path FROM {} WHERE name = ?1", table),
+        [&name],
+        |row| row.get(0),
+    ).map_err(|_| format!("资产 {} 不存在", name))?;
+
+    let references_dir = get_references_dir(&path);
+    let file_name = match source_path {
+        Some(source) => {
+            let source = PathBuf::from(&source);
+            let ext = source.extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("png")
+                .to_ascii_lowercase();
+            let file_name = format!("{}_{}.{}", asset_type, sanitize_file_name(&name), ext);
+
+            fs::create_dir_all(&references_dir)
+                .map_err(|e| format!("创建参考图目录失败: {}", e))?;
+            let target = references_dir.join(&file_name);
+            if source != target {
+                fs::copy(&source, &target)
+                    .map_err(|e| format!("复制参考图失败: {}", e))?;
+            }
+            Some(file_name)
+        }
+        None => None,
+    };
+
+    db.conn().execute(
+        &format!("UPDATE {} SET reference_image_path = ?1 WHERE name = ?2", table),
+        rusqlite::params![file_name, name],
+    ).map_err(|e| format!("更新参考图失败: {}", e))?;
+
+    // 替换或清除后删除旧参考图
+    if let Some(previous) = previous.filter(|p| !p.is_empty() && Some(p) != file_name.as_ref()) {
+        let _ = fs::remove_file(references_dir.join(previous));
+    }
+
+    Ok(file_name)
+}
+
+/// 调用图生图 / 参考图生成 API
+/// 根据分镜提示词中的 #资产名 收集已设置的参考图，可选附带上一镜的尾帧，
+/// 以 multipart 调用 /v1/images/edits（或按 reference_mode = "json" 以 base64 数组提交）。
+/// 没有任何参考图时退回纯文本生成。
+#[tauri::command]
+pub fn call_image_api_with_references(
+    folder_path: String,
+    api_config: ApiConfig,
+    prompt: String,
+    mirror_id: String,
+    frame: String,
+    include_previous_frame: Option<bool>,
+) -> Result<String, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    let references = load_reference_images(&db, &path, &mirror_id, &frame, include_previous_frame.unwrap_or(false))?;
+    let settings = ImageSettings { n: Some(1), ..db.get_image_settings() };
+
+    request_images(&api_config, &prompt, &settings, &references)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "无法从响应中提取图片 URL".to_string())
+}
+
+/// 读取分镜关联的参考图内容
+fn load_reference_images(
+    db: &ProjectDatabase,
+    project_path: &Path,
+    mirror_id: &str,
+    frame: &str,
+    include_previous_frame: bool,
+) -> Result<Vec<(PathBuf, Vec<u8>)>, String> {
+    collect_reference_images(db, project_path, mirror_id, frame, include_previous_frame)?
+        .into_iter()
+        .map(|file| {
+            fs::read(&file)
+                .map(|data| (file.clone(), data))
+                .map_err(|e| format!("读取参考图失败 {}: {}", file.display(), e))
+        })
+        .collect()
+}
+
+/// 收集分镜关联的参考图文件
+/// 顺序：角色 → 场景 → 道具 → 上一镜尾帧
+fn collect_reference_images(
+    db: &ProjectDatabase,
+    project_path: &Path,
+    mirror_id: &str,
+    frame: &str,
+    include_previous_frame: bool,
+) -> Result<Vec<PathBuf>, String> {
+    let storyboard = load_storyboard(db, mirror_id)?;
+
+    let prompt_text = match frame {
+        "first" => [&storyboard.image_prompt_zh, &storyboard.image_prompt_en],
+        "last" => [&storyboard.image_prompt_tail_zh, &storyboard.image_prompt_tail_en],
+        _ => return Err("无效的图片类型".to_string()),
+    }
+        .iter()
+        .filter_map(|p| p.as_deref())
+        .chain(storyboard.description.as_deref())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let asset_names = parse_asset_refs(&prompt_text);
+
+    let references_dir = get_references_dir(project_path);
+    let mut files = Vec::new();
+
+    for table in ["characters", "scenes", "props"] {
+        for name in &asset_names {
+            let reference: Option<String> = db.conn().query_row(
+                &format!("SELECT reference_image_path FROM {} WHERE name = ?1", table),
+                [name],
+                |row| row.get(0),
+            ).ok().flatten();
+            if let Some(file_name) = reference.filter(|f| !f.is_empty()) {
+                let file = references_dir.join(file_name);
+                if file.exists() && !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+    }
+
+    if include_previous_frame {
+        let previous_last: Option<String> = db.conn().query_row(
+            "SELECT image_last_path FROM storyboards
+             WHERE sequence_number < ?1
+             ORDER BY sequence_number DESC LIMIT 1",
+            [storyboard.sequence_number],
+            |row| row.get(0),
+        ).ok().flatten();
+        if let Some(file_name) = previous_last.filter(|f| !f.is_empty()) {
+            let file = get_images_dir(project_path).join(file_name);
+            if file.exists() {
+                files.push(file);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// 下载图片（同时支持 http(s) URL 与 base64 data URL）
+#[tauri::command]
+pub fn download_image(url: String, save_path: String) -> Result<(), String> {
+    let data = fetch_bytes(&url)?;
+
+    fs::write(&save_path, data)
+        .map_err(|e| format!("保存图片失败: {}", e))?;
 
     Ok(())
 }
 
+/// 更新分镜图片路径
+/// 同时登记为该镜该帧的一个图片版本并设为选中，避免覆盖丢失历史
+#[tauri::command]
+pub fn update_storyboard_image(
+    folder_path: String,
+    mirror_id: String,
+    image_type: String,
+    image_path: String,
+) -> Result<(), String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    if image_type != "first" && image_type != "last" {
+        return Err("无效的图片类型".to_string());
+    }
+    ensure_unlocked(&db, &mirror_id)?;
+
+    let existing: Option<i64> = db.conn().query_row(
+        "SELECT id FROM storyboard_images WHERE mirror_id = ?1 AND frame = ?2 AND file_path = ?3",
+        [&mirror_id, &image_type, &image_path],
+        |row| row.get(0),
+    ).ok();
+
+    let image_id = match existing {
+        Some(id) => id,
+        None => db.insert_storyboard_image(&StoryboardImage {
+            id: None,
+            mirror_id: mirror_id.clone(),
+            frame: image_type.clone(),
+            file_path: image_path.clone(),
+            prompt: None,
+            provider: None,
+            model: None,
+            seed: None,
+            created_at: unix_timestamp()?,
+            selected: false,
+        }).map_err(|e| format!("登记图片版本失败: {}", e))?,
+    };
+
+    db.select_storyboard_image(image_id)
+        .map_err(|e| format!("更新分镜图片失败: {}", e))?;
+
+    Ok(())
+}
+
+/// 生成分镜图片（一次完成：组装提示词 → 调用接口 → 解码/下载 → 写入项目图片目录 → 更新分镜）
+/// 按项目生图参数生成全部候选图，每张都登记为图片版本，第一张设为选中；
+/// 文件名带时间戳，重新生成不会覆盖之前的版本
+#[tauri::command]
+pub fn generate_storyboard_image(
+    folder_path: String,
+    api_config: ApiConfig,
+    mirror_id: String,
+    frame: String,
+    use_references: Option<bool>,
+) -> Result<Vec<StoryboardImage>, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    ensure_unlocked(&db, &mirror_id)?;
+    let storyboard = load_storyboard(&db, &mirror_id)?;
+    let prompt = build_frame_prompt(&db, &storyboard, &frame)?;
+    let settings = db.get_image_settings();
+    let references = if use_references.unwrap_or(false) {
+        load_reference_images(&db, &path, &mirror_id, &frame, true)?
+    } else {
+        Vec::new()
+    };
+
+    let image_urls = request_images(&api_config, &prompt, &settings, &references)?;
+
+    let images_dir = get_images_dir(&path);
+    fs::create_dir_all(&images_dir)
+        .map_err(|e| format!("创建图片目录失败: {}", e))?;
+
+    let created_at = unix_timestamp()?;
+    let mut images = Vec::new();
+    for (index, image_url) in image_urls.iter().enumerate() {
+        let data = fetch_bytes(image_url)?;
+        let file_name = format!(
+            "storyboard_{:03}_{}_{}_{}.{}",
+            storyboard.sequence_number,
+            frame,
+            created_at,
+            index + 1,
+            sniff_image_extension(&data)
+        );
+        fs::write(images_dir.join(&file_name), data)
+            .map_err(|e| format!("保存图片失败: {}", e))?;
+
+        let mut image = StoryboardImage {
+            id: None,
+            mirror_id: mirror_id.clone(),
+            frame: frame.clone(),
+            file_path: file_name,
+            prompt: Some(prompt.clone()),
+            provider: Some(api_config.name.clone()),
+            model: api_config.model.clone(),
+            seed: settings.seed,
+            created_at,
+            selected: index == 0,
+        };
+        image.id = Some(db.insert_storyboard_image(&image)
+            .map_err(|e| format!("登记图片版本失败: {}", e))?);
+        images.push(image);
+    }
+
+    if let Some(first_id) = images.first().and_then(|image| image.id) {
+        db.select_storyboard_image(first_id)
+            .map_err(|e| format!("更新分镜图片失败: {}", e))?;
+    }
+
+    Ok(images)
+}
+
+/// 获取分镜的图片版本列表（新的在前），frame 为空时返回首帧和尾帧
+#[tauri::command]
+pub fn list_storyboard_images(
+    folder_path: String,
+    mirror_id: String,
+    frame: Option<String>,
+) -> Result<Vec<StoryboardImage>, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    let mut stmt = db.conn().prepare(
+        "SELECT id, mirror_id, frame, file_path, prompt, provider, model, seed, created_at, selected
+         FROM storyboard_images
+         WHERE mirror_id = ?1 AND (?2 IS NULL OR frame = ?2)
+         ORDER BY created_at DESC, id DESC"
+    ).map_err(|e| format!("查询图片版本失败: {}", e))?;
+
+    let images = stmt.query_map(rusqlite::params![mirror_id, frame], |row| {
+        Ok(StoryboardImage {
+            id: Some(row.get(0)?),
+            mirror_id: row.get(1)?,
+            frame: row.get(2)?,
+            file_path: row.get(3)?,
+            prompt: row.get(4)?,
+            provider: row.get(5)?,
+            model: row.get(6)?,
+            seed: row.get(7)?,
+            created_at: row.get(8)?,
+            selected: row.get(9)?,
+        })
+    }).map_err(|e| format!("解析图片版本失败: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("收集图片版本失败: {}", e))?;
+
+    Ok(images)
+}
+
+/// 选中某个图片版本作为分镜的首帧/尾帧
+#[tauri::command]
+pub fn select_storyboard_image(folder_path: String, image_id: i64) -> Result<(), String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    let mirror_id: String = db.conn().query_row(
+        "SELECT mirror_id FROM storyboard_images WHERE id = ?1",
+        [image_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("图片版本不存在: {}", e))?;
+    ensure_unlocked(&db, &mirror_id)?;
+
+    db.select_storyboard_image(image_id)
+        .map_err(|e| format!("选择图片版本失败: {}", e))
+}
+
+/// 删除图片版本（同时删除图片文件）
+/// 删除的是当前选中版本时，自动选中同帧最新的剩余版本；没有剩余版本则清空该帧
+#[tauri::command]
+pub fn delete_storyboard_image(folder_path: String, image_id: i64) -> Result<(), String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    let (mirror_id, file_path, selected): (String, String, bool) = db.conn().query_row(
+        "SELECT mirror_id, file_path, selected FROM storyboard_images WHERE id = ?1",
+        [image_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).map_err(|e| format!("图片版本不存在: {}", e))?;
+    if selected {
+        ensure_unlocked(&db, &mirror_id)?;
+    }
+
+    db.delete_storyboard_image(image_id)
+        .map_err(|e| format!("删除图片版本失败: {}", e))?;
+
+    // 其他图片版本或任一分镜版本仍引用同一文件时保留文件
+    let still_referenced: i64 = db.conn().query_row(
+        "SELECT COUNT(*) FROM storyboard_images WHERE file_path = ?1",
+        [&file_path],
+        |row| row.get(0),
+    ).unwrap_or(0);
+    if still_referenced == 0 && !boards::image_in_use(&db, &file_path)? {
+        let _ = fs::remove_file(get_images_dir(&path).join(&file_path));
+    }
+
+    Ok(())
+}
+
+/// 批量添加生图任务到项目队列，返回新增任务数
+/// 已有相同镜号+帧的 pending/running 任务或已锁定的镜头会被跳过
+#[tauri::command]
+pub fn enqueue_image_jobs(
+    folder_path: String,
+    api_id: String,
+    mirror_ids: Vec<String>,
+    frames: Vec<String>,
+    use_references: Option<bool>,
+) -> Result<usize, String> {
+    if frames.iter().any(|f| f != "first" && f != "last") {
+        return Err("无效的图片类型".to_string());
+    }
+
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    enqueue_jobs(&db, &api_id, &mirror_ids, &frames, use_references.unwrap_or(false))
+        .map_err(|e| format!("添加生图任务失败: {}", e))
+}
+
+/// 启动项目生图队列（后台并发执行，进度通过 image-job-* / image-queue-finished 事件推送）
+/// 启动前会把上次中断时遗留的 running 任务恢复为 pending；返回 false 表示队列已在运行
+#[tauri::command]
+pub fn start_image_queue(
+    app: AppHandle,
+    queue: State<'_, ImageQueueState>,
+    folder_path: String,
+    concurrency: Option<usize>,
+) -> Result<bool, String> {
+    if queue.is_running(&folder_path) {
+        return Ok(false);
+    }
+    queue.start(app, folder_path, concurrency.unwrap_or(2))
+}
+
+/// 停止项目生图队列（执行中的任务会完成，其余保持 pending，可再次启动继续）
+#[tauri::command]
+pub fn stop_image_queue(queue: State<'_, ImageQueueState>, folder_path: String) -> Result<bool, String> {
+    queue.stop(&folder_path)
+}
+
+/// 按场景切分源剧本，重建长剧本分块生成任务（每块分配镜号前缀 A, B, C…）
+/// document_id 为空时使用最近导入的源剧本
+#[tauri::command]
+pub fn plan_script_generation(
+    pipeline: State<'_, ScriptPipelineState>,
+    folder_path: String,
+    document_id: Option<i64>,
+    max_chunk_chars: Option<usize>,
+) -> Result<Vec<GenerationChunk>, String> {
+    if pipeline.is_running(&folder_path) {
+        return Err("分块生成正在进行，请先停止".to_string());
+    }
+    let db = ProjectDatabase::open(&PathBuf::from(&folder_path))
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    let doc = db.get_source_document(document_id)
+        .map_err(|e| format!("读取源剧本失败: {}", e))?
+        .ok_or_else(|| "项目中没有源剧本，请先导入剧本文件".to_string())?;
+
+    plan_chunks(&db, doc.id, &doc.text, max_chunk_chars.unwrap_or(DEFAULT_CHUNK_CHARS))
+        .map_err(|e| format!("创建剧本分块失败: {}", e))?;
+    list_chunks(&db).map_err(|e| format!("读取剧本分块失败: {}", e))
+}
+
+/// 启动（或续跑）长剧本分块生成：按顺序逐块请求 AI，失败与中断的分块会重新排队，
+/// 已完成的分块不再处理；进度通过 script-chunk-* / script-generation-finished 事件推送
+#[tauri::command]
+pub fn start_script_generation(
+    app: AppHandle,
+    pipeline: State<'_, ScriptPipelineState>,
+    folder_path: String,
+    api_config: ApiConfig,
+) -> Result<bool, String> {
+    if pipeline.is_running(&folder_path) {
+        return Ok(false);
+    }
+    let db = ProjectDatabase::open(&PathBuf::from(&folder_path))
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    reset_unfinished_chunks(&db)
+        .map_err(|e| format!("恢复剧本分块失败: {}", e))?;
+
+    pipeline.start(app, folder_path, api_config)
+}
+
+/// 停止长剧本分块生成（当前分块完成后停止，可再次启动续跑）
+#[tauri::command]
+pub fn stop_script_generation(pipeline: State<'_, ScriptPipelineState>, folder_path: String) -> Result<bool, String> {
+    pipeline.stop(&folder_path)
+}
+
+/// 获取长剧本分块生成进度
+#[tauri::command]
+pub fn get_script_generation(folder_path: String) -> Result<Vec<GenerationChunk>, String> {
+    let db = ProjectDatabase::open(&PathBuf::from(&folder_path))
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    list_chunks(&db).map_err(|e| format!("读取剧本分块失败: {}", e))
+}
+
+/// 获取项目生图队列中的任务
+#[tauri::command]
+pub fn get_image_jobs(folder_path: String, status: Option<String>) -> Result<Vec<ImageJob>, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    let mut stmt = db.conn().prepare(
+        "SELECT id, mirror_id, frame, api_id, use_references, status, attempts, error, created_at, updated_at
+         FROM image_jobs WHERE (?1 IS NULL OR status = ?1) ORDER BY id"
+    ).map_err(|e| format!("查询生图任务失败: {}", e))?;
+
+    let jobs = stmt.query_map([status], image_job_from_row)
+        .map_err(|e| format!("解析生图任务失败: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("收集生图任务失败: {}", e))?;
+
+    Ok(jobs)
+}
+
+/// 取消 pending 任务（job_ids 为空时取消全部）
+#[tauri::command]
+pub fn cancel_image_jobs(folder_path: String, job_ids: Option<Vec<i64>>) -> Result<usize, String> {
+    update_image_job_status(&folder_path, job_ids, "pending", "cancelled")
+}
+
+/// 将失败的任务重新放回队列（job_ids 为空时重试全部失败任务）
+#[tauri::command]
+pub fn retry_image_jobs(folder_path: String, job_ids: Option<Vec<i64>>) -> Result<usize, String> {
+    update_image_job_status(&folder_path, job_ids, "failed", "pending")
+}
+
+/// 批量修改指定状态的任务
+fn update_image_job_status(
+    folder_path: &str,
+    job_ids: Option<Vec<i64>>,
+    from: &str,
+    to: &str,
+) -> Result<usize, String> {
+    let path = PathBuf::from(folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    let now = unix_timestamp()?;
+
+    let sql = "UPDATE image_jobs SET status = ?1, error = NULL, updated_at = ?2 WHERE status = ?3";
+    let updated = match job_ids {
+        Some(ids) => {
+            let mut updated = 0;
+            for id in ids {
+                updated += db.conn().execute(
+                    &format!("{} AND id = ?4", sql),
+                    rusqlite::params![to, now, from, id],
+                ).map_err(|e| format!("更新生图任务失败: {}", e))?;
+            }
+            updated
+        }
+        None => db.conn().execute(sql, rusqlite::params![to, now, from])
+            .map_err(|e| format!("更新生图任务失败: {}", e))?,
+    };
+
+    Ok(updated)
+}
+
+/// 生成分镜视频：提交任务（视频提示词 + 首帧/尾帧）→ 轮询状态 → 下载 MP4 → 写入分镜
+/// 任务 id 会先写入分镜，若上次生成被中断（超时、网络或下载失败），再次调用时继续轮询而不是重新提交；
+/// 轮询过程通过 video-generation-progress 事件推送。返回保存在 .storyboard/assets/videos/ 下的文件名
+#[tauri::command(async)]
+pub fn generate_storyboard_video(
+    app: AppHandle,
+    videos: State<'_, VideoGenerationState>,
+    folder_path: String,
+    api_config: ApiConfig,
+    mirror_id: String,
+) -> Result<String, String> {
+    let _generating = videos.begin(&folder_path);
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    ensure_unlocked(&db, &mirror_id)?;
+    let storyboard = load_storyboard(&db, &mirror_id)?;
+    let pending_task: Option<String> = db.conn().query_row(
+        "SELECT video_task_id FROM storyboards WHERE mirror_id = ?1 AND video_status = 'generating'",
+        [&mirror_id],
+        |row| row.get(0),
+    ).ok().flatten();
+
+    let emit_progress = |status: &str, task_status: Option<String>, error: Option<String>| {
+        let _ = app.emit("video-generation-progress", VideoProgressEvent {
+            folder_path: folder_path.clone(),
+            mirror_id: mirror_id.clone(),
+            status: status.to_string(),
+            task_status,
+            error,
+        });
+    };
+
+    let result = (|| -> Result<String, String> {
+        let task_id = match pending_task {
+            Some(task_id) => task_id,
+            None => {
+                let english = storyboard.video_prompt_en.as_deref().is_some_and(|p| !p.trim().is_empty());
+                let prompt = storyboard.video_prompt_en.as_deref()
+                    .filter(|p| !p.trim().is_empty())
+                    .or(storyboard.video_prompt_zh.as_deref())
+                    .filter(|p| !p.trim().is_empty())
+                    .ok_or_else(|| format!("分镜 {} 没有视频提示词", mirror_id))?;
+                let camera = camera_prompt(&Vocabulary::load(&db)?, &storyboard, english, true);
+                let prompt = if camera.is_empty() { prompt.to_string() } else { format!("{}\n{}", prompt, camera) };
+
+                let images_dir = get_images_dir(&path);
+                let frame_data_url = |file: &Option<String>| -> Result<Option<String>, String> {
+                    match file.as_deref().filter(|f| !f.is_empty()) {
+                        Some(file) => {
+                            let file = images_dir.join(file);
+                            let data = fs::read(&file)
+                                .map_err(|e| format!("读取帧图片失败 {}: {}", file.display(), e))?;
+                            Ok(Some(format!(
+                                "data:{};base64,{}",
+                                guess_image_mime(&file),
+                                general_purpose::STANDARD.encode(data)
+                            )))
+                        }
+                        None => Ok(None),
+                    }
+                };
+
+                let request = VideoRequest {
+                    prompt: &prompt,
+                    first_frame: frame_data_url(&storyboard.image_first_path)?,
+                    last_frame: frame_data_url(&storyboard.image_last_path)?,
+                    duration: storyboard.duration,
+                };
+                let task_id = submit_video_task(&api_config, &request)?;
+
+                db.conn().execute(
+                    "UPDATE storyboards SET video_task_id = ?1, video_status = 'generating' WHERE mirror_id = ?2",
+                    [&task_id, &mirror_id],
+                ).map_err(|e| format!("更新视频状态失败: {}", e))?;
+                task_id
+            }
+        };
+        emit_progress("submitted", None, None);
+
+        let status = wait_for_video_task(&api_config, &task_id, |task_status| {
+            emit_progress("running", Some(task_status.to_string()), None)
+        })?;
+        let video_url = match status {
+            TaskStatus::Failed(message) => {
+                // 厂商明确失败时清除任务 id，下次调用重新提交
+                let _ = db.conn().execute(
+                    "UPDATE storyboards SET video_task_id = NULL WHERE mirror_id = ?1",
+                    [&mirror_id],
+                );
+                return Err(format!("视频生成失败: {}", message));
+            }
+            TaskStatus::Succeeded(urls) => urls.into_iter().next().unwrap_or_default(),
+            TaskStatus::Running(status) => return Err(format!("视频任务未完成: {}", status)),
+        };
+
+        let data = fetch_bytes(&video_url)?;
+        let videos_dir = get_videos_dir(&path);
+        fs::create_dir_all(&videos_dir)
+            .map_err(|e| format!("创建视频目录失败: {}", e))?;
+
+        let file_name = format!("storyboard_{:03}_{}.mp4", storyboard.sequence_number, unix_timestamp()?);
+        fs::write(videos_dir.join(&file_name), data)
+            .map_err(|e| format!("保存视频失败: {}", e))?;
+
+        db.conn().execute(
+            "UPDATE storyboards SET video_path = ?1, video_status = 'generated', video_task_id = NULL WHERE mirror_id = ?2",
+            [&file_name, &mirror_id],
+        ).map_err(|e| format!("更新视频状态失败: {}", e))?;
+
+        Ok(file_name)
+    })();
+
+    match &result {
+        Ok(_) => emit_progress("generated", None, None),
+        Err(e) => {
+            // 仍有任务 id（超时、网络或下载失败）时保持 generating，下次调用继续轮询
+            let _ = db.conn().execute(
+                "UPDATE storyboards SET video_status = 'failed' WHERE mirror_id = ?1 AND video_task_id IS NULL",
+                [&mirror_id],
+            );
+            emit_progress("failed", None, Some(e.clone()));
+        }
+    }
+
+    result
+}
+
+/// 导出动态分镜（按时长拼接选中的帧图，可烧录对白字幕）
+#[tauri::command(async)]
+pub fn export_animatic(folder_path: String, options: AnimaticOptions, scope: Option<StoryboardScope>) -> Result<ExportResult, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    let mut storyboards = get_storyboards(folder_path.clone(), scope)?;
+    if let Some(mirror_ids) = &options.mirror_ids {
+        storyboards.retain(|sb| mirror_ids.contains(&sb.mirror_id));
+    }
+    if storyboards.is_empty() {
+        return Err("没有可导出的分镜".to_string());
+    }
+
+    let mut warnings = Vec::new();
+
+    // 画面尺寸：宽度默认 960，高度按项目画幅（默认 16:9），对齐到偶数
+    let width = options.width.unwrap_or(960).clamp(64, 3840);
+    let ratio = db.get_image_settings().aspect_ratio.as_deref()
+        .and_then(parse_aspect_ratio)
+        .unwrap_or(16.0 / 9.0);
+    let height = (((width as f64 / ratio) / 2.0).round() * 2.0).max(2.0) as u32;
+
+    let font = if options.captions {
+        match load_font_data(options.font_path.as_deref())
+            .and_then(|(_, data)| FontVec::try_from_vec(data).map_err(|e| format!("解析字体失败: {}", e)))
+        {
+            Ok(font) => Some(font),
+            Err(e) => {
+                warnings.push(format!("{}，已跳过字幕", e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let images_dir = get_images_dir(&path);
+    let default_duration = options.default_duration.filter(|d| *d > 0.0).unwrap_or(DEFAULT_SHOT_DURATION);
+    let use_last = options.frame.as_deref() == Some("last");
+    let frames: Vec<AnimaticFrame> = storyboards.iter().map(|sb| {
+        let file = if use_last {
+            sb.image_last_path.as_ref().or(sb.image_first_path.as_ref())
+        } else {
+            sb.image_first_path.as_ref().or(sb.image_last_path.as_ref())
+        };
+        let image = file.map(|f| images_dir.join(f)).filter(|p| p.exists());
+        if image.is_none() {
+            warnings.push(format!("{} 没有分镜图，使用占位图", sb.mirror_id));
+        }
+        AnimaticFrame {
+            storyboard: sb,
+            image,
+            duration: sb.duration.filter(|d| *d > 0.0).unwrap_or(default_duration),
+        }
+    }).collect();
+
+    let format = options.format.as_deref().unwrap_or("gif");
+    let output = match &options.output_path {
+        Some(output) => PathBuf::from(output),
+        None => {
+            let exports_dir = get_exports_dir(&path);
+            fs::create_dir_all(&exports_dir).map_err(|e| format!("创建导出目录失败: {}", e))?;
+            let name = format!("animatic_{}", unix_timestamp()?);
+            match format {
+                "sequence" => exports_dir.join(name),
+                "apng" => exports_dir.join(format!("{}.png", name)),
+                _ => exports_dir.join(format!("{}.gif", name)),
+            }
+        }
+    };
+
+    let renderer = AnimaticRenderer { width, height, font };
+    match format {
+        "gif" => renderer.write_gif(&frames, &output)?,
+        "apng" => renderer.write_apng(&frames, &output)?,
+        "sequence" => renderer.write_sequence(&frames, &output)?,
+        other => return Err(format!("不支持的导出格式: {}", other)),
+    }
+
+    Ok(ExportResult {
+        output_path: output.to_string_lossy().to_string(),
+        item_count: frames.len(),
+        total_duration: Some(frames.iter().map(|f| f.duration).sum()),
+        warnings,
+    })
+}
+
+/// 导出对白字幕（SRT / WebVTT），时间码与动态分镜一致
+#[tauri::command]
+pub fn export_subtitles(folder_path: String, options: SubtitleOptions, scope: Option<StoryboardScope>) -> Result<ExportResult, String> {
+    let path = PathBuf::from(&folder_path);
+    let storyboards = get_storyboards(folder_path.clone(), scope)?;
+
+    let cue_options = CueOptions {
+        default_duration: options.default_duration.filter(|d| *d > 0.0).unwrap_or(DEFAULT_SHOT_DURATION),
+        merge_same_speaker: options.merge_same_speaker,
+        skip_empty: options.skip_empty.unwrap_or(true),
+    };
+    let cues = build_cues(&storyboards, &cue_options);
+    if cues.is_empty() {
+        return Err("没有可导出的对白".to_string());
+    }
+
+    let format = options.format.as_deref().unwrap_or("srt");
+    let content = match format {
+        "srt" => to_srt(&cues),
+        "vtt" => to_vtt(&cues),
+        other => return Err(format!("不支持的字幕格式: {}", other)),
+    };
+
+    let output = match &options.output_path {
+        Some(output) => PathBuf::from(output),
+        None => {
+            let exports_dir = get_exports_dir(&path);
+            fs::create_dir_all(&exports_dir).map_err(|e| format!("创建导出目录失败: {}", e))?;
+            exports_dir.join(format!("subtitles_{}.{}", unix_timestamp()?, format))
+        }
+    };
+    fs::write(&output, content).map_err(|e| format!("写入字幕失败: {}", e))?;
+
+    Ok(ExportResult {
+        output_path: output.to_string_lossy().to_string(),
+        item_count: cues.len(),
+        total_duration: cues.last().map(|cue| cue.end),
+        warnings: Vec::new(),
+    })
+}
+
+/// 导出剪辑时间线（CMX3600 EDL / FCPXML / OpenTimelineIO）
+#[tauri::command]
+pub fn export_timeline(folder_path: String, options: TimelineOptions, scope: Option<StoryboardScope>) -> Result<ExportResult, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    let storyboards = get_storyboards(folder_path.clone(), scope)?;
+    if storyboards.is_empty() {
+        return Err("没有可导出的分镜".to_string());
+    }
+
+    let fps = options.fps.filter(|f| *f > 0.0 && *f <= 120.0).unwrap_or(24.0);
+    let rate = FrameRate { fps };
+    let default_duration = options.default_duration.filter(|d| *d > 0.0).unwrap_or(DEFAULT_SHOT_DURATION);
+    let prefer_video = options.prefer_video.unwrap_or(true);
+    let use_last = options.frame.as_deref() == Some("last");
+    let (images_dir, videos_dir) = (get_images_dir(&path), get_videos_dir(&path));
+
+    let mut warnings = Vec::new();
+    let clips: Vec<TimelineClip> = storyboards.iter().map(|sb| {
+        let video = sb.video_path.as_ref()
+            .filter(|_| prefer_video)
+            .map(|f| videos_dir.join(f))
+            .filter(|p| p.exists());
+        let image = if use_last {
+            sb.image_last_path.as_ref().or(sb.image_first_path.as_ref())
+        } else {
+            sb.image_first_path.as_ref().or(sb.image_last_path.as_ref())
+        }.map(|f| images_dir.join(f)).filter(|p| p.exists());
+
+        let is_video = video.is_some();
+        let media = video.or(image);
+        if media.is_none() {
+            warnings.push(format!("{} 没有图片或视频，导出为空白片段", sb.mirror_id));
+        }
+
+        let mut markers = Vec::new();
+        if let Some(notes) = sb.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+            markers.push(("备注".to_string(), notes.trim().to_string()));
+        }
+        if let Some(dialogue) = sb.dialogue.as_deref().filter(|d| !d.trim().is_empty()) {
+            markers.push(("对白".to_string(), dialogue.trim().to_string()));
+        }
+
+        TimelineClip {
+            name: sb.mirror_id.clone(),
+            media,
+            is_video,
+            frames: rate.frames(sb.duration.filter(|d| *d > 0.0).unwrap_or(default_duration)),
+            markers,
+        }
+    }).collect();
+
+    let title = path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("storyboard")
+        .to_string();
+    let format = options.format.as_deref().unwrap_or("edl");
+    let (content, extension) = match format {
+        "edl" => (to_edl(&title, &clips, rate), "edl"),
+        "fcpxml" => {
+            // 画面尺寸按项目画幅，宽度固定 1920
+            let ratio = db.get_image_settings().aspect_ratio.as_deref()
+                .and_then(parse_aspect_ratio)
+                .unwrap_or(16.0 / 9.0);
+            let height = (((1920.0 / ratio) / 2.0).round() * 2.0) as u32;
+            (to_fcpxml(&title, &clips, rate, 1920, height), "fcpxml")
+        }
+        "otio" => (
+            serde_json::to_string_pretty(&to_otio(&title, &clips, rate)).map_err(|e| e.to_string())?,
+            "otio",
+        ),
+        other => return Err(format!("不支持的时间线格式: {}", other)),
+    };
+
+    let output = match &options.output_path {
+        Some(output) => PathBuf::from(output),
+        None => {
+            let exports_dir = get_exports_dir(&path);
+            fs::create_dir_all(&exports_dir).map_err(|e| format!("创建导出目录失败: {}", e))?;
+            exports_dir.join(format!("timeline_{}.{}", unix_timestamp()?, extension))
+        }
+    };
+    fs::write(&output, content).map_err(|e| format!("写入时间线失败: {}", e))?;
+
+    Ok(ExportResult {
+        output_path: output.to_string_lossy().to_string(),
+        item_count: clips.len(),
+        total_duration: Some(clips.iter().map(|c| c.frames).sum::<u64>() as f64 / fps),
+        warnings,
+    })
+}
+
+/// 当前 Unix 时间戳（秒）
+pub(crate) fn unix_timestamp() -> Result<i64, String> {
+    Ok(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("获取时间戳失败: {}", e))?
+        .as_secs() as i64)
+}
+
+/// 镜头参数提示词：角度与焦距；motion 为 true 时（视频）加上运镜与方向
+fn camera_prompt(vocabulary: &Vocabulary, storyboard: &Storyboard, english: bool, motion: bool) -> String {
+    let field = |value: &Option<String>| value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+    let mut parts = Vec::new();
+    if motion {
+        if let Some(movement) = field(&storyboard.camera_movement) {
+            let movement = vocabulary.label("camera_movement", &movement, english);
+            parts.push(match (field(&storyboard.camera_direction), english) {
+                (Some(direction), true) => format!("{} camera movement, {}", movement, direction),
+                (Some(direction), false) => format!("{}镜头，{}", movement, direction),
+                (None, true) => format!("{} camera movement", movement),
+                (None, false) => format!("{}镜头", movement),
+            });
+        }
+    }
+    if let Some(angle) = field(&storyboard.camera_angle) {
+        let angle = vocabulary.label("camera_angle", &angle, english);
+        parts.push(if english { format!("{} shot", angle) } else { format!("{}视角", angle) });
+    }
+    if let Some(focal_length) = storyboard.focal_length {
+        parts.push(if english { format!("{}mm lens", focal_length) } else { format!("{}mm 镜头", focal_length) });
+    }
+    parts.join(if english { ", " } else { "，" })
+}
+
+/// 组装分镜生图提示词（4 层结构：全局风格 / 资产锚点 / 动作分镜 / 画质增强）
+fn build_frame_prompt(db: &ProjectDatabase, storyboard: &Storyboard, frame: &str) -> Result<String, String> {
+    let (style_prompt, quality_prompt) = db.get_project_style();
+
+    let (prompt_en, prompt_zh) = match frame {
+        "first" => (&storyboard.image_prompt_en, &storyboard.image_prompt_zh),
+        "last" => (&storyboard.image_prompt_tail_en, &storyboard.image_prompt_tail_zh),
+        _ => return Err("无效的图片类型".to_string()),
+    };
+    let english = prompt_en.as_deref().is_some_and(|p| !p.is_empty());
+    let mut action_layer = prompt_en.as_deref()
+        .filter(|p| !p.is_empty())
+        .or(prompt_zh.as_deref())
+        .unwrap_or("")
+        .to_string();
+
+    if action_layer.trim().is_empty() {
+        return Err(format!(
+            "分镜 {} 没有{}生图提示词",
+            storyboard.mirror_id,
+            if frame == "first" { "首帧" } else { "尾帧" }
+        ));
+    }
+
+    // 角度与焦距作为动作分镜层的补充
+    let camera = camera_prompt(&Vocabulary::load(db)?, storyboard, english, false);
+    if !camera.is_empty() {
+        action_layer = format!("{}{}{}", action_layer, if english { ", " } else { "，" }, camera);
+    }
+
+    // 资产锚点层：与前端一致，从首帧提示词中的 #角色名/#场景名 解析
+    let anchor_source = storyboard.image_prompt_en.as_deref()
+        .filter(|p| !p.is_empty())
+        .or(storyboard.image_prompt_zh.as_deref())
+        .unwrap_or("");
+    let asset_layer = parse_asset_refs(anchor_source)
+        .into_iter()
+        .map(|name| {
+            ["characters", "scenes"].iter()
+                .find_map(|table| db.conn().query_row(
+                    &format!("SELECT image_prompt_en, image_prompt_zh FROM {} WHERE name = ?1", table),
+                    [&name],
+                    |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, Option<String>>(1)?)),
+                ).ok())
+                .and_then(|(en, zh)| en.filter(|p| !p.is_empty()).or(zh).filter(|p| !p.is_empty()))
+                .unwrap_or_else(|| format!("#{}", name))
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let layers: Vec<String> = [style_prompt.unwrap_or_default(), asset_layer, action_layer, quality_prompt.unwrap_or_default()]
+        .into_iter()
+        .filter(|layer| !layer.is_empty())
+        .collect();
+
+    Ok(layers.join("\n"))
+}
+
 /// 获取项目风格配置
 #[tauri::command]
 pub fn get_project_style(folder_path: String) -> Result<ProjectStyle, String> {
@@ -821,3 +2332,468 @@ pub fn save_excel_file(folder_path: String) -> Result<String, String> {
 pub fn save_excel_with_dialog(folder_path: String) -> Result<String, String> {
     save_excel_file(folder_path)
 }
+
+/// 导出分镜表 PDF（网格版式，嵌入中文字体，未生成的镜头显示占位框）
+#[tauri::command(async)]
+pub fn export_pdf(folder_path: String, options: PdfOptions, scope: Option<StoryboardScope>) -> Result<ExportResult, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    let storyboards = get_storyboards(folder_path.clone(), scope)?;
+    if storyboards.is_empty() {
+        return Err("没有可导出的分镜".to_string());
+    }
+
+    let (_, font_data) = load_font_data(options.font_path.as_deref())?;
+    let ratio = db.get_image_settings().aspect_ratio.as_deref()
+        .and_then(parse_aspect_ratio)
+        .unwrap_or(16.0 / 9.0);
+
+    let images_dir = get_images_dir(&path);
+    let use_last = options.frame.as_deref() == Some("last");
+    let entries: Vec<SheetEntry> = storyboards.iter().map(|sb| {
+        let file = if use_last {
+            sb.image_last_path.as_ref().or(sb.image_first_path.as_ref())
+        } else {
+            sb.image_first_path.as_ref().or(sb.image_last_path.as_ref())
+        };
+        SheetEntry {
+            storyboard: sb,
+            image: file.map(|f| images_dir.join(f)).filter(|p| p.exists()),
+        }
+    }).collect();
+
+    let title = path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("storyboard")
+        .to_string();
+    let output = match &options.output_path {
+        Some(output) => PathBuf::from(output),
+        None => {
+            let exports_dir = get_exports_dir(&path);
+            fs::create_dir_all(&exports_dir).map_err(|e| format!("创建导出目录失败: {}", e))?;
+            exports_dir.join(format!("{}_{}.pdf", title, unix_timestamp()?))
+        }
+    };
+
+    let layout = options.layout.as_deref().unwrap_or("2x3");
+    let warnings = write_storyboard_pdf(&output, &title, &entries, layout, ratio, &font_data)?;
+
+    Ok(ExportResult {
+        output_path: output.to_string_lossy().to_string(),
+        item_count: entries.len(),
+        total_duration: None,
+        warnings,
+    })
+}
+
+/// 导出离线审阅包（index.html + 缩略图 + JSON 数据），可选同时打包为 zip
+#[tauri::command(async)]
+pub fn export_review_package(
+    folder_path: String,
+    output_path: Option<String>,
+    zip: Option<bool>,
+    scope: Option<StoryboardScope>,
+) -> Result<ExportResult, String> {
+    let path = PathBuf::from(&folder_path);
+    let db = ProjectDatabase::open(&path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    let storyboards = get_storyboards(folder_path.clone(), scope)?;
+    let (style_prompt, quality_prompt) = db.get_project_style();
+    let settings = db.get_image_settings();
+
+    let title = path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("storyboard")
+        .to_string();
+    let exported_at = unix_timestamp()?;
+    let output_dir = match output_path {
+        Some(output) => PathBuf::from(output),
+        None => get_exports_dir(&path).join(format!("{}_review_{}", title, exported_at)),
+    };
+
+    let data = json!({
+        "title": title,
+        "exported_at": exported_at,
+        "storyboards": storyboards,
+        "characters": get_characters(folder_path.clone())?,
+        "scenes": get_scenes(folder_path.clone())?,
+        "props": get_props(folder_path.clone())?,
+        "style": {
+            "style_prompt": style_prompt,
+            "quality_prompt": quality_prompt,
+            "aspect_ratio": settings.aspect_ratio,
+            "resolution": settings.resolution,
+            "negative_prompt": settings.negative_prompt,
+        },
+    });
+
+    let mut package = ReviewPackage {
+        output_dir: output_dir.clone(),
+        images_dir: get_images_dir(&path),
+        references_dir: get_references_dir(&path),
+        warnings: Vec::new(),
+    };
+    package.write(&title, data)?;
+
+    let output = if zip.unwrap_or(false) {
+        // 目录名可能含点（如 My.Film），不能用 with_extension 替换
+        let file_name = output_dir.file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| title.clone());
+        let zip_path = output_dir.parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(format!("{}.zip", file_name));
+        zip_directory(&output_dir, &zip_path)?;
+        zip_path
+    } else {
+        output_dir
+    };
+
+    Ok(ExportResult {
+        output_path: output.to_string_lossy().to_string(),
+        item_count: storyboards.len(),
+        total_duration: None,
+        warnings: package.warnings,
+    })
+}
+
+/// 预览表格导入：自动识别工作表类型与列映射，并校验数据
+#[tauri::command]
+pub fn preview_spreadsheet_import(folder_path: String, file_path: String) -> Result<ImportPreview, String> {
+    let tables = read_tables(Path::new(&file_path))?;
+    let sheets = preview_sheets(&tables);
+    let data = parse_sheets(&tables, &sheets, &get_storyboards(folder_path, None)?);
+    Ok(import_preview(sheets, &data))
+}
+
+/// 按确认后的映射导入表格（xlsx / csv），经 save_generated_data 合并进项目
+/// sheets 为空时使用自动映射；校验不通过时不写入
+#[tauri::command]
+pub fn import_spreadsheet(
+    folder_path: String,
+    file_path: String,
+    sheets: Option<Vec<ImportSheet>>,
+) -> Result<ImportPreview, String> {
+    let tables = read_tables(Path::new(&file_path))?;
+    let sheets = sheets.unwrap_or_else(|| preview_sheets(&tables));
+    let data = parse_sheets(&tables, &sheets, &get_storyboards(folder_path.clone(), None)?);
+    if !data.issues.is_empty() {
+        return Err(format!("导入数据校验失败:\n{}", data.issues.join("\n")));
+    }
+
+    let preview = import_preview(sheets, &data);
+    save_generated_data(folder_path, data.storyboards, data.characters, data.scenes, data.props, None)?;
+    Ok(preview)
+}
+
+fn import_preview(sheets: Vec<ImportSheet>, data: &ImportedData) -> ImportPreview {
+    ImportPreview {
+        sheets,
+        storyboard_count: data.storyboards.len(),
+        character_count: data.characters.len(),
+        scene_count: data.scenes.len(),
+        prop_count: data.props.len(),
+        issues: data.issues.clone(),
+    }
+}
+
+/// 解析 Fountain 剧本：按场景拆分并切块，seed_assets 为 true（默认）时
+/// 以场景标题与角色提示预置场景、角色资产（已存在的不覆盖）
+/// file_path 与 content 二选一
+#[tauri::command]
+pub fn import_fountain(
+    folder_path: String,
+    file_path: Option<String>,
+    content: Option<String>,
+    seed_assets: Option<bool>,
+    max_chunk_chars: Option<usize>,
+) -> Result<ScriptImportResult, String> {
+    let content = match (content, &file_path) {
+        (Some(content), _) => content,
+        (None, Some(file_path)) => fs::read_to_string(file_path)
+            .map_err(|e| format!("读取剧本失败: {}", e))?,
+        (None, None) => return Err("未提供剧本内容".to_string()),
+    };
+    let play = parse_fountain(&content);
+    let mut result = script_import_result(&folder_path, &play, seed_assets.unwrap_or(true), max_chunk_chars)?;
+
+    // 与 FDX 一致保存为源剧本；直接粘贴的内容按标题命名
+    let file_name = match &file_path {
+        Some(file_path) => script_file_name(file_path)?,
+        None => format!("{}.fountain", sanitize_file_name(play.title.as_deref().unwrap_or("script"))),
+    };
+    let text = result.scenes.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join("\n\n");
+    result.document_id = Some(store_source_script(&PathBuf::from(&folder_path), &file_name, content.as_bytes(), &text)?);
+    Ok(result)
+}
+
+/// 汇总剧本场景、角色与分块，并按需预置资产
+fn script_import_result(
+    folder_path: &str,
+    play: &Screenplay,
+    seed_assets: bool,
+    max_chunk_chars: Option<usize>,
+) -> Result<ScriptImportResult, String> {
+    let scenes = play.scenes();
+    let characters = play.characters();
+    let chunks = chunk_scenes(&scenes, max_chunk_chars.unwrap_or(DEFAULT_CHUNK_CHARS));
+
+    let mut seeded_scenes = 0;
+    let mut seeded_characters = 0;
+    if seed_assets {
+        let db = ProjectDatabase::open(&PathBuf::from(folder_path))
+            .map_err(|e| format!("打开数据库失败: {}", e))?;
+        for scene in &scenes {
+            let Some(location) = &scene.location else { continue };
+            if db.seed_asset("scenes", location, &scene.heading, "来自剧本场景标题")
+                .map_err(|e| format!("保存场景失败: {}", e))? {
+                seeded_scenes += 1;
+            }
+        }
+        for name in &characters {
+            if db.seed_asset("characters", name, "", "来自剧本角色提示")
+                .map_err(|e| format!("保存角色失败: {}", e))? {
+                seeded_characters += 1;
+            }
+        }
+    }
+
+    Ok(ScriptImportResult {
+        title: play.title.clone(),
+        scenes,
+        characters,
+        chunks,
+        seeded_scenes,
+        seeded_characters,
+        document_id: None,
+    })
+}
+
+/// 导入 Final Draft 剧本：原文件保存为项目源剧本，并按场景拆分、预置场景与角色资产
+#[tauri::command]
+pub fn import_fdx(
+    folder_path: String,
+    file_path: String,
+    seed_assets: Option<bool>,
+    max_chunk_chars: Option<usize>,
+) -> Result<ScriptImportResult, String> {
+    let data = fs::read(&file_path)
+        .map_err(|e| format!("读取剧本失败: {}", e))?;
+    let play = parse_fdx(&String::from_utf8_lossy(&data))?;
+    let mut result = script_import_result(&folder_path, &play, seed_assets.unwrap_or(true), max_chunk_chars)?;
+
+    let text = result.scenes.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join("\n\n");
+    result.document_id = Some(store_source_script(&PathBuf::from(&folder_path), &script_file_name(&file_path)?, &data, &text)?);
+    Ok(result)
+}
+
+/// 读取剧本文件（docx / pdf / txt / md / fountain），保留段落与标题结构；
+/// 指定项目时原文件与提取的文本保存到项目，未打开项目时只返回提取结果
+#[tauri::command(async)]
+pub fn ingest_script(folder_path: Option<String>, file_path: String) -> Result<IngestedScript, String> {
+    let data = fs::read(&file_path).map_err(|e| format!("读取剧本失败: {}", e))?;
+    ingest_script_bytes(folder_path.as_deref(), &script_file_name(&file_path)?, &data)
+}
+
+/// 前端上传剧本：请求体为文件原始字节（二进制 IPC），
+/// 文件名与项目目录经 encodeURIComponent 编码后放在 x-file-name / x-folder-path 请求头中
+#[tauri::command(async)]
+pub fn upload_script(request: tauri::ipc::Request<'_>) -> Result<IngestedScript, String> {
+    let tauri::ipc::InvokeBody::Raw(data) = request.body() else {
+        return Err("上传的剧本内容格式无效".to_string());
+    };
+    let header = |name: &str| -> Result<Option<String>, String> {
+        match request.headers().get(name) {
+            Some(value) => {
+                let value = value.to_str().map_err(|_| format!("请求头 {} 无效", name))?;
+                percent_decode(value).map(Some)
+            }
+            None => Ok(None),
+        }
+    };
+    let file_name = header("x-file-name")?.ok_or_else(|| "缺少剧本文件名".to_string())?;
+    let folder_path = header("x-folder-path")?.filter(|p| !p.is_empty());
+    ingest_script_bytes(folder_path.as_deref(), &script_file_name(&file_name)?, data)
+}
+
+fn ingest_script_bytes(folder_path: Option<&str>, file_name: &str, data: &[u8]) -> Result<IngestedScript, String> {
+    let mut script = ingest_script_data(file_name, data)?;
+    if let Some(folder_path) = folder_path {
+        script.document_id = Some(store_source_script(&PathBuf::from(folder_path), file_name, data, &script.text)?);
+    }
+    Ok(script)
+}
+
+/// 解码 encodeURIComponent 编码的请求头
+fn percent_decode(value: &str) -> Result<String, String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = value.get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| format!("无效的编码: {}", value))?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| format!("无效的编码: {}", value))
+}
+
+fn script_file_name(file_path: &str) -> Result<String, String> {
+    Path::new(file_path).file_name()
+        .and_then(|n| n.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| "无效的剧本文件名".to_string())
+}
+
+/// 保存源剧本：原文件写入项目 sources 目录，提取的文本存为源文档，返回文档 id
+fn store_source_script(project_path: &PathBuf, file_name: &str, data: &[u8], text: &str) -> Result<i64, String> {
+    let sources_dir = get_sources_dir(project_path);
+    fs::create_dir_all(&sources_dir)
+        .map_err(|e| format!("创建源剧本目录失败: {}", e))?;
+    fs::write(sources_dir.join(file_name), data)
+        .map_err(|e| format!("保存源剧本失败: {}", e))?;
+
+    let db = ProjectDatabase::open(project_path)
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    db.insert_source_document(file_name, text, unix_timestamp()?)
+        .map_err(|e| format!("保存源剧本失败: {}", e))
+}
+
+/// 获取分镜对应的源剧本摘录（含同一场中延伸到下一镜之前的段落）
+#[tauri::command]
+pub fn get_script_excerpt(folder_path: String, mirror_id: String) -> Result<ScriptExcerpt, String> {
+    let db = ProjectDatabase::open(&PathBuf::from(&folder_path))
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    let document_id: i64 = db.conn().query_row(
+        "SELECT document_id FROM storyboard_sources WHERE mirror_id = ?1",
+        [&mirror_id],
+        |row| row.get(0),
+    ).map_err(|_| format!("分镜 {} 没有对应的剧本位置", mirror_id))?;
+    let doc = db.get_source_document(Some(document_id))
+        .map_err(|e| format!("读取源剧本失败: {}", e))?
+        .ok_or_else(|| "源剧本不存在".to_string())?;
+    let sources = db.get_storyboard_sources(document_id)
+        .map_err(|e| format!("读取分镜源位置失败: {}", e))?;
+
+    let lines: Vec<&str> = doc.text.lines().collect();
+    let source = extend_sources(&lines, &sources)
+        .into_iter()
+        .find(|s| s.mirror_id == mirror_id)
+        .ok_or_else(|| format!("分镜 {} 没有对应的剧本位置", mirror_id))?;
+    let end = source.end_line.min(lines.len().saturating_sub(1));
+    Ok(ScriptExcerpt {
+        mirror_id,
+        document_id,
+        file_name: doc.file_name,
+        start_line: source.start_line,
+        end_line: end,
+        text: lines.get(source.start_line..=end).map(|l| l.join("\n")).unwrap_or_default(),
+    })
+}
+
+/// 列出源剧本中没有任何分镜覆盖的段落，document_id 为空时使用最近导入的剧本
+#[tauri::command]
+pub fn get_uncovered_passages(folder_path: String, document_id: Option<i64>) -> Result<Vec<UncoveredPassage>, String> {
+    let db = ProjectDatabase::open(&PathBuf::from(&folder_path))
+        .map_err(|e| format!("打开数据库失败: {}", e))?;
+    let doc = db.get_source_document(document_id)
+        .map_err(|e| format!("读取源剧本失败: {}", e))?
+        .ok_or_else(|| "项目中没有源剧本".to_string())?;
+    let sources = db.get_storyboard_sources(doc.id)
+        .map_err(|e| format!("读取分镜源位置失败: {}", e))?;
+
+    let lines: Vec<&str> = doc.text.lines().collect();
+    Ok(uncovered_ranges(&lines, &sources).into_iter().map(|(start, end)| UncoveredPassage {
+        document_id: doc.id,
+        start_line: start,
+        end_line: end,
+        text: lines[start..=end].join("\n"),
+    }).collect())
+}
+
+/// 将分镜导出为带注释的 Final Draft 剧本（镜号、景别、备注写入 Shot 段落与 ScriptNote）
+#[tauri::command]
+pub fn export_fdx(
+    folder_path: String,
+    output_path: Option<String>,
+    scope: Option<StoryboardScope>,
+) -> Result<ExportResult, String> {
+    let path = PathBuf::from(&folder_path);
+    let storyboards = get_storyboards(folder_path.clone(), scope)?;
+    if storyboards.is_empty() {
+        return Err("没有可导出的分镜".to_string());
+    }
+    let scenes = get_scenes(folder_path.clone())?;
+
+    let title = path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("storyboard")
+        .to_string();
+    let output = match output_path {
+        Some(p) => PathBuf::from(p),
+        None => {
+            let exports_dir = get_exports_dir(&path);
+            fs::create_dir_all(&exports_dir).map_err(|e| format!("创建导出目录失败: {}", e))?;
+            exports_dir.join(format!("{}_{}.fdx", title, unix_timestamp()?))
+        }
+    };
+    fs::write(&output, write_fdx(&title, &storyboards, &scenes))
+        .map_err(|e| format!("写入 FDX 失败: {}", e))?;
+
+    Ok(ExportResult {
+        output_path: output.to_string_lossy().to_string(),
+        item_count: storyboards.len(),
+        total_duration: None,
+        warnings: Vec::new(),
+    })
+}
+
+/// 由后端直接写入 Excel（分镜、角色、场景、道具四个工作表，可嵌入首尾帧缩略图）
+/// output_path 为空时写入项目 exports 目录
+#[tauri::command(async)]
+pub fn export_xlsx(
+    folder_path: String,
+    output_path: Option<String>,
+    include_images: Option<bool>,
+    scope: Option<StoryboardScope>,
+) -> Result<ExportResult, String> {
+    let path = PathBuf::from(&folder_path);
+    let storyboards = get_storyboards(folder_path.clone(), scope)?;
+    let characters = get_characters(folder_path.clone())?;
+    let scenes = get_scenes(folder_path.clone())?;
+    let props = get_props(folder_path.clone())?;
+
+    let output = match output_path {
+        Some(output) => PathBuf::from(output),
+        None => {
+            let exports_dir = get_exports_dir(&path);
+            fs::create_dir_all(&exports_dir).map_err(|e| format!("创建导出目录失败: {}", e))?;
+            let project_name = path.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("export");
+            exports_dir.join(format!("{}.xlsx", project_name))
+        }
+    };
+
+    let mut workbook = ProjectWorkbook {
+        images_dir: get_images_dir(&path),
+        references_dir: get_references_dir(&path),
+        include_images: include_images.unwrap_or(true),
+        warnings: Vec::new(),
+    };
+    workbook.write(&output, &storyboards, &characters, &scenes, &props)?;
+
+    Ok(ExportResult {
+        output_path: output.to_string_lossy().to_string(),
+        item_count: storyboards.len(),
+        total_duration: None,
+        warnings: workbook.warnings,
+    })
+}
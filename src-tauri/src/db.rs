@@ -1,5 +1,6 @@
+use crate::models::{ImageSettings, SourceDocument, StoryboardImage, StoryboardSource};
 use rusqlite::{Connection, Result as SqliteResult};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use dirs::home_dir;
 
 /// 全局配置路径
@@ -14,6 +15,31 @@ pub fn get_config_path() -> PathBuf {
     get_config_dir().join("config.json")
 }
 
+/// 项目分镜图片目录
+pub fn get_images_dir(project_path: &Path) -> PathBuf {
+    project_path.join(".storyboard").join("assets").join("images")
+}
+
+/// 项目导出目录
+pub fn get_exports_dir(project_path: &Path) -> PathBuf {
+    project_path.join("exports")
+}
+
+/// 项目分镜视频目录
+pub fn get_videos_dir(project_path: &Path) -> PathBuf {
+    project_path.join(".storyboard").join("assets").join("videos")
+}
+
+/// 项目资产参考图目录
+pub fn get_references_dir(project_path: &Path) -> PathBuf {
+    project_path.join(".storyboard").join("assets").join("references")
+}
+
+/// 项目源剧本目录
+pub fn get_sources_dir(project_path: &Path) -> PathBuf {
+    project_path.join(".storyboard").join("sources")
+}
+
 /// 项目数据库管理器
 pub struct ProjectDatabase {
     conn: Connection,
@@ -30,6 +56,8 @@ impl ProjectDatabase {
         }
 
         let conn = Connection::open(db_path)?;
+        // 批量生图队列会从多个线程同时打开项目库
+        conn.busy_timeout(std::time::Duration::from_secs(10))?;
 
         let db = ProjectDatabase { conn };
         db.init_tables()?;
@@ -67,6 +95,10 @@ impl ProjectDatabase {
             [],
         )?;
         self.migrate_storyboard_images()?;
+        self.migrate_storyboard_videos()?;
+        self.migrate_sequences()?;
+        self.migrate_camera_metadata()?;
+        self.migrate_review_status()?;
 
         // 角色资产表 (characters)
         self.conn.execute(
@@ -104,6 +136,8 @@ impl ProjectDatabase {
             [],
         )?;
 
+        self.migrate_asset_reference_images()?;
+
         // 项目元数据表 (project_meta)
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS project_meta (
@@ -124,6 +158,94 @@ impl ProjectDatabase {
             [],
         )?;
 
+        // 分镜图片版本表 (storyboard_images)
+        self.migrate_storyboard_image_history()?;
+
+        // 批量生图任务表 (image_jobs)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS image_jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mirror_id TEXT NOT NULL,
+                frame TEXT NOT NULL,
+                api_id TEXT NOT NULL,
+                use_references INTEGER NOT NULL DEFAULT 0,
+                status TEXT NOT NULL DEFAULT 'pending',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                error TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // 源文档表 (source_documents) 与分镜源位置表 (storyboard_sources)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS source_documents (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                file_name TEXT NOT NULL,
+                format TEXT NOT NULL,
+                text TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS storyboard_sources (
+                mirror_id TEXT PRIMARY KEY,
+                document_id INTEGER NOT NULL,
+                start_line INTEGER NOT NULL,
+                end_line INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // 长剧本分块生成任务表 (generation_chunks)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS generation_chunks (
+                chunk_index INTEGER PRIMARY KEY,
+                prefix TEXT NOT NULL,
+                headings TEXT NOT NULL DEFAULT '[]',
+                text TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                error TEXT,
+                storyboard_count INTEGER NOT NULL DEFAULT 0,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        self.migrate_generation_chunks()?;
+
+        // 备选分镜版本表 (boards)：当前版本的分镜存放在 storyboards 中，其余版本以快照保存
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS boards (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                snapshot TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        self.migrate_boards()?;
+
+        // 景别 / 镜头类型受控词表 (vocabulary_terms)，新项目写入内置词条
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS vocabulary_terms (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                category TEXT NOT NULL,
+                value TEXT NOT NULL,
+                label_zh TEXT NOT NULL DEFAULT '',
+                label_en TEXT NOT NULL DEFAULT '',
+                aliases TEXT NOT NULL DEFAULT '[]',
+                sort_order INTEGER NOT NULL DEFAULT 0,
+                UNIQUE(category, value)
+            )",
+            [],
+        )?;
+        self.migrate_vocabulary()?;
+        self.migrate_shot_type_movements()?;
+
         // 迁移风格相关字段
         self.migrate_project_style()?;
 
@@ -265,6 +387,403 @@ impl ProjectDatabase {
         Ok(())
     }
 
+    /// 迁移：为 storyboards 表添加视频相关字段
+    fn migrate_storyboard_videos(&self) -> SqliteResult<()> {
+        let has_video: bool = self.conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('storyboards') WHERE name='video_path'",
+            [],
+            |row| row.get(0),
+        ).unwrap_or(0) > 0;
+        if !has_video {
+            let _ = self.conn.execute("ALTER TABLE storyboards ADD COLUMN video_path TEXT", []);
+            let _ = self.conn.execute("ALTER TABLE storyboards ADD COLUMN video_status TEXT DEFAULT 'empty'", []);
+            let _ = self.conn.execute("ALTER TABLE storyboards ADD COLUMN video_task_id TEXT", []);
+        }
+        Ok(())
+    }
+
+    /// 迁移：剧集 / 序列层级，已有分镜归入默认序列
+    fn migrate_sequences(&self) -> SqliteResult<()> {
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS episodes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                sort_order INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS sequences (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                episode_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                prefix TEXT NOT NULL DEFAULT '',
+                sort_order INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )?;
+        let has_sequence: bool = self.conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('storyboards') WHERE name='sequence_id'",
+            [],
+            |row| row.get(0),
+        ).unwrap_or(0) > 0;
+        if !has_sequence {
+            let _ = self.conn.execute("ALTER TABLE storyboards ADD COLUMN sequence_id INTEGER", []);
+        }
+
+        let default_id = self.default_sequence_id()?;
+        self.conn.execute(
+            "UPDATE storyboards SET sequence_id = ?1
+             WHERE sequence_id IS NULL OR sequence_id NOT IN (SELECT id FROM sequences)",
+            [default_id],
+        )?;
+        self.fill_sequence_prefixes()?;
+        Ok(())
+    }
+
+    /// 迁移：为 generation_chunks 表添加来源文档字段
+    fn migrate_generation_chunks(&self) -> SqliteResult<()> {
+        let exists: bool = self.conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('generation_chunks') WHERE name = 'document_id'",
+            [],
+            |row| row.get(0),
+        ).unwrap_or(0) > 0;
+        if !exists {
+            let _ = self.conn.execute("ALTER TABLE generation_chunks ADD COLUMN document_id INTEGER", []);
+        }
+        Ok(())
+    }
+
+    /// 迁移：为 storyboards 表添加运镜与镜头参数字段
+    fn migrate_camera_metadata(&self) -> SqliteResult<()> {
+        for (column, kind) in [
+            ("camera_movement", "TEXT"),
+            ("camera_direction", "TEXT"),
+            ("focal_length", "REAL"),
+            ("camera_angle", "TEXT"),
+            ("transition", "TEXT"),
+        ] {
+            let exists: bool = self.conn.query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('storyboards') WHERE name = ?1",
+                [column],
+                |row| row.get(0),
+            ).unwrap_or(0) > 0;
+            if !exists {
+                let _ = self.conn.execute(&format!("ALTER TABLE storyboards ADD COLUMN {} {}", column, kind), []);
+            }
+        }
+        Ok(())
+    }
+
+    /// 迁移：为 storyboards 表添加审核状态字段，已有分镜为草稿
+    fn migrate_review_status(&self) -> SqliteResult<()> {
+        for (column, kind) in [
+            ("review_status", "TEXT NOT NULL DEFAULT 'draft'"),
+            ("reviewed_by", "TEXT"),
+            ("reviewed_at", "INTEGER"),
+        ] {
+            let exists: bool = self.conn.query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('storyboards') WHERE name = ?1",
+                [column],
+                |row| row.get(0),
+            ).unwrap_or(0) > 0;
+            if !exists {
+                let _ = self.conn.execute(&format!("ALTER TABLE storyboards ADD COLUMN {} {}", column, kind), []);
+            }
+        }
+        // 审核历史表 (storyboard_reviews)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS storyboard_reviews (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mirror_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                reviewer TEXT,
+                comment TEXT,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// 项目默认序列（排在最前的剧集中的第一个序列），没有时创建“第1集 / 默认序列”
+    pub fn default_sequence_id(&self) -> SqliteResult<i64> {
+        let existing = self.conn.query_row(
+            "SELECT s.id FROM sequences s JOIN episodes e ON e.id = s.episode_id
+             ORDER BY e.sort_order, e.id, s.sort_order, s.id LIMIT 1",
+            [],
+            |row| row.get(0),
+        );
+        match existing {
+            Ok(id) => Ok(id),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                let episode_id: i64 = match self.conn.query_row(
+                    "SELECT id FROM episodes ORDER BY sort_order, id LIMIT 1", [], |row| row.get(0),
+                ) {
+                    Ok(id) => id,
+                    Err(rusqlite::Error::QueryReturnedNoRows) => {
+                        self.conn.execute("INSERT INTO episodes (name, sort_order) VALUES ('第1集', 0)", [])?;
+                        self.conn.last_insert_rowid()
+                    }
+                    Err(e) => return Err(e),
+                };
+                self.conn.execute(
+                    "INSERT INTO sequences (episode_id, name, prefix, sort_order) VALUES (?1, '默认序列', '', 0)",
+                    [episode_id],
+                )?;
+                Ok(self.conn.last_insert_rowid())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 序列前缀是否已被其他序列使用（不区分大小写）
+    pub fn sequence_prefix_taken(&self, prefix: &str, except_id: Option<i64>) -> SqliteResult<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM sequences WHERE prefix != '' AND lower(prefix) = lower(?1) AND id != COALESCE(?2, -1)",
+            rusqlite::params![prefix, except_id],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// 未被使用的序列前缀：SQ1、SQ2……
+    pub fn unused_sequence_prefix(&self) -> SqliteResult<String> {
+        let mut n = 1;
+        loop {
+            let prefix = format!("SQ{}", n);
+            if !self.sequence_prefix_taken(&prefix, None)? {
+                return Ok(prefix);
+            }
+            n += 1;
+        }
+    }
+
+    /// 项目有多个序列时，为没有前缀的序列补上前缀，保证新生成的镜号不会跨序列重复
+    pub fn fill_sequence_prefixes(&self) -> SqliteResult<()> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM sequences", [], |row| row.get(0))?;
+        if count < 2 {
+            return Ok(());
+        }
+        let empty: Vec<i64> = {
+            let mut stmt = self.conn.prepare("SELECT id FROM sequences WHERE prefix = '' ORDER BY id")?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        for id in empty {
+            let prefix = self.unused_sequence_prefix()?;
+            self.conn.execute("UPDATE sequences SET prefix = ?1 WHERE id = ?2", rusqlite::params![prefix, id])?;
+        }
+        Ok(())
+    }
+
+    /// 镜号写入指定序列时实际使用的镜号：
+    /// 同一序列中已有的镜号直接沿用，否则加上序列前缀（如 SQ2-A1）；
+    /// 加前缀后仍与其他序列的镜头重复时返回 None
+    pub fn sequence_mirror_id(&self, mirror_id: &str, sequence_id: i64) -> SqliteResult<Option<String>> {
+        let owner = |id: &str| -> SqliteResult<Option<Option<i64>>> {
+            match self.conn.query_row("SELECT sequence_id FROM storyboards WHERE mirror_id = ?1", [id], |row| row.get(0)) {
+                Ok(owner) => Ok(Some(owner)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(e),
+            }
+        };
+        if owner(mirror_id)? == Some(Some(sequence_id)) {
+            return Ok(Some(mirror_id.to_string()));
+        }
+        let prefix: String = self.conn.query_row(
+            "SELECT prefix FROM sequences WHERE id = ?1",
+            [sequence_id],
+            |row| row.get(0),
+        )?;
+        let candidate = if prefix.is_empty() || mirror_id.starts_with(&format!("{}-", prefix)) {
+            mirror_id.to_string()
+        } else {
+            format!("{}-{}", prefix, mirror_id)
+        };
+        match owner(&candidate)? {
+            None => Ok(Some(candidate)),
+            Some(owner) if owner == Some(sequence_id) => Ok(Some(candidate)),
+            Some(_) => Ok(None),
+        }
+    }
+
+    /// 迁移：已有项目的分镜作为“主版本”
+    fn migrate_boards(&self) -> SqliteResult<()> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM boards", [], |row| row.get(0))?;
+        if count == 0 {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+            self.conn.execute(
+                "INSERT INTO boards (name, created_at, updated_at) VALUES ('主版本', ?1, ?1)",
+                [now],
+            )?;
+            let id = self.conn.last_insert_rowid().to_string();
+            self.set_meta("active_board_id", Some(&id))?;
+        }
+        Ok(())
+    }
+
+    /// 迁移：按类别写入内置词表（每个类别只初始化一次，之后的增删由项目自行维护）
+    fn migrate_vocabulary(&self) -> SqliteResult<()> {
+        // 早期版本只有景别与镜头类型两类，标记为 "1"
+        let seeded = match self.get_meta("vocabulary_seeded") {
+            Some(marker) if marker == "1" => "shot_size,shot_type".to_string(),
+            marker => marker.unwrap_or_default(),
+        };
+        let mut categories: Vec<&str> = seeded.split(',').filter(|c| !c.is_empty()).collect();
+        let terms = crate::vocabulary::default_terms();
+        for category in crate::vocabulary::CATEGORIES {
+            if categories.contains(&category) {
+                continue;
+            }
+            for term in terms.iter().filter(|t| t.category == category) {
+                self.conn.execute(
+                    "INSERT OR IGNORE INTO vocabulary_terms (category, value, label_zh, label_en, aliases, sort_order)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    rusqlite::params![
+                        term.category,
+                        term.value,
+                        term.label_zh,
+                        term.label_en,
+                        serde_json::to_string(&term.aliases).unwrap_or_else(|_| "[]".to_string()),
+                        term.sort_order,
+                    ],
+                )?;
+            }
+            categories.push(category);
+        }
+        self.set_meta("vocabulary_seeded", Some(&categories.join(",")))
+    }
+
+    /// 迁移：镜头类型不再包含运镜，旧值（推、拉、摇…）移到 camera_movement / camera_direction，
+    /// 当前分镜与各版本快照一并处理，词表中的旧词条换成新的内置镜头类型
+    fn migrate_shot_type_movements(&self) -> SqliteResult<()> {
+        if self.get_meta("shot_type_movements_migrated").is_some() {
+            return Ok(());
+        }
+        for (shot_type, movement, direction) in crate::vocabulary::MOVEMENT_SHOT_TYPES {
+            self.conn.execute(
+                "UPDATE storyboards SET
+                    camera_movement = COALESCE(NULLIF(camera_movement, ''), ?2),
+                    camera_direction = COALESCE(NULLIF(camera_direction, ''), NULLIF(?3, '')),
+                    shot_type = NULL
+                 WHERE shot_type = ?1",
+                [shot_type, movement, direction],
+            )?;
+            self.conn.execute(
+                "DELETE FROM vocabulary_terms WHERE category = 'shot_type' AND value = ?1",
+                [shot_type],
+            )?;
+        }
+
+        let snapshots: Vec<(i64, String)> = {
+            let mut stmt = self.conn.prepare("SELECT id, snapshot FROM boards")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        for (id, snapshot) in snapshots {
+            let Ok(mut snapshot) = serde_json::from_str::<serde_json::Value>(&snapshot) else { continue };
+            let Some(rows) = snapshot.get_mut("storyboards").and_then(|r| r.as_array_mut()) else { continue };
+            let mut changed = false;
+            for row in rows.iter_mut().filter_map(|r| r.as_object_mut()) {
+                let shot_type = row.get("shot_type").and_then(|v| v.as_str()).unwrap_or_default();
+                let Some((_, movement, direction)) = crate::vocabulary::MOVEMENT_SHOT_TYPES.iter()
+                    .find(|(value, _, _)| *value == shot_type)
+                else {
+                    continue;
+                };
+                for (column, value) in [("camera_movement", movement), ("camera_direction", direction)] {
+                    let empty = row.get(column).and_then(|v| v.as_str()).is_none_or(str::is_empty);
+                    if empty && !value.is_empty() {
+                        row.insert(column.to_string(), serde_json::Value::from(*value));
+                    }
+                }
+                row.insert("shot_type".to_string(), serde_json::Value::Null);
+                changed = true;
+            }
+            if changed {
+                self.conn.execute(
+                    "UPDATE boards SET snapshot = ?1 WHERE id = ?2",
+                    rusqlite::params![snapshot.to_string(), id],
+                )?;
+            }
+        }
+
+        for term in crate::vocabulary::default_terms().iter().filter(|t| t.category == "shot_type") {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO vocabulary_terms (category, value, label_zh, label_en, aliases, sort_order)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![
+                    term.category,
+                    term.value,
+                    term.label_zh,
+                    term.label_en,
+                    serde_json::to_string(&term.aliases).unwrap_or_else(|_| "[]".to_string()),
+                    term.sort_order,
+                ],
+            )?;
+        }
+        self.set_meta("shot_type_movements_migrated", Some("1"))
+    }
+
+    /// 迁移：为角色/场景/道具表添加参考图字段
+    fn migrate_asset_reference_images(&self) -> SqliteResult<()> {
+        for table in ["characters", "scenes", "props"] {
+            let has_reference: bool = self.conn.query_row(
+                &format!("SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name='reference_image_path'", table),
+                [],
+                |row| row.get(0),
+            ).unwrap_or(0) > 0;
+            if !has_reference {
+                let _ = self.conn.execute(&format!("ALTER TABLE {} ADD COLUMN reference_image_path TEXT", table), []);
+            }
+        }
+        Ok(())
+    }
+
+    /// 迁移：创建分镜图片版本表，并把已有的首帧/尾帧登记为已选版本
+    fn migrate_storyboard_image_history(&self) -> SqliteResult<()> {
+        let table_exists: bool = self.conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='storyboard_images'",
+            [],
+            |row| row.get::<_, i64>(0),
+        ).unwrap_or(0) > 0;
+
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS storyboard_images (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mirror_id TEXT NOT NULL,
+                frame TEXT NOT NULL,
+                file_path TEXT NOT NULL,
+                prompt TEXT,
+                provider TEXT,
+                model TEXT,
+                seed INTEGER,
+                created_at INTEGER NOT NULL,
+                selected INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )?;
+
+        if !table_exists {
+            for (frame, column) in [("first", "image_first_path"), ("last", "image_last_path")] {
+                self.conn.execute(
+                    &format!(
+                        "INSERT INTO storyboard_images (mirror_id, frame, file_path, created_at, selected)
+                         SELECT mirror_id, '{}', {}, strftime('%s', 'now'), 1
+                         FROM storyboards WHERE {} IS NOT NULL AND {} != ''",
+                        frame, column, column, column
+                    ),
+                    [],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     /// 迁移：为 project_meta 表添加风格相关字段
     /// 注意：project_meta 使用 key-value 结构，新字段通过 INSERT OR REPLACE 添加
     /// 此函数预留用于未来可能的表结构调整
@@ -310,6 +829,203 @@ impl ProjectDatabase {
         Ok(())
     }
 
+    /// 读取 project_meta 中的单个值
+    pub fn get_meta(&self, key: &str) -> Option<String> {
+        self.conn.query_row(
+            "SELECT value FROM project_meta WHERE key = ?1",
+            [key],
+            |row| row.get(0),
+        ).ok()
+    }
+
+    /// 写入 project_meta 中的单个值，None 表示删除
+    pub fn set_meta(&self, key: &str, value: Option<&str>) -> SqliteResult<()> {
+        match value {
+            Some(value) => self.conn.execute(
+                "INSERT OR REPLACE INTO project_meta (key, value) VALUES (?1, ?2)",
+                [key, value],
+            )?,
+            None => self.conn.execute("DELETE FROM project_meta WHERE key = ?1", [key])?,
+        };
+        Ok(())
+    }
+
+    /// 预置资产（characters / scenes / props）：仅插入不存在的名称，返回是否新增
+    pub fn seed_asset(&self, table: &str, name: &str, description: &str, notes: &str) -> SqliteResult<bool> {
+        let inserted = self.conn.execute(
+            &format!(
+                "INSERT INTO {} (name, description, notes) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(name) DO NOTHING",
+                table
+            ),
+            [name, description, notes],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// 获取项目生图参数
+    pub fn get_image_settings(&self) -> ImageSettings {
+        ImageSettings {
+            aspect_ratio: self.get_meta("image_aspect_ratio"),
+            resolution: self.get_meta("image_resolution"),
+            quality: self.get_meta("image_quality"),
+            style: self.get_meta("image_style"),
+            n: self.get_meta("image_n").and_then(|v| v.parse().ok()),
+            seed: self.get_meta("image_seed").and_then(|v| v.parse().ok()),
+            negative_prompt: self.get_meta("image_negative_prompt"),
+        }
+    }
+
+    /// 保存项目生图参数（未设置的字段会被清除）
+    pub fn save_image_settings(&self, settings: &ImageSettings) -> SqliteResult<()> {
+        let n = settings.n.map(|v| v.to_string());
+        let seed = settings.seed.map(|v| v.to_string());
+        self.set_meta("image_aspect_ratio", settings.aspect_ratio.as_deref())?;
+        self.set_meta("image_resolution", settings.resolution.as_deref())?;
+        self.set_meta("image_quality", settings.quality.as_deref())?;
+        self.set_meta("image_style", settings.style.as_deref())?;
+        self.set_meta("image_n", n.as_deref())?;
+        self.set_meta("image_seed", seed.as_deref())?;
+        self.set_meta("image_negative_prompt", settings.negative_prompt.as_deref())?;
+        Ok(())
+    }
+
+    /// 登记一张分镜图片版本，返回版本 id
+    pub fn insert_storyboard_image(&self, image: &StoryboardImage) -> SqliteResult<i64> {
+        self.conn.execute(
+            "INSERT INTO storyboard_images (mirror_id, frame, file_path, prompt, provider, model, seed, created_at, selected)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 0)",
+            rusqlite::params![
+                image.mirror_id,
+                image.frame,
+                image.file_path,
+                image.prompt,
+                image.provider,
+                image.model,
+                image.seed,
+                image.created_at,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// 选中某个图片版本：同镜同帧的其他版本取消选中，并同步到分镜的首帧/尾帧字段
+    pub fn select_storyboard_image(&self, image_id: i64) -> SqliteResult<()> {
+        let (mirror_id, frame, file_path): (String, String, String) = self.conn.query_row(
+            "SELECT mirror_id, frame, file_path FROM storyboard_images WHERE id = ?1",
+            [image_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        let column = if frame == "last" { "image_last_path" } else { "image_first_path" };
+
+        self.conn.execute(
+            "UPDATE storyboard_images SET selected = (id = ?1) WHERE mirror_id = ?2 AND frame = ?3",
+            rusqlite::params![image_id, mirror_id, frame],
+        )?;
+        self.conn.execute(
+            &format!("UPDATE storyboards SET {} = ?1, image_status = 'generated' WHERE mirror_id = ?2", column),
+            [&file_path, &mirror_id],
+        )?;
+        Ok(())
+    }
+
+    /// 删除图片版本记录（不删除文件）
+    /// 删除的是当前选中版本时，自动选中同帧最新的剩余版本；没有剩余版本则清空该帧
+    pub fn delete_storyboard_image(&self, image_id: i64) -> SqliteResult<()> {
+        let (mirror_id, frame, selected): (String, String, bool) = self.conn.query_row(
+            "SELECT mirror_id, frame, selected FROM storyboard_images WHERE id = ?1",
+            [image_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        self.conn.execute("DELETE FROM storyboard_images WHERE id = ?1", [image_id])?;
+        if !selected {
+            return Ok(());
+        }
+
+        let fallback: Option<i64> = self.conn.query_row(
+            "SELECT id FROM storyboard_images WHERE mirror_id = ?1 AND frame = ?2
+             ORDER BY created_at DESC, id DESC LIMIT 1",
+            [&mirror_id, &frame],
+            |row| row.get(0),
+        ).ok();
+        match fallback {
+            Some(id) => self.select_storyboard_image(id),
+            None => {
+                // SET 中的 CASE 读取的是更新前的值，只能依据另一帧判断是否已无图片
+                let (column, other) = if frame == "last" {
+                    ("image_last_path", "image_first_path")
+                } else {
+                    ("image_first_path", "image_last_path")
+                };
+                self.conn.execute(
+                    &format!(
+                        "UPDATE storyboards SET {} = NULL,
+                            image_status = CASE WHEN COALESCE({}, '') = '' THEN 'empty' ELSE image_status END
+                         WHERE mirror_id = ?1",
+                        column, other
+                    ),
+                    [&mirror_id],
+                )?;
+                Ok(())
+            }
+        }
+    }
+
+    /// 新增源文档，格式取文件扩展名
+    pub fn insert_source_document(&self, file_name: &str, text: &str, created_at: i64) -> SqliteResult<i64> {
+        let format = file_name.rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+        self.conn.execute(
+            "INSERT INTO source_documents (file_name, format, text, created_at) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![file_name, format, text, created_at],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// 读取源文档，id 为空时取最近导入的一份
+    pub fn get_source_document(&self, id: Option<i64>) -> SqliteResult<Option<SourceDocument>> {
+        let sql = "SELECT id, file_name, format, text, created_at FROM source_documents
+                   WHERE ?1 IS NULL OR id = ?1 ORDER BY id DESC LIMIT 1";
+        match self.conn.query_row(sql, [id], |row| Ok(SourceDocument {
+            id: row.get(0)?,
+            file_name: row.get(1)?,
+            format: row.get(2)?,
+            text: row.get(3)?,
+            created_at: row.get(4)?,
+        })) {
+            Ok(doc) => Ok(Some(doc)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 记录分镜对应的源文档位置
+    pub fn set_storyboard_source(&self, source: &StoryboardSource) -> SqliteResult<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO storyboard_sources (mirror_id, document_id, start_line, end_line)
+             VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![source.mirror_id, source.document_id, source.start_line as i64, source.end_line as i64],
+        )?;
+        Ok(())
+    }
+
+    /// 源文档中仍存在的分镜的位置（已删除的分镜不计）
+    pub fn get_storyboard_sources(&self, document_id: i64) -> SqliteResult<Vec<StoryboardSource>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.mirror_id, s.document_id, s.start_line, s.end_line
+             FROM storyboard_sources s JOIN storyboards b ON b.mirror_id = s.mirror_id
+             WHERE s.document_id = ?1 ORDER BY s.start_line, b.sequence_number",
+        )?;
+        let sources = stmt.query_map([document_id], |row| Ok(StoryboardSource {
+            mirror_id: row.get(0)?,
+            document_id: row.get(1)?,
+            start_line: row.get::<_, i64>(2)? as usize,
+            end_line: row.get::<_, i64>(3)? as usize,
+        }))?.collect();
+        sources
+    }
+
     /// 获取数据库连接引用
     pub fn conn(&self) -> &Connection {
         &self.conn
@@ -317,7 +1033,7 @@ impl ProjectDatabase {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
     #[test]
@@ -325,4 +1041,249 @@ mod tests {
         let config_dir = get_config_dir();
         assert!(config_dir.ends_with(".storyboard"));
     }
+
+    /// 创建临时项目目录
+    pub(crate) fn temp_project(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("storyboard_test_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_select_storyboard_image() {
+        let dir = temp_project("select_image");
+        let db = ProjectDatabase::open(&dir).unwrap();
+        db.conn().execute(
+            "INSERT INTO storyboards (mirror_id, sequence_number) VALUES ('A1', 1)",
+            [],
+        ).unwrap();
+
+        let take = |file: &str| StoryboardImage {
+            id: None,
+            mirror_id: "A1".to_string(),
+            frame: "first".to_string(),
+            file_path: file.to_string(),
+            prompt: None,
+            provider: None,
+            model: None,
+            seed: None,
+            created_at: 0,
+            selected: false,
+        };
+        let first = db.insert_storyboard_image(&take("a.png")).unwrap();
+        let second = db.insert_storyboard_image(&take("b.png")).unwrap();
+
+        db.select_storyboard_image(first).unwrap();
+        db.select_storyboard_image(second).unwrap();
+
+        let path: String = db.conn().query_row(
+            "SELECT image_first_path FROM storyboards WHERE mirror_id = 'A1'", [], |row| row.get(0),
+        ).unwrap();
+        let selected: i64 = db.conn().query_row(
+            "SELECT COUNT(*) FROM storyboard_images WHERE selected = 1", [], |row| row.get(0),
+        ).unwrap();
+        assert_eq!(path, "b.png");
+        assert_eq!(selected, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_delete_only_storyboard_image() {
+        let dir = temp_project("delete_image");
+        let db = ProjectDatabase::open(&dir).unwrap();
+        db.conn().execute(
+            "INSERT INTO storyboards (mirror_id, sequence_number) VALUES ('A1', 1)",
+            [],
+        ).unwrap();
+        let image_id = db.insert_storyboard_image(&StoryboardImage {
+            id: None,
+            mirror_id: "A1".to_string(),
+            frame: "first".to_string(),
+            file_path: "a.png".to_string(),
+            prompt: None,
+            provider: None,
+            model: None,
+            seed: None,
+            created_at: 0,
+            selected: false,
+        }).unwrap();
+        db.select_storyboard_image(image_id).unwrap();
+
+        db.delete_storyboard_image(image_id).unwrap();
+        let (path, status): (Option<String>, String) = db.conn().query_row(
+            "SELECT image_first_path, image_status FROM storyboards WHERE mirror_id = 'A1'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!(path, None);
+        assert_eq!(status, "empty");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_default_sequence_migration() {
+        let dir = temp_project("default_sequence");
+        let db = ProjectDatabase::open(&dir).unwrap();
+        db.conn().execute(
+            "INSERT INTO storyboards (mirror_id, sequence_number, sequence_id) VALUES ('A1', 1, NULL), ('A2', 2, 99)",
+            [],
+        ).unwrap();
+        drop(db);
+
+        // 重新打开时，未分配或指向不存在序列的分镜归入默认序列
+        let db = ProjectDatabase::open(&dir).unwrap();
+        let default_id = db.default_sequence_id().unwrap();
+        let assigned: i64 = db.conn().query_row(
+            "SELECT COUNT(*) FROM storyboards WHERE sequence_id = ?1", [default_id], |row| row.get(0),
+        ).unwrap();
+        let episodes: i64 = db.conn().query_row("SELECT COUNT(*) FROM episodes", [], |row| row.get(0)).unwrap();
+        assert_eq!(assigned, 2);
+        assert_eq!(episodes, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_legacy_vocabulary_marker() {
+        let dir = temp_project("vocabulary_marker");
+        let db = ProjectDatabase::open(&dir).unwrap();
+        db.conn().execute("DELETE FROM vocabulary_terms WHERE category IN ('shot_size', 'camera_angle')", []).unwrap();
+        db.set_meta("vocabulary_seeded", Some("1")).unwrap();
+        drop(db);
+
+        // 标记为 "1" 的项目不再补回已删除的景别，只写入新增的类别
+        let db = ProjectDatabase::open(&dir).unwrap();
+        let count = |category: &str| -> i64 {
+            db.conn().query_row(
+                "SELECT COUNT(*) FROM vocabulary_terms WHERE category = ?1", [category], |row| row.get(0),
+            ).unwrap()
+        };
+        assert_eq!(count("shot_size"), 0);
+        assert!(count("camera_angle") > 0);
+        assert_eq!(db.get_meta("vocabulary_seeded").as_deref(), Some("shot_size,shot_type,camera_movement,camera_angle,transition"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_shot_type_movement_migration() {
+        let dir = temp_project("shot_type_movements");
+        let db = ProjectDatabase::open(&dir).unwrap();
+        db.conn().execute(
+            "INSERT INTO storyboards (mirror_id, sequence_number, shot_type, camera_movement) VALUES ('A1', 1, '推', NULL), ('A2', 2, '摇', '横移'), ('A3', 3, '过肩', NULL)",
+            [],
+        ).unwrap();
+        db.conn().execute(
+            "INSERT INTO vocabulary_terms (category, value) VALUES ('shot_type', '推')",
+            [],
+        ).unwrap();
+        db.set_meta("shot_type_movements_migrated", None).unwrap();
+        drop(db);
+
+        let db = ProjectDatabase::open(&dir).unwrap();
+        let rows: Vec<(Option<String>, Option<String>, Option<String>)> = db.conn()
+            .prepare("SELECT shot_type, camera_movement, camera_direction FROM storyboards ORDER BY sequence_number").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))).unwrap()
+            .collect::<SqliteResult<_>>().unwrap();
+        assert_eq!(rows, vec![
+            (None, Some("推拉".to_string()), Some("推进".to_string())),
+            (None, Some("横移".to_string()), None),
+            (Some("过肩".to_string()), None, None),
+        ]);
+        let stale: i64 = db.conn().query_row(
+            "SELECT COUNT(*) FROM vocabulary_terms WHERE category = 'shot_type' AND value = '推'", [], |row| row.get(0),
+        ).unwrap();
+        assert_eq!(stale, 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_clear_camera_fields() {
+        use crate::commands::save_generated_data;
+        use crate::models::Storyboard;
+
+        let dir = temp_project("clear_camera_fields");
+        let folder = dir.to_string_lossy().to_string();
+        let shot = |camera_movement: Option<&str>, focal_length| Storyboard {
+            mirror_id: "A1".to_string(),
+            sequence_number: 1,
+            camera_movement: camera_movement.map(str::to_string),
+            camera_angle: Some("平视".to_string()),
+            focal_length,
+            ..Default::default()
+        };
+
+        save_generated_data(folder.clone(), vec![shot(Some("推拉"), Some(35.0))], vec![], vec![], vec![], None).unwrap();
+        // 缺省保留原值
+        save_generated_data(folder.clone(), vec![shot(None, None)], vec![], vec![], vec![], None).unwrap();
+        let db = ProjectDatabase::open(&dir).unwrap();
+        let read = || db.conn().query_row(
+            "SELECT camera_movement, focal_length FROM storyboards WHERE mirror_id = 'A1'", [],
+            |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, Option<f64>>(1)?)),
+        ).unwrap();
+        assert_eq!(read(), (Some("推拉".to_string()), Some(35.0)));
+
+        // 空字符串清空
+        save_generated_data(folder.clone(), vec![shot(Some(""), None)], vec![], vec![], vec![], None).unwrap();
+        assert_eq!(read(), (None, Some(35.0)));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_same_mirror_id_in_two_sequences() {
+        use crate::commands::{save_episode, save_generated_data};
+        use crate::models::Storyboard;
+
+        let dir = temp_project("sequence_mirror_ids");
+        let folder = dir.to_string_lossy().to_string();
+        let shot = |description: &str, sequence_id| Storyboard {
+            mirror_id: "A1".to_string(),
+            sequence_number: 1,
+            description: Some(description.to_string()),
+            sequence_id,
+            ..Default::default()
+        };
+
+        // 第1集的 A1 在只有一个序列时生成
+        save_generated_data(folder.clone(), vec![shot("第1集开场", None)], vec![], vec![], vec![], None).unwrap();
+        let episode_id = save_episode(folder.clone(), None, "第2集".to_string()).unwrap();
+
+        let db = ProjectDatabase::open(&dir).unwrap();
+        let first: i64 = db.default_sequence_id().unwrap();
+        let second: i64 = db.conn().query_row(
+            "SELECT id FROM sequences WHERE episode_id = ?1", [episode_id], |row| row.get(0),
+        ).unwrap();
+        let prefixes: Vec<String> = db.conn().prepare("SELECT prefix FROM sequences ORDER BY id").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<SqliteResult<_>>().unwrap();
+        assert_eq!(prefixes, vec!["SQ1".to_string(), "SQ2".to_string()]);
+
+        // 第2集也返回 A1：写入新镜头，不覆盖第1集的 A1
+        save_generated_data(folder.clone(), vec![shot("第2集开场", Some(second))], vec![], vec![], vec![], None).unwrap();
+        // 再次为第1集返回 A1：原地更新
+        save_generated_data(folder.clone(), vec![shot("第1集开场（改）", Some(first))], vec![], vec![], vec![], None).unwrap();
+
+        let rows: Vec<(String, i64, String)> = db.conn().prepare(
+            "SELECT mirror_id, sequence_id, description FROM storyboards ORDER BY mirror_id"
+        ).unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))).unwrap()
+            .collect::<SqliteResult<_>>().unwrap();
+        assert_eq!(rows, vec![
+            ("A1".to_string(), first, "第1集开场（改）".to_string()),
+            ("SQ2-A1".to_string(), second, "第2集开场".to_string()),
+        ]);
+
+        // 前缀在项目内唯一
+        assert!(db.sequence_prefix_taken("sq2", Some(first)).unwrap());
+        assert_eq!(db.sequence_mirror_id("A1", second).unwrap(), Some("SQ2-A1".to_string()));
+        db.conn().execute("UPDATE storyboards SET sequence_id = ?1 WHERE mirror_id = 'SQ2-A1'", [first]).unwrap();
+        assert_eq!(db.sequence_mirror_id("A1", second).unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,198 @@
+use crate::image_api::parse_asset_refs;
+use crate::models::{Scene, Storyboard};
+use crate::screenplay::{is_scene_heading, ElementKind, Screenplay, ScriptElement};
+use crate::subtitles::parse_speaker;
+use crate::timeline::xml_escape;
+use roxmltree::{Document, Node};
+
+/// 段落中直接包含的文字（忽略 ScriptNote 等嵌套内容）
+fn paragraph_text(paragraph: Node) -> String {
+    paragraph.children()
+        .filter(|n| n.has_tag_name("Text"))
+        .map(|n| n.descendants().filter(|d| d.is_text()).filter_map(|d| d.text()).collect::<String>())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn element_kind(paragraph_type: &str) -> ElementKind {
+    match paragraph_type {
+        "Scene Heading" => ElementKind::SceneHeading,
+        "Character" => ElementKind::Character,
+        "Parenthetical" => ElementKind::Parenthetical,
+        "Dialogue" => ElementKind::Dialogue,
+        "Transition" => ElementKind::Transition,
+        _ => ElementKind::Action,
+    }
+}
+
+/// 读取段落（DualDialogue 中的段落按顺序展开）
+fn collect_paragraphs(parent: Node, elements: &mut Vec<ScriptElement>) {
+    for paragraph in parent.children().filter(|n| n.has_tag_name("Paragraph")) {
+        for dual in paragraph.children().filter(|n| n.has_tag_name("DualDialogue")) {
+            collect_paragraphs(dual, elements);
+        }
+        let text = paragraph_text(paragraph);
+        if text.is_empty() {
+            continue;
+        }
+        let mut element = ScriptElement::new(element_kind(paragraph.attribute("Type").unwrap_or("Action")), text);
+        if element.kind == ElementKind::SceneHeading {
+            element.number = paragraph.attribute("Number").map(str::to_string);
+        }
+        elements.push(element);
+    }
+}
+
+/// 解析 Final Draft .fdx
+pub fn parse_fdx(xml: &str) -> Result<Screenplay, String> {
+    let doc = Document::parse(xml.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("解析 FDX 失败: {}", e))?;
+    let root = doc.root_element();
+    if !root.has_tag_name("FinalDraft") {
+        return Err("不是 Final Draft 文档".to_string());
+    }
+
+    let mut elements = Vec::new();
+    if let Some(content) = root.children().find(|n| n.has_tag_name("Content")) {
+        collect_paragraphs(content, &mut elements);
+    }
+    let title = root.children()
+        .find(|n| n.has_tag_name("TitlePage"))
+        .and_then(|page| page.descendants().filter(|n| n.has_tag_name("Paragraph")).map(paragraph_text).find(|t| !t.is_empty()));
+
+    Ok(Screenplay { title, elements })
+}
+
+/// FDX 段落
+fn paragraph(kind: &str, text: &str, note: Option<&str>) -> String {
+    let mut xml = format!("    <Paragraph Type=\"{}\">\n", kind);
+    if let Some(note) = note {
+        xml.push_str(&format!(
+            "      <ScriptNote Range=\"0,{}\">\n        <Paragraph>\n          <Text>{}</Text>\n        </Paragraph>\n      </ScriptNote>\n",
+            text.chars().count(),
+            xml_escape(note)
+        ));
+    }
+    xml.push_str(&format!("      <Text>{}</Text>\n    </Paragraph>\n", xml_escape(text)));
+    xml
+}
+
+/// 镜头所属场景：描述或画面提示词中第一个引用的场景资产
+fn shot_scene<'a>(sb: &Storyboard, scenes: &'a [Scene]) -> Option<&'a Scene> {
+    let text = format!("{} {}", sb.description.as_deref().unwrap_or(""), sb.image_prompt_zh.as_deref().unwrap_or(""));
+    parse_asset_refs(&text).iter().find_map(|name| scenes.iter().find(|s| &s.name == name))
+}
+
+/// 将分镜写为带注释的 FDX：场景切换处写场景标题，每个镜头以 Shot 段落标明镜号与景别，
+/// 画面描述为动作段落（备注作为 ScriptNote），对白按“角色：台词”拆为角色与对白
+pub fn write_fdx(title: &str, storyboards: &[Storyboard], scenes: &[Scene]) -> String {
+    let mut ordered: Vec<&Storyboard> = storyboards.iter().collect();
+    ordered.sort_by_key(|sb| sb.sequence_number);
+
+    let mut body = String::new();
+    let mut current_scene: Option<&str> = None;
+    for sb in ordered {
+        if let Some(scene) = shot_scene(sb, scenes) {
+            if current_scene != Some(scene.name.as_str()) {
+                current_scene = Some(scene.name.as_str());
+                // 由剧本预置的场景资产以原场景标题为描述
+                let heading = scene.description.as_deref()
+                    .filter(|d| is_scene_heading(d))
+                    .unwrap_or(&scene.name);
+                body.push_str(&paragraph("Scene Heading", heading, None));
+            }
+        }
+
+        let mut shot = vec![sb.mirror_id.clone()];
+        shot.extend(sb.shot_size.clone().filter(|s| !s.is_empty()));
+        shot.extend(sb.shot_type.clone().filter(|s| !s.is_empty()));
+        shot.extend(sb.duration.filter(|d| *d > 0.0).map(|d| format!("{}s", d)));
+        body.push_str(&paragraph("Shot", &shot.join(" / "), None));
+
+        let notes = sb.notes.as_deref().map(str::trim).filter(|n| !n.is_empty());
+        match sb.description.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+            Some(description) => body.push_str(&paragraph("Action", description, notes)),
+            None => {
+                if let Some(notes) = notes {
+                    body.push_str(&paragraph("Action", notes, None));
+                }
+            }
+        }
+
+        for line in sb.dialogue.as_deref().unwrap_or("").lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let Some(speaker) = parse_speaker(line) else {
+                body.push_str(&paragraph("Action", line, None));
+                continue;
+            };
+            let text = line.split_once(['：', ':']).map(|(_, t)| t.trim()).unwrap_or(line);
+            match speaker.find(['（', '(']) {
+                Some(pos) if pos > 0 => {
+                    body.push_str(&paragraph("Character", speaker[..pos].trim(), None));
+                    body.push_str(&paragraph("Parenthetical", speaker[pos..].trim(), None));
+                }
+                _ => body.push_str(&paragraph("Character", speaker, None)),
+            }
+            body.push_str(&paragraph("Dialogue", text, None));
+        }
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\" ?>\n\
+         <FinalDraft DocumentType=\"Script\" Template=\"No\" Version=\"5\">\n\
+         \x20 <Content>\n{}  </Content>\n\
+         \x20 <TitlePage>\n    <Content>\n{}    </Content>\n  </TitlePage>\n\
+         </FinalDraft>\n",
+        body,
+        paragraph("General", title, None)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fdx_round_trip() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<FinalDraft DocumentType="Script" Version="5">
+  <Content>
+    <Paragraph Type="Scene Heading" Number="7"><Text>INT. LAB - NIGHT</Text></Paragraph>
+    <Paragraph Type="Action"><Text>Sparks </Text><Text Style="Bold">fly</Text><Text>.</Text></Paragraph>
+    <Paragraph Type="Character"><Text>ADA</Text></Paragraph>
+    <Paragraph Type="Parenthetical"><Text>(whispering)</Text></Paragraph>
+    <Paragraph Type="Dialogue"><Text>It works.</Text></Paragraph>
+  </Content>
+  <TitlePage><Content><Paragraph><Text>Spark</Text></Paragraph></Content></TitlePage>
+</FinalDraft>"#;
+        let play = parse_fdx(xml).unwrap();
+        assert_eq!(play.title.as_deref(), Some("Spark"));
+        assert_eq!(play.elements.len(), 5);
+        assert_eq!(play.elements[0].number.as_deref(), Some("7"));
+        assert_eq!(play.elements[1].text, "Sparks fly.");
+        assert_eq!(play.characters(), vec!["ADA"]);
+
+        let scenes = vec![Scene { name: "LAB".to_string(), description: Some("INT. LAB - NIGHT".to_string()), ..Default::default() }];
+        let storyboards = vec![Storyboard {
+            sequence_number: 1,
+            mirror_id: "A1".to_string(),
+            shot_size: Some("近景".to_string()),
+            description: Some("#LAB 火花 & 烟雾".to_string()),
+            notes: Some("补光".to_string()),
+            dialogue: Some("ADA（低声）：It works.".to_string()),
+            ..Default::default()
+        }];
+        let written = write_fdx("Spark", &storyboards, &scenes);
+        let reparsed = parse_fdx(&written).unwrap();
+        let kinds: Vec<ElementKind> = reparsed.elements.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![
+            ElementKind::SceneHeading, ElementKind::Action, ElementKind::Action,
+            ElementKind::Character, ElementKind::Parenthetical, ElementKind::Dialogue,
+        ]);
+        assert_eq!(reparsed.elements[0].text, "INT. LAB - NIGHT");
+        assert_eq!(reparsed.elements[1].text, "A1 / 近景");
+        assert_eq!(reparsed.elements[2].text, "#LAB 火花 & 烟雾");
+        assert!(written.contains("<ScriptNote"));
+        assert_eq!(reparsed.title.as_deref(), Some("Spark"));
+    }
+}
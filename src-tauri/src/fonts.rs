@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+
+/// 常见系统中文字体位置（Windows / macOS / Linux）
+const CJK_FONT_CANDIDATES: &[&str] = &[
+    "C:/Windows/Fonts/msyh.ttc",
+    "C:/Windows/Fonts/msyh.ttf",
+    "C:/Windows/Fonts/simhei.ttf",
+    "C:/Windows/Fonts/simsun.ttc",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Medium.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+];
+
+/// 查找可用的中文字体：优先使用指定路径，否则按常见系统字体位置查找
+pub fn find_cjk_font(explicit: Option<&str>) -> Option<PathBuf> {
+    if let Some(path) = explicit.filter(|p| !p.trim().is_empty()) {
+        let path = PathBuf::from(path);
+        return path.exists().then_some(path);
+    }
+    CJK_FONT_CANDIDATES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.exists())
+}
+
+/// 读取字体文件数据
+pub fn load_font_data(explicit: Option<&str>) -> Result<(PathBuf, Vec<u8>), String> {
+    let path = find_cjk_font(explicit)
+        .ok_or_else(|| "找不到可用的中文字体，请指定字体文件路径".to_string())?;
+    let data = std::fs::read(&path)
+        .map_err(|e| format!("读取字体失败 {}: {}", path.display(), e))?;
+    Ok((path, data))
+}
@@ -0,0 +1,304 @@
+use crate::screenplay::{character_name, is_scene_heading, ElementKind, Screenplay, ScriptElement};
+
+/// 去掉注释块 /* */ 与备注 [[ ]]
+fn strip_comments(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    loop {
+        let next = [rest.find("/*").map(|p| (p, "*/")), rest.find("[[").map(|p| (p, "]]"))]
+            .into_iter()
+            .flatten()
+            .min_by_key(|(p, _)| *p);
+        match next {
+            Some((start, end)) => {
+                result.push_str(&rest[..start]);
+                rest = match rest[start + 2..].find(end) {
+                    Some(len) => &rest[start + 2 + len + 2..],
+                    None => "",
+                };
+            }
+            None => {
+                result.push_str(rest);
+                return result;
+            }
+        }
+    }
+}
+
+/// 标题页字段
+const TITLE_PAGE_KEYS: &[&str] = &[
+    "Title", "Credit", "Author", "Authors", "Source", "Draft date", "Date", "Contact", "Copyright", "Notes", "Revision",
+];
+
+/// 解析开头的标题页（Key: Value），返回标题与正文起始行
+fn parse_title_page(lines: &[&str]) -> (Option<String>, usize) {
+    let is_key = |line: &str| {
+        line.split_once(':').is_some_and(|(key, _)| {
+            TITLE_PAGE_KEYS.iter().any(|k| key.trim().eq_ignore_ascii_case(k))
+        })
+    };
+    if !lines.first().is_some_and(|line| is_key(line)) {
+        return (None, 0);
+    }
+
+    let mut title: Option<String> = None;
+    let mut in_title = false;
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            return (title, i + 1);
+        }
+        if is_key(line) && !line.starts_with([' ', '\t']) {
+            let (key, value) = line.split_once(':').unwrap_or_default();
+            in_title = key.trim().eq_ignore_ascii_case("title");
+            if in_title && !value.trim().is_empty() {
+                title = Some(value.trim().to_string());
+            }
+        } else if in_title {
+            let value = line.trim();
+            title = Some(match title {
+                Some(t) => format!("{} {}", t, value),
+                None => value.to_string(),
+            });
+        }
+    }
+    (title, lines.len())
+}
+
+/// 英文角色提示行：全大写，可带 (V.O.) 等扩展
+fn is_character_cue(line: &str) -> bool {
+    let name = line.split('(').next().unwrap_or_default().trim().trim_end_matches('^');
+    !name.is_empty()
+        && name.chars().count() <= 40
+        && name.chars().any(|c| c.is_ascii_uppercase())
+        && !name.chars().any(|c| c.is_lowercase())
+        && !name.ends_with(':')
+}
+
+/// 冒号前常见的非角色标注（“时间：夜”“地点：客厅”等）
+const COLON_STOP_WORDS: &[&str] = &[
+    "时间", "地点", "场景", "旁白", "人物", "画面", "字幕", "镜头", "备注", "说明", "注释", "提示",
+    "背景", "日期", "天气", "音效", "音乐", "道具", "标题", "内容", "第", "说", "问", "喊",
+];
+
+/// “角色：台词”写法，返回（角色提示, 括号说明, 台词）
+/// 角色名须是已出现过的角色，或全大写英文名、不含标注词的短中文名
+fn split_colon_dialogue(line: &str, known: &[String]) -> Option<(String, Option<String>, String)> {
+    let (cue, text) = line.split_once('：').or_else(|| line.split_once(':'))?;
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let (name, paren) = match cue.find(['（', '(']) {
+        Some(pos) => (&cue[..pos], Some(cue[pos..].trim().to_string())),
+        None => (cue, None),
+    };
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name.chars().count() <= 12
+        && !name.chars().any(|c| c.is_lowercase() || c.is_ascii_digit() || c.is_whitespace() || "，。！？、,.!?\"“”".contains(c));
+    let all_caps = name.chars().all(|c| c.is_ascii_uppercase() || "'-.".contains(c));
+    // 中文名不超过四个字，带间隔号的译名放宽到八个字
+    let short_cjk = name.chars().count() <= if name.contains('·') { 8 } else { 4 }
+        && name.chars().all(|c| ('\u{4e00}'..='\u{9fff}').contains(&c) || c == '·')
+        && !COLON_STOP_WORDS.iter().any(|w| name.contains(w));
+    let accepted = valid && (known.iter().any(|k| k == name) || all_caps || short_cjk);
+    accepted.then(|| (name.to_string(), paren, text.to_string()))
+}
+
+/// 解析 Fountain 剧本（兼容中文“内景/外景”场景标题与“角色：台词”对白）
+pub fn parse_fountain(text: &str) -> Screenplay {
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n").replace('\r', "\n");
+    let text = strip_comments(&text);
+    let lines: Vec<&str> = text.lines().collect();
+    let (title, start) = parse_title_page(&lines);
+
+    let mut elements: Vec<ScriptElement> = Vec::new();
+    let mut in_dialogue = false;
+    let mut prev_blank = true;
+    let mut prev_action = false;
+    // 已出现的角色名，用于识别“角色：台词”
+    let mut known: Vec<String> = Vec::new();
+
+    for (i, raw) in lines.iter().enumerate().skip(start) {
+        let line = raw.trim();
+        let next_blank = lines.get(i + 1).is_none_or(|l| l.trim().is_empty());
+
+        if line.is_empty() {
+            in_dialogue = false;
+            prev_blank = true;
+            prev_action = false;
+            continue;
+        }
+        let was_blank = prev_blank;
+        prev_blank = false;
+
+        // 章节、梗概、分页符不进入正文
+        if line.starts_with('#') || line.starts_with('=') {
+            continue;
+        }
+
+        if in_dialogue {
+            let kind = if line.starts_with('(') && line.ends_with(')') {
+                ElementKind::Parenthetical
+            } else {
+                ElementKind::Dialogue
+            };
+            match elements.last_mut() {
+                Some(last) if kind == ElementKind::Dialogue && last.kind == ElementKind::Dialogue => {
+                    last.text.push('\n');
+                    last.text.push_str(line);
+                }
+                _ => elements.push(ScriptElement::new(kind, line)),
+            }
+            continue;
+        }
+
+        let forced_heading = line.starts_with('.') && !line.starts_with("..");
+        if forced_heading || (was_blank && is_scene_heading(line)) {
+            let heading = if forced_heading { &line[1..] } else { line };
+            let mut element = ScriptElement::new(ElementKind::SceneHeading, heading.trim());
+            // 场号：INT. HOUSE - DAY #12#
+            if let Some(body) = heading.trim().strip_suffix('#') {
+                if let Some(pos) = body.rfind('#') {
+                    element.text = body[..pos].trim().to_string();
+                    element.number = Some(body[pos + 1..].trim().to_string());
+                }
+            }
+            elements.push(element);
+            prev_action = false;
+            continue;
+        }
+
+        if let Some(transition) = line.strip_prefix('>') {
+            if let Some(centered) = transition.strip_suffix('<') {
+                elements.push(ScriptElement::new(ElementKind::Action, centered.trim()));
+            } else {
+                elements.push(ScriptElement::new(ElementKind::Transition, transition.trim()));
+            }
+            prev_action = false;
+            continue;
+        }
+        if was_blank && next_blank && line.ends_with("TO:") && is_character_cue(line.trim_end_matches(':')) {
+            elements.push(ScriptElement::new(ElementKind::Transition, line));
+            prev_action = false;
+            continue;
+        }
+
+        if let Some(action) = line.strip_prefix('!') {
+            elements.push(ScriptElement::new(ElementKind::Action, action.trim()));
+            prev_action = true;
+            continue;
+        }
+
+        let forced_character = line.starts_with('@');
+        if was_blank && !next_blank && (forced_character || is_character_cue(line)) {
+            let cue = line.trim_start_matches('@').trim();
+            let name = character_name(cue);
+            if !known.contains(&name) {
+                known.push(name);
+            }
+            elements.push(ScriptElement::new(ElementKind::Character, cue));
+            in_dialogue = true;
+            prev_action = false;
+            continue;
+        }
+
+        if let Some((name, paren, dialogue)) = split_colon_dialogue(line, &known) {
+            if !known.contains(&name) {
+                known.push(name.clone());
+            }
+            elements.push(ScriptElement::new(ElementKind::Character, name));
+            if let Some(paren) = paren {
+                elements.push(ScriptElement::new(ElementKind::Parenthetical, paren));
+            }
+            elements.push(ScriptElement::new(ElementKind::Dialogue, dialogue));
+            prev_action = false;
+            continue;
+        }
+
+        // 连续的动作行合并为一段
+        match elements.last_mut() {
+            Some(last) if prev_action && last.kind == ElementKind::Action => {
+                last.text.push('\n');
+                last.text.push_str(raw.trim_end());
+            }
+            _ => elements.push(ScriptElement::new(ElementKind::Action, raw.trim_end())),
+        }
+        prev_action = true;
+    }
+
+    Screenplay { title, elements }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screenplay::chunk_scenes;
+
+    #[test]
+    fn test_parse_fountain() {
+        let script = "Title: The Test\nAuthor: Someone\n\n\
+            INT. KITCHEN - NIGHT #1#\n\n\
+            Rain hits the window. /* cut this */\n\n\
+            JOHN (V.O.)\n(quietly)\nWhere is it?\n\n\
+            MARY\nGone.\n\n\
+            CUT TO:\n\n\
+            3. 外景 街道 日\n\n\
+            小明（低声）：快走！\n";
+        let play = parse_fountain(script);
+        assert_eq!(play.title.as_deref(), Some("The Test"));
+        assert_eq!(play.characters(), vec!["JOHN", "MARY", "小明"]);
+
+        let scenes = play.scenes();
+        assert_eq!(scenes.len(), 2);
+        assert_eq!(scenes[0].number.as_deref(), Some("1"));
+        assert_eq!(scenes[0].location.as_deref(), Some("KITCHEN"));
+        assert_eq!(scenes[0].characters, vec!["JOHN", "MARY"]);
+        assert!(scenes[0].text.contains("JOHN：Where is it?"));
+        assert!(!scenes[0].text.contains("cut this"));
+        assert!(play.elements.iter().any(|e| e.kind == ElementKind::Transition && e.text == "CUT TO:"));
+        assert_eq!(scenes[1].location.as_deref(), Some("街道"));
+        assert!(scenes[1].text.contains("小明：快走！"));
+
+        let chunks = chunk_scenes(&scenes, 2000);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!((chunks[0].scene_start, chunks[0].scene_end), (0, 1));
+    }
+
+    #[test]
+    fn test_colon_lines_that_are_not_dialogue() {
+        let script = "内景 客厅 夜\n\n\
+            时间：深夜\n\n\
+            地点：老宅客厅\n\n\
+            旁白：那一年，雨下个不停。\n\n\
+            画面说明：窗外闪电\n\n\
+            他低声说：我们走吧。\n\n\
+            Note: keep the lights low\n\n\
+            欧阳·娜娜：你来了。\n\n\
+            JOHN：Hi.\n";
+        let play = parse_fountain(script);
+        assert_eq!(play.characters(), vec!["欧阳·娜娜", "JOHN"]);
+        let actions: Vec<&str> = play.elements.iter()
+            .filter(|e| e.kind == ElementKind::Action)
+            .map(|e| e.text.as_str())
+            .collect();
+        assert_eq!(actions, vec![
+            "时间：深夜", "地点：老宅客厅", "旁白：那一年，雨下个不停。", "画面说明：窗外闪电",
+            "他低声说：我们走吧。", "Note: keep the lights low",
+        ]);
+    }
+
+    #[test]
+    fn test_colon_dialogue_for_known_character() {
+        // 超过四个字的角色名在以提示行出现过后可用冒号写法
+        let script = "内景 客厅 夜\n\n\
+            @司马光明大人\n\
+            来了。\n\n\
+            司马光明大人：坐吧。\n\n\
+            司马光明大人的随从：是。\n";
+        let play = parse_fountain(script);
+        assert_eq!(play.characters(), vec!["司马光明大人"]);
+        assert!(play.elements.iter().any(|e| e.kind == ElementKind::Dialogue && e.text == "坐吧。"));
+        assert!(play.elements.iter().any(|e| e.kind == ElementKind::Action && e.text == "司马光明大人的随从：是。"));
+    }
+}
@@ -0,0 +1,303 @@
+use crate::animatic::sanitize_file_name;
+use crate::thumbnails::{encode_jpeg, load_thumbnail};
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// 分镜图与参考图缩略图的最长边（像素）
+const SHOT_THUMBNAIL_EDGE: u32 = 960;
+const ASSET_THUMBNAIL_EDGE: u32 = 480;
+
+/// 审阅包页面：数据由 data.js 注入（file:// 下浏览器不允许 fetch 本地 JSON）
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html lang="zh-CN">
+<head>
+<meta charset="UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{TITLE}}</title>
+<style>
+  * { box-sizing: border-box; }
+  body { margin: 0; font-family: -apple-system, "PingFang SC", "Microsoft YaHei", "Noto Sans CJK SC", sans-serif; background: #f4f5f7; color: #222; }
+  header { background: #1f2430; color: #fff; padding: 16px 24px; }
+  header h1 { margin: 0; font-size: 20px; }
+  header .meta { font-size: 12px; color: #aab; margin-top: 4px; }
+  nav { display: flex; gap: 4px; padding: 0 24px; background: #2a3040; }
+  nav button { background: none; border: 0; color: #ccd; padding: 10px 14px; cursor: pointer; font-size: 14px; }
+  nav button.active { color: #fff; border-bottom: 2px solid #4c8dff; }
+  main { padding: 20px 24px; }
+  .grid { display: grid; grid-template-columns: repeat(auto-fill, minmax(360px, 1fr)); gap: 16px; }
+  .card { background: #fff; border-radius: 8px; box-shadow: 0 1px 3px rgba(0,0,0,.12); overflow: hidden; }
+  .frames { display: flex; gap: 2px; background: #111; }
+  .frames figure { flex: 1; margin: 0; aspect-ratio: var(--ratio, 16 / 9); display: flex; align-items: center; justify-content: center; }
+  .frames img { max-width: 100%; max-height: 100%; cursor: zoom-in; }
+  .placeholder { color: #888; font-size: 13px; }
+  .body { padding: 10px 12px; font-size: 13px; line-height: 1.5; }
+  .head { display: flex; gap: 8px; align-items: baseline; margin-bottom: 6px; }
+  .head b { font-size: 16px; }
+  .tag { background: #eef2ff; color: #3451b2; border-radius: 4px; padding: 0 6px; font-size: 12px; }
+  .field { margin: 4px 0; white-space: pre-wrap; }
+  .field span { color: #888; margin-right: 4px; }
+  details { margin-top: 6px; color: #555; }
+  details summary { cursor: pointer; color: #888; }
+  .asset img { width: 100%; aspect-ratio: 1; object-fit: cover; background: #ddd; cursor: zoom-in; }
+  .style { background: #fff; border-radius: 8px; padding: 16px; font-size: 14px; }
+  #lightbox { position: fixed; inset: 0; background: rgba(0,0,0,.85); display: none; align-items: center; justify-content: center; cursor: zoom-out; }
+  #lightbox img { max-width: 95vw; max-height: 95vh; }
+</style>
+</head>
+<body>
+<header><h1>{{TITLE}}</h1><div class="meta" id="meta"></div></header>
+<nav id="tabs"></nav>
+<main id="content"></main>
+<div id="lightbox" onclick="this.style.display='none'"><img alt=""></div>
+<script src="data.js"></script>
+<script>
+(function () {
+  var data = window.STORYBOARD_DATA;
+  var tabs = [
+    ["storyboards", "分镜 (" + data.storyboards.length + ")"],
+    ["characters", "角色 (" + data.characters.length + ")"],
+    ["scenes", "场景 (" + data.scenes.length + ")"],
+    ["props", "道具 (" + data.props.length + ")"],
+    ["style", "风格设置"]
+  ];
+  function esc(text) {
+    return String(text == null ? "" : text).replace(/[&<>"]/g, function (c) {
+      return { "&": "&amp;", "<": "&lt;", ">": "&gt;", '"': "&quot;" }[c];
+    });
+  }
+  function field(label, value) {
+    return value ? '<div class="field"><span>' + label + '</span>' + esc(value) + '</div>' : "";
+  }
+  function image(src, label) {
+    return src ? '<img src="' + esc(src) + '" alt="' + esc(label) + '" loading="lazy">' : '<span class="placeholder">未生成</span>';
+  }
+  function prompts(item, keys) {
+    var rows = keys.map(function (k) { return field(k[1], item[k[0]]); }).join("");
+    return rows ? "<details><summary>提示词</summary>" + rows + "</details>" : "";
+  }
+  function renderStoryboards() {
+    return '<div class="grid">' + data.storyboards.map(function (s) {
+      var tags = [s.shot_size, s.shot_type, s.duration != null ? s.duration + "s" : ""].filter(Boolean)
+        .map(function (t) { return '<span class="tag">' + esc(t) + "</span>"; }).join("");
+      return '<div class="card"><div class="frames"><figure>' + image(s.first_image, s.mirror_id + " 首帧") +
+        "</figure>" + (s.last_image ? "<figure>" + image(s.last_image, s.mirror_id + " 尾帧") + "</figure>" : "") +
+        '</div><div class="body"><div class="head"><b>' + esc(s.mirror_id) + "</b>" + tags + "</div>" +
+        field("对白", s.dialogue) + field("画面", s.description) + field("备注", s.notes) +
+        prompts(s, [["image_prompt_zh", "首帧(中)"], ["image_prompt_en", "首帧(英)"], ["image_prompt_tail_zh", "尾帧(中)"],
+          ["image_prompt_tail_en", "尾帧(英)"], ["video_prompt_zh", "视频(中)"], ["video_prompt_en", "视频(英)"]]) +
+        "</div></div>";
+    }).join("") + "</div>";
+  }
+  function renderAssets(items) {
+    return '<div class="grid">' + items.map(function (a) {
+      return '<div class="card asset">' + (a.image ? image(a.image, a.name) : "") + '<div class="body"><div class="head"><b>' +
+        esc(a.name) + "</b></div>" + field("描述", a.description) + field("备注", a.notes) +
+        prompts(a, [["image_prompt_zh", "中文"], ["image_prompt_en", "英文"]]) + "</div></div>";
+    }).join("") + "</div>";
+  }
+  function renderStyle() {
+    var s = data.style;
+    return '<div class="style">' + field("风格提示词", s.style_prompt) + field("画质提示词", s.quality_prompt) +
+      field("画幅", s.aspect_ratio) + field("分辨率", s.resolution) + field("负面提示词", s.negative_prompt) + "</div>";
+  }
+  function show(name) {
+    var html = name === "storyboards" ? renderStoryboards() : name === "style" ? renderStyle() : renderAssets(data[name]);
+    document.getElementById("content").innerHTML = html;
+    Array.prototype.forEach.call(document.querySelectorAll("nav button"), function (b) {
+      b.className = b.getAttribute("data-tab") === name ? "active" : "";
+    });
+  }
+  document.documentElement.style.setProperty("--ratio", data.style.aspect_ratio ? data.style.aspect_ratio.replace(":", " / ") : "16 / 9");
+  document.getElementById("meta").textContent = "导出时间 " + new Date(data.exported_at * 1000).toLocaleString();
+  document.getElementById("tabs").innerHTML = tabs.map(function (t) {
+    return '<button data-tab="' + t[0] + '">' + t[1] + "</button>";
+  }).join("");
+  document.getElementById("tabs").addEventListener("click", function (e) {
+    if (e.target.getAttribute("data-tab")) show(e.target.getAttribute("data-tab"));
+  });
+  document.getElementById("content").addEventListener("click", function (e) {
+    if (e.target.tagName === "IMG") {
+      var box = document.getElementById("lightbox");
+      box.querySelector("img").src = e.target.src;
+      box.style.display = "flex";
+    }
+  });
+  show("storyboards");
+})();
+</script>
+</body>
+</html>
+"#;
+
+/// 审阅包写入器：生成 index.html、data.js / data.json 与 images 目录
+pub struct ReviewPackage {
+    pub output_dir: PathBuf,
+    pub images_dir: PathBuf,
+    pub references_dir: PathBuf,
+    pub warnings: Vec<String>,
+}
+
+impl ReviewPackage {
+    /// data 为项目数据（storyboards / characters / scenes / props / style），
+    /// 其中的图片路径会被替换为包内缩略图的相对路径
+    pub fn write(&mut self, title: &str, mut data: Value) -> Result<(), String> {
+        let images_out = self.output_dir.join("images");
+        fs::create_dir_all(&images_out).map_err(|e| format!("创建目录失败: {}", e))?;
+
+        if let Some(storyboards) = data["storyboards"].as_array_mut() {
+            // 文件名带序号，替换非法字符后同名也不会互相覆盖
+            for (index, sb) in storyboards.iter_mut().enumerate() {
+                let mirror_id = sb["mirror_id"].as_str().unwrap_or_default().to_string();
+                for (source_key, target_key, suffix) in [("image_first_path", "first_image", "first"), ("image_last_path", "last_image", "last")] {
+                    let file = sb[source_key].as_str().map(|f| self.images_dir.join(f));
+                    let name = format!("shot_{:03}_{}_{}.jpg", index + 1, sanitize_file_name(&mirror_id), suffix);
+                    sb[target_key] = self.thumbnail(file, &name, SHOT_THUMBNAIL_EDGE);
+                }
+                for key in ["image_first_path", "image_last_path", "video_path"] {
+                    if let Some(fields) = sb.as_object_mut() {
+                        fields.remove(key);
+                    }
+                }
+            }
+        }
+
+        for (kind, prefix) in [("characters", "character"), ("scenes", "scene"), ("props", "prop")] {
+            if let Some(assets) = data[kind].as_array_mut() {
+                for (index, asset) in assets.iter_mut().enumerate() {
+                    let name = asset["name"].as_str().unwrap_or_default().to_string();
+                    let file = asset["reference_image_path"].as_str().map(|f| self.references_dir.join(f));
+                    let out_name = format!("{}_{:03}_{}.jpg", prefix, index + 1, sanitize_file_name(&name));
+                    asset["image"] = self.thumbnail(file, &out_name, ASSET_THUMBNAIL_EDGE);
+                    if let Some(fields) = asset.as_object_mut() {
+                        fields.remove("reference_image_path");
+                    }
+                }
+            }
+        }
+
+        let json = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
+        fs::write(self.output_dir.join("data.json"), &json)
+            .map_err(|e| format!("写入数据失败: {}", e))?;
+        // 防止数据中的 </script> 提前结束脚本
+        let script = format!("window.STORYBOARD_DATA = {};\n", json.replace("</", "<\\/"));
+        fs::write(self.output_dir.join("data.js"), script)
+            .map_err(|e| format!("写入数据失败: {}", e))?;
+
+        let html = INDEX_HTML.replace("{{TITLE}}", &html_escape(title));
+        fs::write(self.output_dir.join("index.html"), html)
+            .map_err(|e| format!("写入页面失败: {}", e))
+    }
+
+    /// 生成缩略图，返回包内相对路径；图片缺失时返回 null
+    fn thumbnail(&mut self, source: Option<PathBuf>, name: &str, max_edge: u32) -> Value {
+        let Some(source) = source.filter(|p| p.exists()) else {
+            return Value::Null;
+        };
+        let result = load_thumbnail(&source, max_edge)
+            .and_then(|image| encode_jpeg(&image))
+            .and_then(|jpeg| {
+                fs::write(self.output_dir.join("images").join(name), jpeg)
+                    .map_err(|e| format!("写入缩略图失败: {}", e))
+            });
+        match result {
+            Ok(()) => json!(format!("images/{}", name)),
+            Err(e) => {
+                self.warnings.push(e);
+                Value::Null
+            }
+        }
+    }
+}
+
+/// 将目录打包为 zip（目录名作为压缩包内的根目录）
+pub fn zip_directory(dir: &Path, output: &Path) -> Result<(), String> {
+    let root = dir.file_name().and_then(|n| n.to_str()).unwrap_or("package").to_string();
+    let file = File::create(output).map_err(|e| format!("创建压缩包失败: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let mut entries: Vec<_> = fs::read_dir(&current)
+            .map_err(|e| format!("读取目录失败: {}", e))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .collect();
+        entries.sort();
+        for path in entries {
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let relative = path.strip_prefix(dir).map_err(|e| e.to_string())?;
+            let name = format!("{}/{}", root, relative.to_string_lossy().replace('\\', "/"));
+            // 图片本身已压缩，直接存储
+            let method = if name.ends_with(".jpg") { CompressionMethod::Stored } else { CompressionMethod::Deflated };
+            zip.start_file(name, SimpleFileOptions::default().compression_method(method))
+                .map_err(|e| format!("写入压缩包失败: {}", e))?;
+            let bytes = fs::read(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+            zip.write_all(&bytes).map_err(|e| format!("写入压缩包失败: {}", e))?;
+        }
+    }
+
+    zip.finish().map_err(|e| format!("写入压缩包失败: {}", e))?;
+    Ok(())
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_review_package() {
+        let root = std::env::temp_dir().join(format!("storyboard_test_review_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let images_dir = root.join("images_src");
+        fs::create_dir_all(&images_dir).unwrap();
+        image::RgbImage::new(2000, 1000).save(images_dir.join("a1.png")).unwrap();
+
+        let mut package = ReviewPackage {
+            output_dir: root.join("review"),
+            images_dir: images_dir.clone(),
+            references_dir: images_dir,
+            warnings: Vec::new(),
+        };
+        let data = json!({
+            "storyboards": [
+                { "mirror_id": "A1", "image_first_path": "a1.png", "dialogue": "</script>" },
+                { "mirror_id": "A/1", "image_first_path": "a1.png" },
+                { "mirror_id": "A:1", "image_first_path": "a1.png" },
+            ],
+            "characters": [{ "name": "小明", "reference_image_path": null }],
+            "scenes": [],
+            "props": [],
+            "style": {},
+        });
+        package.write("<Demo>", data).unwrap();
+
+        let saved: Value = serde_json::from_str(&fs::read_to_string(root.join("review/data.json")).unwrap()).unwrap();
+        assert_eq!(saved["storyboards"][0]["first_image"], json!("images/shot_001_A1_first.jpg"));
+        assert_eq!(saved["storyboards"][0]["last_image"], Value::Null);
+        // 替换非法字符后同名的镜号各自保留缩略图
+        assert_eq!(saved["storyboards"][1]["first_image"], json!("images/shot_002_A_1_first.jpg"));
+        assert_eq!(saved["storyboards"][2]["first_image"], json!("images/shot_003_A_1_first.jpg"));
+        let thumbnail = image::open(root.join("review/images/shot_001_A1_first.jpg")).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (960, 480));
+        assert!(!fs::read_to_string(root.join("review/data.js")).unwrap().contains("</script>"));
+        assert!(fs::read_to_string(root.join("review/index.html")).unwrap().contains("<title>&lt;Demo&gt;</title>"));
+
+        zip_directory(&root.join("review"), &root.join("review.zip")).unwrap();
+        assert!(fs::read(root.join("review.zip")).unwrap().starts_with(b"PK"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,516 @@
+use crate::async_task::{submit_task, wait_for_task, TaskStatus};
+use crate::models::{ApiConfig, AsyncTaskConfig, ImageSettings};
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{json, Map, Value};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 构建带超时设置的 HTTP 客户端
+pub fn build_agent(read_timeout_secs: u64) -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_read(std::time::Duration::from_secs(read_timeout_secs))
+        .timeout_write(std::time::Duration::from_secs(60))
+        .build()
+}
+
+/// 图片接口厂商类型（决定参数映射方式）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageProvider {
+    /// OpenAI：size 只接受固定枚举，不支持 seed / 负向提示词
+    OpenAi,
+    /// 火山方舟 Seedream：size 为任意 WxH，支持 seed，不支持 n
+    Seedream,
+    /// 其他 OpenAI 兼容中转：参数全部透传
+    Generic,
+}
+
+impl ImageProvider {
+    pub fn detect(api_config: &ApiConfig) -> Self {
+        let base_url = api_config.base_url.to_ascii_lowercase();
+        let model = api_config.model.as_deref().unwrap_or("").to_ascii_lowercase();
+        if base_url.contains("openai") || model.starts_with("dall-e") || model.starts_with("gpt-image") {
+            ImageProvider::OpenAi
+        } else if base_url.contains("volces") || model.contains("seedream") {
+            ImageProvider::Seedream
+        } else {
+            ImageProvider::Generic
+        }
+    }
+}
+
+/// 解析画幅比例，如 "16:9"、"2.39:1"、"2.39"
+pub fn parse_aspect_ratio(aspect_ratio: &str) -> Option<f64> {
+    let ratio = match aspect_ratio.split_once(':') {
+        Some((w, h)) => w.trim().parse::<f64>().ok()? / h.trim().parse::<f64>().ok()?,
+        None => aspect_ratio.trim().parse::<f64>().ok()?,
+    };
+    (ratio.is_finite() && ratio > 0.0).then_some(ratio)
+}
+
+/// WxH 写法允许的边长范围（像素）
+const MIN_EDGE: u32 = 64;
+const MAX_EDGE: u32 = 8192;
+
+/// 解析 WxH 写法的分辨率（如 1920x1080），边长超出范围时返回 None
+pub fn parse_resolution(resolution: &str) -> Option<(u32, u32)> {
+    let resolution = resolution.trim().to_ascii_uppercase();
+    let (w, h) = resolution.split_once('X')?;
+    let (w, h): (u32, u32) = (w.trim().parse().ok()?, h.trim().parse().ok()?);
+    let valid = |edge: u32| (MIN_EDGE..=MAX_EDGE).contains(&edge);
+    (valid(w) && valid(h)).then_some((w, h))
+}
+
+/// 校验分辨率设置：为空、1K / 2K / 4K 或合法的 WxH
+pub fn validate_resolution(resolution: Option<&str>) -> Result<(), String> {
+    let resolution = resolution.unwrap_or("").trim();
+    let preset = ["", "1K", "2K", "4K"].iter().any(|p| p.eq_ignore_ascii_case(resolution));
+    if preset || parse_resolution(resolution).is_some() {
+        Ok(())
+    } else {
+        Err("无效的分辨率".to_string())
+    }
+}
+
+/// 根据画幅与分辨率计算宽高（未设置时保持 1024x1024）
+pub fn resolve_dimensions(settings: &ImageSettings) -> (u32, u32) {
+    let resolution = settings.resolution.as_deref().unwrap_or("").trim().to_ascii_uppercase();
+
+    // 直接指定 WxH 时优先使用
+    if let Some(dimensions) = parse_resolution(&resolution) {
+        return dimensions;
+    }
+
+    let long_edge = match resolution.as_str() {
+        "2K" => 2048.0,
+        "4K" => 4096.0,
+        _ => 1024.0,
+    };
+    let ratio = settings.aspect_ratio.as_deref()
+        .and_then(parse_aspect_ratio)
+        .unwrap_or(1.0);
+
+    // 对齐到 16 的倍数，多数扩散模型要求
+    let align = |v: f64| ((v / 16.0).round() * 16.0).max(16.0) as u32;
+    if ratio >= 1.0 {
+        (align(long_edge), align(long_edge / ratio))
+    } else {
+        (align(long_edge * ratio), align(long_edge))
+    }
+}
+
+/// OpenAI 只接受固定尺寸，按横/竖/方形就近映射
+fn openai_size(model: &str, settings: &ImageSettings) -> &'static str {
+    let (w, h) = resolve_dimensions(settings);
+    let ratio = w as f64 / h as f64;
+    let orientation = if ratio > 1.2 { 1 } else if ratio < 0.83 { -1 } else { 0 };
+    match (model.starts_with("dall-e-3"), model.starts_with("dall-e-2"), orientation) {
+        (_, true, _) | (_, _, 0) => "1024x1024",
+        (true, _, 1) => "1792x1024",
+        (true, _, _) => "1024x1792",
+        (false, _, 1) => "1536x1024",
+        (false, _, _) => "1024x1536",
+    }
+}
+
+/// 将生图参数按厂商映射为请求字段（含 prompt）
+/// 不支持负向提示词的厂商会把负向提示词并入正向提示词
+pub fn image_request_fields(
+    provider: ImageProvider,
+    model: &str,
+    prompt: &str,
+    settings: &ImageSettings,
+) -> Map<String, Value> {
+    let mut fields = Map::new();
+    let mut prompt = prompt.to_string();
+    let negative = settings.negative_prompt.as_deref().filter(|p| !p.trim().is_empty());
+    let (w, h) = resolve_dimensions(settings);
+
+    fields.insert("model".into(), json!(model));
+    match provider {
+        ImageProvider::OpenAi => {
+            fields.insert("size".into(), json!(openai_size(model, settings)));
+            // dall-e-3 每次只能返回一张，多张候选由调用方重复请求
+            let n = if model.starts_with("dall-e-3") { 1 } else { settings.n.unwrap_or(1) };
+            fields.insert("n".into(), json!(n));
+            if let Some(quality) = &settings.quality {
+                fields.insert("quality".into(), json!(quality));
+            }
+            if model.starts_with("dall-e-3") {
+                if let Some(style) = &settings.style {
+                    fields.insert("style".into(), json!(style));
+                }
+            }
+            if let Some(negative) = negative {
+                prompt = format!("{}\nAvoid: {}", prompt, negative);
+            }
+        }
+        ImageProvider::Seedream => {
+            fields.insert("size".into(), json!(format!("{}x{}", w, h)));
+            if let Some(seed) = settings.seed {
+                fields.insert("seed".into(), json!(seed));
+            }
+            fields.insert("watermark".into(), json!(false));
+            if let Some(negative) = negative {
+                prompt = format!("{}\n避免：{}", prompt, negative);
+            }
+        }
+        ImageProvider::Generic => {
+            fields.insert("size".into(), json!(format!("{}x{}", w, h)));
+            fields.insert("n".into(), json!(settings.n.unwrap_or(1)));
+            if let Some(aspect_ratio) = &settings.aspect_ratio {
+                fields.insert("aspect_ratio".into(), json!(aspect_ratio));
+            }
+            if let Some(quality) = &settings.quality {
+                fields.insert("quality".into(), json!(quality));
+            }
+            if let Some(style) = &settings.style {
+                fields.insert("style".into(), json!(style));
+            }
+            if let Some(seed) = settings.seed {
+                fields.insert("seed".into(), json!(seed));
+            }
+            if let Some(negative) = negative {
+                fields.insert("negative_prompt".into(), json!(negative));
+            }
+        }
+    }
+    fields.insert("prompt".into(), json!(prompt));
+
+    fields
+}
+
+/// 调用图片接口，返回全部候选图（URL 或 data URL）
+/// references 非空时走参考图模式：默认 multipart 调用 /v1/images/edits，
+/// reference_mode = "json" 时以 data URL 数组随生成请求提交。
+/// 配置了 async_task 的厂商走提交 → 轮询流程（参考图以 data URL 数组提交）。
+/// 厂商返回的数量少于设置的 n 时会继续请求补齐。
+pub fn request_images(
+    api_config: &ApiConfig,
+    prompt: &str,
+    settings: &ImageSettings,
+    references: &[(PathBuf, Vec<u8>)],
+) -> Result<Vec<String>, String> {
+    let wanted = settings.n.unwrap_or(1).max(1) as usize;
+    let mut images = Vec::new();
+
+    // 最多补请求 wanted 次，防止厂商始终只返回一张时死循环
+    for _ in 0..wanted {
+        let batch = match &api_config.async_task {
+            Some(task) => run_async_image_task(api_config, task, prompt, settings, references)?,
+            None => extract_image_urls(&send_image_request(api_config, prompt, settings, references)?),
+        };
+        if batch.is_empty() {
+            return Err("无法从响应中提取图片 URL".to_string());
+        }
+        images.extend(batch);
+        if images.len() >= wanted {
+            break;
+        }
+    }
+
+    images.truncate(wanted);
+    Ok(images)
+}
+
+/// 以异步任务方式生成图片
+fn run_async_image_task(
+    api_config: &ApiConfig,
+    task: &AsyncTaskConfig,
+    prompt: &str,
+    settings: &ImageSettings,
+    references: &[(PathBuf, Vec<u8>)],
+) -> Result<Vec<String>, String> {
+    let provider = ImageProvider::detect(api_config);
+    let model = api_config.model.as_deref().unwrap_or("");
+    let mut fields = image_request_fields(provider, model, prompt, settings);
+    if !references.is_empty() {
+        fields.insert("image".into(), json!(reference_data_urls(references)));
+    }
+
+    let task_id = submit_task(api_config, task, "/v1/images/generations", Value::Object(fields))
+        .map_err(|e| format!("图片生成请求失败: {}", e))?;
+    match wait_for_task(api_config, task, &task_id, 300, |_| {})? {
+        TaskStatus::Succeeded(urls) => Ok(urls),
+        TaskStatus::Failed(message) => Err(format!("图片生成失败: {}", message)),
+        TaskStatus::Running(status) => Err(format!("图片任务未完成: {}", status)),
+    }
+}
+
+/// 参考图转换为 data URL 数组
+fn reference_data_urls(references: &[(PathBuf, Vec<u8>)]) -> Vec<String> {
+    references.iter()
+        .map(|(file, data)| format!(
+            "data:{};base64,{}",
+            guess_image_mime(file),
+            general_purpose::STANDARD.encode(data)
+        ))
+        .collect()
+}
+
+/// 发送单次图片请求
+fn send_image_request(
+    api_config: &ApiConfig,
+    prompt: &str,
+    settings: &ImageSettings,
+    references: &[(PathBuf, Vec<u8>)],
+) -> Result<Value, String> {
+    let base_url = api_config.base_url.trim_end_matches('/');
+    let api_key = &api_config.api_key;
+    let provider = ImageProvider::detect(api_config);
+    let default_model = if references.is_empty() { "dall-e-3" } else { "gpt-image-1" };
+    let model = api_config.model.as_deref().unwrap_or(default_model);
+    let mut fields = image_request_fields(provider, model, prompt, settings);
+
+    let agent = build_agent(180);
+
+    let response = if references.is_empty() || api_config.reference_mode.as_deref() == Some("json") {
+        if !references.is_empty() {
+            // 厂商兼容格式：生成接口 + image 数组（data URL）
+            fields.insert("image".into(), json!(reference_data_urls(references)));
+        }
+
+        agent.post(&format!("{}/v1/images/generations", base_url))
+            .set("Authorization", &format!("Bearer {}", api_key))
+            .set("Content-Type", "application/json")
+            .send_string(
+                &serde_json::to_string(&Value::Object(fields)).map_err(|e| e.to_string())?
+            )
+    } else {
+        // OpenAI 兼容格式：/v1/images/edits multipart 上传
+        let field = if references.len() > 1 { "image[]" } else { "image" };
+        let mut form = MultipartForm::new();
+        for (key, value) in &fields {
+            let text = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            form = form.text(key, &text);
+        }
+        for (file, data) in references {
+            let file_name = file.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("reference.png");
+            form = form.file(field, file_name, guess_image_mime(file), data);
+        }
+        let (content_type, body) = form.finish();
+
+        agent.post(&format!("{}/v1/images/edits", base_url))
+            .set("Authorization", &format!("Bearer {}", api_key))
+            .set("Content-Type", &content_type)
+            .send_bytes(&body)
+    }.map_err(|e| format!("图片生成请求失败: {}", e))?;
+
+    let response_text = response.into_string()
+        .map_err(|e| format!("读取图片响应失败: {}", e))?;
+
+    serde_json::from_str(&response_text)
+        .map_err(|e| format!("解析图片响应失败: {}", e))
+}
+
+/// 从图片接口响应中提取全部图片
+/// 兼容 data[].url、data[].b64_json 以及部分厂商直接返回的顶层 url / b64_json；
+/// b64_json 会转换为 data URL，便于与普通 URL 统一下载
+pub fn extract_image_urls(response_json: &Value) -> Vec<String> {
+    match response_json["data"].as_array() {
+        Some(items) if !items.is_empty() => items.iter().filter_map(image_from_item).collect(),
+        _ => image_from_item(response_json).into_iter().collect(),
+    }
+}
+
+/// 从单个图片条目中读取 url 或 b64_json
+fn image_from_item(item: &Value) -> Option<String> {
+    item["url"]
+        .as_str()
+        .map(|s| s.to_string())
+        .or_else(|| item["b64_json"].as_str().map(to_data_url))
+}
+
+/// 将 base64 图片数据包装为 data URL
+fn to_data_url(b64: &str) -> String {
+    format!("data:image/png;base64,{}", b64)
+}
+
+/// 获取图片/视频二进制数据：data URL 直接解码，其余按 HTTP 下载
+pub fn fetch_bytes(image_url: &str) -> Result<Vec<u8>, String> {
+    if let Some(rest) = image_url.strip_prefix("data:") {
+        let b64 = rest
+            .split_once(',')
+            .map(|(_, data)| data)
+            .ok_or_else(|| "无效的 data URL".to_string())?;
+        return general_purpose::STANDARD
+            .decode(b64.trim())
+            .map_err(|e| format!("解码 base64 图片失败: {}", e));
+    }
+
+    let response = build_agent(120)
+        .get(image_url)
+        .call()
+        .map_err(|e| format!("下载图片失败: {}", e))?;
+
+    let mut data = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut data)
+        .map_err(|e| format!("读取图片数据失败: {}", e))?;
+    Ok(data)
+}
+
+/// 根据文件头判断图片扩展名
+pub fn sniff_image_extension(data: &[u8]) -> &'static str {
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        "jpg"
+    } else if data.len() > 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        "webp"
+    } else {
+        "png"
+    }
+}
+
+/// 从提示词中解析 #资产名 引用（与前端 /#[一-龥\w]+/ 规则一致）
+pub fn parse_asset_refs(text: &str) -> Vec<String> {
+    let mut refs = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '#' {
+            continue;
+        }
+        let mut name = String::new();
+        while let Some(&next) = chars.peek() {
+            if next.is_alphanumeric() || next == '_' {
+                name.push(next);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        if !name.is_empty() && !refs.contains(&name) {
+            refs.push(name);
+        }
+    }
+    refs
+}
+
+/// 根据扩展名推断图片 MIME 类型
+pub fn guess_image_mime(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("gif") => "image/gif",
+        _ => "image/png",
+    }
+}
+
+/// multipart/form-data 请求体构建器（ureq 2 不内置 multipart）
+pub struct MultipartForm {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+impl Default for MultipartForm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultipartForm {
+    pub fn new() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        MultipartForm {
+            boundary: format!("----StoryboardFormBoundary{:x}", nanos),
+            body: Vec::new(),
+        }
+    }
+
+    /// 添加文本字段
+    pub fn text(mut self, name: &str, value: &str) -> Self {
+        self.body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                self.boundary, name, value
+            )
+            .as_bytes(),
+        );
+        self
+    }
+
+    /// 添加文件字段
+    pub fn file(mut self, name: &str, file_name: &str, mime: &str, data: &[u8]) -> Self {
+        self.body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                self.boundary, name, file_name, mime
+            )
+            .as_bytes(),
+        );
+        self.body.extend_from_slice(data);
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+
+    /// 结束表单，返回 (Content-Type, 请求体)
+    pub fn finish(mut self) -> (String, Vec<u8>) {
+        self.body
+            .extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        (
+            format!("multipart/form-data; boundary={}", self.boundary),
+            self.body,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_asset_refs() {
+        let refs = parse_asset_refs("25岁亚洲男性#张三 坐在沙发上，望向 #客厅 的窗户，#张三 起身");
+        assert_eq!(refs, vec!["张三".to_string(), "客厅".to_string()]);
+    }
+
+    #[test]
+    fn test_extract_image_urls() {
+        let v = json!({ "data": [{ "url": "https://example.com/a.png" }, { "b64_json": "aGVsbG8=" }] });
+        assert_eq!(
+            extract_image_urls(&v),
+            vec!["https://example.com/a.png".to_string(), "data:image/png;base64,aGVsbG8=".to_string()]
+        );
+        assert_eq!(fetch_bytes("data:image/png;base64,aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(extract_image_urls(&json!({ "url": "https://example.com/b.png" })).len(), 1);
+        assert!(extract_image_urls(&json!({ "data": [] })).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_dimensions() {
+        let mut settings = ImageSettings::default();
+        assert_eq!(resolve_dimensions(&settings), (1024, 1024));
+        settings.aspect_ratio = Some("16:9".to_string());
+        assert_eq!(resolve_dimensions(&settings), (1024, 576));
+        settings.aspect_ratio = Some("2.39:1".to_string());
+        settings.resolution = Some("2K".to_string());
+        assert_eq!(resolve_dimensions(&settings), (2048, 864));
+        assert_eq!(openai_size("dall-e-3", &settings), "1792x1024");
+        settings.resolution = Some("1920x1080".to_string());
+        assert_eq!(resolve_dimensions(&settings), (1920, 1080));
+    }
+
+    #[test]
+    fn test_validate_resolution() {
+        for ok in [None, Some(""), Some("2k"), Some("4K"), Some("1920x1080"), Some(" 1024 X 1536 ")] {
+            assert!(validate_resolution(ok).is_ok(), "{:?}", ok);
+        }
+        for bad in ["1920", "1920x", "x1080", "0x0", "1920x1080x2", "20000x1080", "16:9", "abc"] {
+            assert_eq!(validate_resolution(Some(bad)), Err("无效的分辨率".to_string()), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use crate::commands::{generate_storyboard_image, get_global_config, unix_timestamp};
+use crate::db::ProjectDatabase;
+use crate::models::{ApiConfig, ImageJob, ImageJobEvent};
+use crate::review::locked_mirror_ids;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// 批量生图队列的运行时状态（任务本身持久化在项目库 image_jobs 表中）
+#[derive(Default)]
+pub struct ImageQueueState {
+    /// 正在运行队列的项目目录 → 停止标记
+    running: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    /// API 地址 → 下一次允许发起请求的时间（按厂商限流，同一地址的多个配置与多个项目共享）
+    next_request_at: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl ImageQueueState {
+    /// 启动项目队列；已在运行时返回 false
+    /// 登记运行状态后才恢复中断的任务，避免与并发启动的队列争抢刚领取的任务
+    pub fn start(&self, app: AppHandle, folder_path: String, concurrency: usize) -> Result<bool, String> {
+        let stop = {
+            let mut running = self.running.lock().map_err(|e| e.to_string())?;
+            if running.contains_key(&folder_path) {
+                return Ok(false);
+            }
+            let stop = Arc::new(AtomicBool::new(false));
+            running.insert(folder_path.clone(), stop.clone());
+            let recovered = ProjectDatabase::open(&PathBuf::from(&folder_path))
+                .map_err(|e| format!("打开数据库失败: {}", e))
+                .and_then(|db| recover_interrupted_jobs(&db).map_err(|e| format!("恢复生图任务失败: {}", e)));
+            if let Err(e) = recovered {
+                running.remove(&folder_path);
+                return Err(e);
+            }
+            stop
+        };
+
+        let running = self.running.clone();
+        let next_request_at = self.next_request_at.clone();
+        thread::spawn(move || {
+            let workers: Vec<_> = (0..concurrency.max(1))
+                .map(|_| {
+                    let app = app.clone();
+                    let folder_path = folder_path.clone();
+                    let stop = stop.clone();
+                    let next_request_at = next_request_at.clone();
+                    thread::spawn(move || run_worker(&app, &folder_path, &stop, &next_request_at))
+                })
+                .collect();
+            for worker in workers {
+                let _ = worker.join();
+            }
+
+            if let Ok(mut running) = running.lock() {
+                running.remove(&folder_path);
+            }
+            let _ = app.emit("image-queue-finished", queue_event(&folder_path, None, "finished", None));
+        });
+
+        Ok(true)
+    }
+
+    /// 请求停止项目队列（正在执行的任务会完成，剩余任务保持 pending）
+    pub fn stop(&self, folder_path: &str) -> Result<bool, String> {
+        let running = self.running.lock().map_err(|e| e.to_string())?;
+        Ok(match running.get(folder_path) {
+            Some(stop) => {
+                stop.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        })
+    }
+
+    /// 项目队列是否在运行
+    pub fn is_running(&self, folder_path: &str) -> bool {
+        self.running
+            .lock()
+            .map(|running| running.contains_key(folder_path))
+            .unwrap_or(false)
+    }
+}
+
+/// 工作线程：循环领取 pending 任务直到队列为空或被停止
+fn run_worker(
+    app: &AppHandle,
+    folder_path: &str,
+    stop: &AtomicBool,
+    next_request_at: &Mutex<HashMap<String, Instant>>,
+) {
+    let path = PathBuf::from(folder_path);
+    let mut api_cache: HashMap<String, ApiConfig> = HashMap::new();
+
+    while !stop.load(Ordering::SeqCst) {
+        let job = match ProjectDatabase::open(&path).and_then(|db| claim_next_job(&db)) {
+            Ok(Some(job)) => job,
+            Ok(None) => break,
+            Err(e) => {
+                let error = format!("领取生图任务失败: {}", e);
+                let _ = app.emit("image-job-failed", queue_event(folder_path, None, "failed", Some(error)));
+                break;
+            }
+        };
+
+        let _ = app.emit("image-job-started", queue_event(folder_path, Some(&job), "running", None));
+
+        let result = resolve_api_config(&mut api_cache, &job.api_id).and_then(|api_config| {
+            wait_for_rate_limit(next_request_at, &api_config);
+            generate_storyboard_image(
+                folder_path.to_string(),
+                api_config,
+                job.mirror_id.clone(),
+                job.frame.clone(),
+                Some(job.use_references),
+            )
+        });
+
+        let (status, error) = match result {
+            Ok(_) => ("done", None),
+            Err(e) => ("failed", Some(e)),
+        };
+        // 状态未能写回时按失败上报（任务保持 running，下次启动队列时恢复为 pending）
+        let (status, error) = match ProjectDatabase::open(&path)
+            .and_then(|db| finish_job(&db, job.id.unwrap_or_default(), status, error.as_deref()))
+        {
+            Ok(()) => (status, error),
+            Err(e) => ("failed", Some(format!("更新生图任务状态失败: {}", e))),
+        };
+
+        let event_name = if status == "done" { "image-job-completed" } else { "image-job-failed" };
+        let _ = app.emit(event_name, queue_event(folder_path, Some(&job), status, error));
+    }
+}
+
+/// 原子地领取下一个 pending 任务并标记为 running
+fn claim_next_job(db: &ProjectDatabase) -> rusqlite::Result<Option<ImageJob>> {
+    let now = unix_now();
+    let mut stmt = db.conn().prepare(
+        "UPDATE image_jobs SET status = 'running', attempts = attempts + 1, updated_at = ?1
+         WHERE id = (SELECT id FROM image_jobs WHERE status = 'pending' ORDER BY id LIMIT 1)
+         RETURNING id, mirror_id, frame, api_id, use_references, status, attempts, error, created_at, updated_at",
+    )?;
+    let mut rows = stmt.query_map([now], image_job_from_row)?;
+    rows.next().transpose()
+}
+
+/// 记录任务结果
+fn finish_job(db: &ProjectDatabase, job_id: i64, status: &str, error: Option<&str>) -> rusqlite::Result<()> {
+    db.conn().execute(
+        "UPDATE image_jobs SET status = ?1, error = ?2, updated_at = ?3 WHERE id = ?4",
+        rusqlite::params![status, error, unix_now(), job_id],
+    )?;
+    Ok(())
+}
+
+/// 按 id 从全局配置中查找 API 配置
+fn resolve_api_config(cache: &mut HashMap<String, ApiConfig>, api_id: &str) -> Result<ApiConfig, String> {
+    if let Some(api_config) = cache.get(api_id) {
+        return Ok(api_config.clone());
+    }
+    let api_config = get_global_config()?
+        .apis
+        .into_iter()
+        .find(|api| api.id == api_id)
+        .ok_or_else(|| format!("找不到 API 配置: {}", api_id))?;
+    cache.insert(api_id.to_string(), api_config.clone());
+    Ok(api_config)
+}
+
+/// 限流键：同一接口地址的多个 API 配置共用额度
+fn rate_limit_key(api_config: &ApiConfig) -> String {
+    api_config.base_url.trim().trim_end_matches('/').to_ascii_lowercase()
+}
+
+/// 按 API 配置的每分钟请求上限排队等待
+fn wait_for_rate_limit(next_request_at: &Mutex<HashMap<String, Instant>>, api_config: &ApiConfig) {
+    let Some(per_minute) = api_config.rate_limit_per_minute.filter(|n| *n > 0) else {
+        return;
+    };
+    let interval = Duration::from_secs(60) / per_minute;
+
+    let wait = {
+        let Ok(mut next) = next_request_at.lock() else {
+            return;
+        };
+        let now = Instant::now();
+        let key = rate_limit_key(api_config);
+        let slot = next.get(&key).copied().filter(|t| *t > now).unwrap_or(now);
+        next.insert(key, slot + interval);
+        slot - now
+    };
+    if !wait.is_zero() {
+        thread::sleep(wait);
+    }
+}
+
+/// 将查询行映射为生图任务
+pub fn image_job_from_row(row: &rusqlite::Row) -> rusqlite::Result<ImageJob> {
+    Ok(ImageJob {
+        id: Some(row.get(0)?),
+        mirror_id: row.get(1)?,
+        frame: row.get(2)?,
+        api_id: row.get(3)?,
+        use_references: row.get(4)?,
+        status: row.get(5)?,
+        attempts: row.get(6)?,
+        error: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+/// 组装队列事件
+fn queue_event(folder_path: &str, job: Option<&ImageJob>, status: &str, error: Option<String>) -> ImageJobEvent {
+    let (completed, failed, total) = ProjectDatabase::open(&PathBuf::from(folder_path))
+        .and_then(|db| {
+            db.conn().query_row(
+                "SELECT COALESCE(SUM(status = 'done'), 0), COALESCE(SUM(status = 'failed'), 0), COUNT(*)
+                 FROM image_jobs WHERE status != 'cancelled'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+        })
+        .unwrap_or((0, 0, 0));
+
+    ImageJobEvent {
+        folder_path: folder_path.to_string(),
+        job_id: job.and_then(|j| j.id),
+        mirror_id: job.map(|j| j.mirror_id.clone()),
+        frame: job.map(|j| j.frame.clone()),
+        status: status.to_string(),
+        error,
+        completed,
+        failed,
+        total,
+    }
+}
+
+/// 把上次异常退出时遗留的 running 任务恢复为 pending
+pub fn recover_interrupted_jobs(db: &ProjectDatabase) -> rusqlite::Result<usize> {
+    db.conn().execute(
+        "UPDATE image_jobs SET status = 'pending', updated_at = ?1 WHERE status = 'running'",
+        [unix_now()],
+    )
+}
+
+/// 向队列添加任务，已存在相同的 pending/running 任务或镜头已锁定时跳过
+pub fn enqueue_jobs(
+    db: &ProjectDatabase,
+    api_id: &str,
+    mirror_ids: &[String],
+    frames: &[String],
+    use_references: bool,
+) -> rusqlite::Result<usize> {
+    let now = unix_now();
+    let queued: HashSet<(String, String)> = {
+        let mut stmt = db.conn().prepare(
+            "SELECT mirror_id, frame FROM image_jobs WHERE status IN ('pending', 'running')",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    let locked = locked_mirror_ids(db)?;
+
+    let mut added = 0;
+    for mirror_id in mirror_ids.iter().filter(|id| !locked.contains(*id)) {
+        for frame in frames {
+            if queued.contains(&(mirror_id.clone(), frame.clone())) {
+                continue;
+            }
+            db.conn().execute(
+                "INSERT INTO image_jobs (mirror_id, frame, api_id, use_references, status, attempts, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, 'pending', 0, ?5, ?5)",
+                rusqlite::params![mirror_id, frame, api_id, use_references, now],
+            )?;
+            added += 1;
+        }
+    }
+    Ok(added)
+}
+
+fn unix_now() -> i64 {
+    unix_timestamp().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::temp_project;
+
+    #[test]
+    fn test_enqueue_and_claim_jobs() {
+        let dir = temp_project("image_queue");
+        let db = ProjectDatabase::open(&dir).unwrap();
+        let mirror_ids = vec!["A1".to_string(), "A2".to_string()];
+        let frames = vec!["first".to_string(), "last".to_string()];
+
+        assert_eq!(enqueue_jobs(&db, "api", &mirror_ids, &frames, false).unwrap(), 4);
+        // 重复添加时跳过已排队的任务
+        assert_eq!(enqueue_jobs(&db, "api", &mirror_ids, &frames, false).unwrap(), 0);
+
+        let job = claim_next_job(&db).unwrap().unwrap();
+        assert_eq!((job.mirror_id.as_str(), job.frame.as_str()), ("A1", "first"));
+        assert_eq!(job.status, "running");
+        assert_eq!(job.attempts, 1);
+
+        // 模拟重启：running 恢复为 pending 后可再次领取
+        assert_eq!(recover_interrupted_jobs(&db).unwrap(), 1);
+        assert_eq!(claim_next_job(&db).unwrap().unwrap().id, job.id);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rate_limit_shared_by_base_url() {
+        let api = |id: &str, base_url: &str| ApiConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            api_type: "image".to_string(),
+            base_url: base_url.to_string(),
+            api_key: String::new(),
+            model: None,
+            is_default: false,
+            reference_mode: None,
+            rate_limit_per_minute: Some(60_000),
+            async_task: None,
+        };
+        let next_request_at = Mutex::new(HashMap::new());
+        wait_for_rate_limit(&next_request_at, &api("a", "https://api.example.com/"));
+        wait_for_rate_limit(&next_request_at, &api("b", "https://API.example.com"));
+        wait_for_rate_limit(&next_request_at, &api("c", "https://other.example.com"));
+
+        let keys: HashSet<String> = next_request_at.lock().unwrap().keys().cloned().collect();
+        assert_eq!(keys, HashSet::from(["https://api.example.com".to_string(), "https://other.example.com".to_string()]));
+    }
+}
@@ -1,12 +1,41 @@
 mod db;
 mod models;
+mod animatic;
+mod async_task;
+mod boards;
 mod commands;
+mod fdx;
+mod fonts;
+mod fountain;
+mod html_export;
+mod image_api;
+mod image_queue;
+mod pdf_export;
+mod review;
+mod runtime_stats;
+mod screenplay;
+mod script_ingest;
+mod script_pipeline;
+mod source_spans;
+mod spreadsheet_import;
+mod subtitles;
+mod thumbnails;
+mod timeline;
+mod video_api;
+mod vocabulary;
+mod xlsx_export;
 
 use commands::*;
+use image_queue::ImageQueueState;
+use script_pipeline::ScriptPipelineState;
+use video_api::VideoGenerationState;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   tauri::Builder::default()
+    .manage(ImageQueueState::default())
+    .manage(ScriptPipelineState::default())
+    .manage(VideoGenerationState::default())
     .setup(|app| {
       if cfg!(debug_assertions) {
         app.handle().plugin(
@@ -28,6 +57,26 @@ pub fn run() {
       update_project_name,
       save_generated_data,
       get_storyboards,
+      get_episodes,
+      save_episode,
+      delete_episode,
+      save_sequence,
+      delete_sequence,
+      assign_storyboards_to_sequence,
+      set_review_status,
+      get_review_history,
+      get_runtime_stats,
+      get_vocabulary,
+      save_vocabulary_term,
+      delete_vocabulary_term,
+      normalize_storyboard_terms,
+      list_boards,
+      create_board,
+      duplicate_board,
+      switch_board,
+      rename_board,
+      delete_board,
+      compare_boards,
       get_characters,
       get_scenes,
       get_props,
@@ -37,9 +86,43 @@ pub fn run() {
       select_folder,
       save_excel_file,
       save_excel_with_dialog,
+      export_xlsx,
+      export_pdf,
+      export_review_package,
+      preview_spreadsheet_import,
+      import_spreadsheet,
+      import_fountain,
+      import_fdx,
+      ingest_script,
+      upload_script,
+      get_script_excerpt,
+      get_uncovered_passages,
+      export_fdx,
       call_image_api,
+      call_image_api_with_references,
+      set_asset_reference_image,
       download_image,
       update_storyboard_image,
+      generate_storyboard_image,
+      list_storyboard_images,
+      select_storyboard_image,
+      delete_storyboard_image,
+      enqueue_image_jobs,
+      start_image_queue,
+      stop_image_queue,
+      plan_script_generation,
+      start_script_generation,
+      stop_script_generation,
+      get_script_generation,
+      get_image_jobs,
+      cancel_image_jobs,
+      retry_image_jobs,
+      generate_storyboard_video,
+      get_image_settings,
+      save_image_settings,
+      export_animatic,
+      export_subtitles,
+      export_timeline,
       get_project_style,
       save_project_style,
       call_ai_api_with_custom_system,
@@ -1,7 +1,7 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 /// 分镜条目
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Storyboard {
     pub sequence_number: i64,
     pub mirror_id: String,
@@ -20,11 +20,164 @@ pub struct Storyboard {
     pub image_first_path: Option<String>,
     pub image_last_path: Option<String>,
     pub image_status: Option<String>,
+    #[serde(default)]
+    pub video_path: Option<String>,
+    #[serde(default)]
+    pub video_status: Option<String>, // empty, generating, generated, failed
+    /// AI 返回的对应剧本原文摘录，仅用于定位源剧本位置，不入库
+    #[serde(default)]
+    pub source_text: Option<String>,
+    /// 所属序列（sequences.id），为空时归入项目默认序列
+    #[serde(default)]
+    pub sequence_id: Option<i64>,
+    #[serde(default)]
+    pub camera_movement: Option<String>,  // 运镜：横摇、俯仰、推拉、升降、手持…
+    #[serde(default)]
+    pub camera_direction: Option<String>, // 运镜方向：向左、推进、上升…
+    #[serde(default, deserialize_with = "deserialize_focal_length")]
+    pub focal_length: Option<f64>,        // 焦距（毫米）
+    #[serde(default)]
+    pub camera_angle: Option<String>,     // 角度：平视、俯拍、仰拍…
+    #[serde(default)]
+    pub transition: Option<String>,       // 到下一镜的转场：切、叠化…
+    /// 审核状态由 set_review_status 维护，保存生成数据时忽略
+    #[serde(default)]
+    pub review_status: Option<String>,    // draft, in_review, changes_requested, approved, locked
+    #[serde(default)]
+    pub reviewed_by: Option<String>,
+    #[serde(default)]
+    pub reviewed_at: Option<i64>,
+}
+
+/// 焦距兼容 35、"35"、"35mm" 等写法，无法识别时为空
+fn deserialize_focal_length<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    Ok(match Option::<serde_json::Value>::deserialize(deserializer)? {
+        Some(serde_json::Value::Number(n)) => n.as_f64(),
+        Some(serde_json::Value::String(s)) => s.trim()
+            .trim_end_matches(|c: char| c.is_alphabetic() || c.is_whitespace())
+            .parse()
+            .ok(),
+        _ => None,
+    }.filter(|f| *f > 0.0))
+}
+
+/// 剧集
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Episode {
+    pub id: i64,
+    pub name: String,
+    pub sort_order: i64,
+    #[serde(default)]
+    pub sequences: Vec<Sequence>,
+}
+
+/// 序列（场次组），隶属于剧集
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sequence {
+    pub id: i64,
+    pub episode_id: i64,
+    pub name: String,
+    pub prefix: String,               // 镜号前缀，如 SQ2 → SQ2-A1；为空时不加前缀
+    pub sort_order: i64,
+    #[serde(default)]
+    pub storyboard_count: i64,
+}
+
+/// 分镜查询与导出范围：指定剧集、序列、景别 / 镜头类型或审核状态，均为空时为整个项目
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoryboardScope {
+    pub episode_id: Option<i64>,
+    pub sequence_id: Option<i64>,
+    #[serde(default)]
+    pub shot_size: Option<String>,   // 可传别名，按规范值过滤
+    #[serde(default)]
+    pub shot_type: Option<String>,
+    #[serde(default)]
+    pub review_status: Option<String>,
+}
+
+/// 审核记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryboardReview {
+    pub id: i64,
+    pub mirror_id: String,
+    pub status: String,
+    pub reviewer: Option<String>,
+    pub comment: Option<String>,
+    pub created_at: i64,
+}
+
+/// 受控词表词条（景别 / 镜头类型）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyTerm {
+    pub id: Option<i64>,
+    pub category: String,             // shot_size | shot_type | camera_movement | camera_angle | transition
+    pub value: String,                // 规范值，写入分镜
+    pub label_zh: String,
+    pub label_en: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub sort_order: i64,
+}
+
+/// 分镜图片版本（每次生成的一张图）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryboardImage {
+    pub id: Option<i64>,
+    pub mirror_id: String,
+    pub frame: String, // first, last
+    pub file_path: String,
+    pub prompt: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub seed: Option<i64>,
+    pub created_at: i64,
+    pub selected: bool,
+}
+
+/// 批量生图任务
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageJob {
+    pub id: Option<i64>,
+    pub mirror_id: String,
+    pub frame: String,
+    pub api_id: String,
+    pub use_references: bool,
+    pub status: String, // pending, running, done, failed, cancelled
+    pub attempts: i64,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// 生图队列事件（image-job-started / image-job-completed / image-job-failed / image-queue-finished）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageJobEvent {
+    pub folder_path: String,
+    pub job_id: Option<i64>,
+    pub mirror_id: Option<String>,
+    pub frame: Option<String>,
+    pub status: String,
+    pub error: Option<String>,
+    pub completed: i64,
+    pub failed: i64,
+    pub total: i64,
+}
+
+/// 视频生成进度事件（video-generation-progress）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoProgressEvent {
+    pub folder_path: String,
+    pub mirror_id: String,
+    pub status: String, // submitted, running, generated, failed
+    pub task_status: Option<String>,
+    pub error: Option<String>,
 }
 
 /// 角色资产
 /// 支持 AI 可能返回的多种字段名：image_prompt_zh/prompt_cn, image_prompt_en/prompt_en, notes/remarks
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Character {
     pub name: String,
     pub description: Option<String>,
@@ -34,10 +187,13 @@ pub struct Character {
     pub image_prompt_en: Option<String>,
     #[serde(alias = "remarks")]
     pub notes: Option<String>,
+    /// 参考图文件名（位于 .storyboard/assets/references/）
+    #[serde(default)]
+    pub reference_image_path: Option<String>,
 }
 
 /// 场景资产
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Scene {
     pub name: String,
     pub description: Option<String>,
@@ -47,10 +203,13 @@ pub struct Scene {
     pub image_prompt_en: Option<String>,
     #[serde(alias = "remarks")]
     pub notes: Option<String>,
+    /// 参考图文件名（位于 .storyboard/assets/references/）
+    #[serde(default)]
+    pub reference_image_path: Option<String>,
 }
 
 /// 道具资产
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Prop {
     pub name: String,
     pub description: Option<String>,
@@ -60,6 +219,9 @@ pub struct Prop {
     pub image_prompt_en: Option<String>,
     #[serde(alias = "remarks")]
     pub notes: Option<String>,
+    /// 参考图文件名（位于 .storyboard/assets/references/）
+    #[serde(default)]
+    pub reference_image_path: Option<String>,
 }
 
 /// AI 消息
@@ -81,6 +243,46 @@ pub struct ApiConfig {
     pub api_key: String,
     pub model: Option<String>,
     pub is_default: bool,
+    /// 参考图上传方式：edits（multipart 调用 /v1/images/edits，默认）/ json（base64 数组随生成请求提交）
+    #[serde(default)]
+    pub reference_mode: Option<String>,
+    /// 每分钟请求上限（批量生图队列按接口地址限流，为空不限）
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
+    /// 异步任务适配（提交 → 轮询 → 取结果），为空时图片走同步接口、视频走内置格式
+    #[serde(default)]
+    pub async_task: Option<AsyncTaskConfig>,
+}
+
+/// 异步任务接口适配配置
+/// 路径字段使用点号/下标写法（如 data.task_id、output.results[0].url），可用 | 分隔多个候选
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AsyncTaskConfig {
+    /// 提交地址（相对 base_url 或完整 URL），为空时使用该类型接口的默认地址
+    #[serde(default)]
+    pub submit_path: Option<String>,
+    /// 查询地址，{task_id} 会被替换为任务 id
+    pub poll_path: String,
+    /// 查询方法：GET（默认）或 POST
+    #[serde(default)]
+    pub poll_method: Option<String>,
+    pub task_id_path: String,
+    pub status_path: String,
+    pub output_url_path: String,
+    #[serde(default)]
+    pub error_path: Option<String>,
+    /// 表示成功/失败的状态值（不区分大小写），为空时使用常见取值
+    #[serde(default)]
+    pub success_values: Vec<String>,
+    #[serde(default)]
+    pub failure_values: Vec<String>,
+    #[serde(default)]
+    pub poll_interval_secs: Option<u64>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// 合并进提交请求体的额外字段
+    #[serde(default)]
+    pub extra_body: Option<serde_json::Value>,
 }
 
 /// 项目元数据
@@ -117,6 +319,304 @@ pub struct ProjectStyle {
     pub quality_prompt: Option<String>,
 }
 
+/// 项目生图参数（存储于 project_meta）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImageSettings {
+    pub aspect_ratio: Option<String>,    // 1:1, 16:9, 2.39:1, 9:16 ...
+    pub resolution: Option<String>,      // 1K, 2K, 4K 或 1920x1080
+    pub quality: Option<String>,         // 原样透传：standard/hd、low/medium/high 等
+    pub style: Option<String>,           // 原样透传：vivid/natural 等
+    pub n: Option<u32>,                  // 每次生成的候选数量
+    pub seed: Option<i64>,
+    pub negative_prompt: Option<String>,
+}
+
+/// 动态分镜导出参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnimaticOptions {
+    pub format: Option<String>,       // gif, apng, sequence
+    pub width: Option<u32>,           // 画面宽度，默认 960，高度按项目画幅计算
+    pub frame: Option<String>,        // 使用的帧：first, last
+    #[serde(default)]
+    pub captions: bool,               // 是否烧录对白字幕
+    pub font_path: Option<String>,    // 字幕字体，未指定时查找系统中文字体
+    pub default_duration: Option<f64>,
+    pub mirror_ids: Option<Vec<String>>, // 仅导出指定镜头，未指定时导出全部
+    pub output_path: Option<String>,  // 输出文件（序列为目录），默认项目 exports 目录
+}
+
+/// 字幕导出参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubtitleOptions {
+    pub format: Option<String>,       // srt, vtt
+    #[serde(default)]
+    pub merge_same_speaker: bool,     // 合并同一说话人的连续镜头
+    pub skip_empty: Option<bool>,     // 跳过无对白镜头，默认 true
+    pub default_duration: Option<f64>,
+    pub output_path: Option<String>,  // 默认项目 exports 目录
+}
+
+/// 时间线导出参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimelineOptions {
+    pub format: Option<String>,       // edl, fcpxml, otio
+    pub fps: Option<f64>,             // 默认 24，支持 23.976 / 29.97 等
+    pub prefer_video: Option<bool>,   // 已生成视频时优先使用视频，默认 true
+    pub frame: Option<String>,        // 使用图片时的帧：first, last
+    pub default_duration: Option<f64>,
+    pub output_path: Option<String>,  // 默认项目 exports 目录
+}
+
+/// 分镜表 PDF 导出参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PdfOptions {
+    pub layout: Option<String>,       // 2x3, 3x4, single
+    pub frame: Option<String>,        // 使用的帧：first, last
+    pub font_path: Option<String>,    // 中文字体，未指定时查找系统字体
+    pub output_path: Option<String>,  // 默认项目 exports 目录
+}
+
+/// 表格导入：列与字段的映射
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportColumn {
+    pub index: usize,
+    pub header: String,
+    pub field: Option<String>,        // 分镜或资产字段名，None 表示忽略该列
+}
+
+/// 表格导入：工作表映射
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportSheet {
+    pub name: String,
+    pub kind: Option<String>,         // storyboard, character, scene, prop；None 表示跳过
+    pub columns: Vec<ImportColumn>,
+    #[serde(default)]
+    pub row_count: usize,
+    #[serde(default)]
+    pub sample_rows: Vec<Vec<String>>,
+}
+
+/// 表格导入预览 / 结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportPreview {
+    pub sheets: Vec<ImportSheet>,
+    pub storyboard_count: usize,
+    pub character_count: usize,
+    pub scene_count: usize,
+    pub prop_count: usize,
+    pub issues: Vec<String>,
+}
+
+/// 剧本场景
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptScene {
+    pub index: usize,
+    pub number: Option<String>,       // 剧本中的场号
+    pub heading: String,              // 场景标题，如 INT. KITCHEN - NIGHT
+    pub location: Option<String>,     // 由标题提取的地点名，用作场景资产名
+    pub characters: Vec<String>,      // 本场出场角色
+    pub text: String,                 // 本场剧本文本
+}
+
+/// 生成用的剧本分块（一个或多个相邻场景）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptChunk {
+    pub index: usize,
+    pub scene_start: usize,
+    pub scene_end: usize,
+    pub headings: Vec<String>,
+    pub text: String,
+}
+
+/// 剧本解析结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptImportResult {
+    pub title: Option<String>,
+    pub scenes: Vec<ScriptScene>,
+    pub characters: Vec<String>,
+    pub chunks: Vec<ScriptChunk>,
+    pub seeded_scenes: usize,         // 新增的场景资产数
+    pub seeded_characters: usize,     // 新增的角色资产数
+    #[serde(default)]
+    pub document_id: Option<i64>,     // 保存为源文档时的 id
+}
+
+/// 源剧本段落
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceParagraph {
+    pub index: usize,
+    pub kind: String,                 // heading, scene_heading, paragraph
+    pub level: Option<u8>,            // 标题层级（1-6）
+    pub text: String,
+}
+
+/// 剧本文件解析结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestedScript {
+    pub file_name: String,
+    pub format: String,               // docx, pdf, txt, md, fountain
+    pub paragraphs: Vec<SourceParagraph>,
+    pub text: String,                 // 每段一行，标题以 # 标记层级
+    #[serde(default)]
+    pub document_id: Option<i64>,     // 保存到项目后的源文档 id
+}
+
+/// 项目源文档（text 每行对应一个段落，行号即段落序号）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceDocument {
+    pub id: i64,
+    pub file_name: String,
+    pub format: String,
+    pub text: String,
+    pub created_at: i64,
+}
+
+/// 分镜在源文档中的对应位置（行号从 0 开始，含首尾）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryboardSource {
+    pub mirror_id: String,
+    pub document_id: i64,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// 分镜对应的剧本摘录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptExcerpt {
+    pub mirror_id: String,
+    pub document_id: i64,
+    pub file_name: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// 没有任何分镜覆盖的剧本段落
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UncoveredPassage {
+    pub document_id: i64,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// 长剧本分块生成任务（每块对应一次 AI 请求）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationChunk {
+    pub chunk_index: i64,
+    pub prefix: String,               // 本块镜号前缀：A, B, C … Z, AA …
+    pub headings: Vec<String>,        // 本块包含的场景标题
+    pub text: String,
+    pub status: String,               // pending, running, done, failed
+    pub error: Option<String>,
+    pub storyboard_count: i64,
+    pub attempts: i64,
+    pub updated_at: i64,
+    pub document_id: Option<i64>,     // 分块来源的源文档
+}
+
+/// 分块生成事件（script-chunk-started / script-chunk-completed / script-chunk-failed / script-generation-finished）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationChunkEvent {
+    pub folder_path: String,
+    pub chunk_index: Option<i64>,
+    pub prefix: Option<String>,
+    pub status: String,
+    pub error: Option<String>,
+    pub completed: i64,
+    pub failed: i64,
+    pub total: i64,
+}
+
+/// 分镜版本（同一项目中的备选剪辑方案，共享资产表）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Board {
+    pub id: i64,
+    pub name: String,
+    pub active: bool,
+    pub storyboard_count: usize,
+    pub total_duration: f64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// 两个版本中同一镜号的字段差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardFieldChange {
+    pub mirror_id: String,
+    pub field: String,
+    pub a: Option<String>,
+    pub b: Option<String>,
+}
+
+/// 版本对比结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardComparison {
+    pub board_a: Board,
+    pub board_b: Board,
+    pub only_in_a: Vec<String>,          // 仅在 A 中的镜号
+    pub only_in_b: Vec<String>,          // 仅在 B 中的镜号
+    pub changes: Vec<BoardFieldChange>,
+    pub reordered: Vec<String>,          // 两版都有但前后顺序不同的镜号
+    pub unchanged: usize,
+}
+
+/// 时长统计参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuntimeOptions {
+    pub default_duration: Option<f64>,   // 未填时长的镜头按此计入，默认 3 秒
+    pub chars_per_second: Option<f64>,   // 中文语速（字/秒），默认 4
+    pub words_per_second: Option<f64>,   // 英文语速（词/秒），默认 2.5
+}
+
+/// 单个序列的时长
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceRuntime {
+    pub sequence_id: Option<i64>,
+    pub name: String,                    // 剧集名 / 序列名
+    pub shot_count: usize,
+    pub duration: f64,
+}
+
+/// 各景别的镜头数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShotSizeCount {
+    pub shot_size: String,               // 空字符串表示未标注
+    pub count: usize,
+    pub duration: f64,
+}
+
+/// 时长不足以说完对白的镜头
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortShot {
+    pub mirror_id: String,
+    pub duration: f64,
+    pub estimated_duration: f64,
+}
+
+/// 项目时长统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeStats {
+    pub shot_count: usize,
+    pub untimed_shot_count: usize,       // 未填时长、按默认时长计入的镜头数
+    pub total_duration: f64,
+    pub average_shot_length: f64,
+    pub sequences: Vec<SequenceRuntime>,
+    pub shot_sizes: Vec<ShotSizeCount>,
+    pub dialogue_shot_count: usize,
+    pub estimated_dialogue_duration: f64, // 按语速估算的对白总时长
+    pub short_shots: Vec<ShortShot>,
+}
+
+/// 导出结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportResult {
+    pub output_path: String,
+    pub item_count: usize,
+    pub total_duration: Option<f64>,
+    pub warnings: Vec<String>,
+}
+
 /// 全局配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalConfig {
@@ -0,0 +1,452 @@
+use crate::models::Storyboard;
+use crate::thumbnails::{encode_jpeg, load_thumbnail};
+use pdf_writer::types::{CidFontType, FontFlags, SystemInfo, UnicodeCmap};
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// 分镜表中的一格
+pub struct SheetEntry<'a> {
+    pub storyboard: &'a Storyboard,
+    /// 帧图片完整路径；None 或 image_status 为 empty 时绘制占位框
+    pub image: Option<PathBuf>,
+}
+
+/// 版式：页面尺寸（磅）、列数、行数、正文字号
+struct SheetLayout {
+    page_width: f32,
+    page_height: f32,
+    columns: usize,
+    rows: usize,
+    font_size: f32,
+    /// 嵌入图片的最长边（像素）
+    image_edge: u32,
+}
+
+impl SheetLayout {
+    /// 2x3、3x4 为 A4 竖版（列 x 行），single 为 A4 横版每页一镜
+    fn parse(layout: &str) -> Result<Self, String> {
+        match layout {
+            "2x3" => Ok(SheetLayout { page_width: 595.0, page_height: 842.0, columns: 2, rows: 3, font_size: 9.0, image_edge: 800 }),
+            "3x4" => Ok(SheetLayout { page_width: 595.0, page_height: 842.0, columns: 3, rows: 4, font_size: 7.0, image_edge: 560 }),
+            "single" => Ok(SheetLayout { page_width: 842.0, page_height: 595.0, columns: 1, rows: 1, font_size: 12.0, image_edge: 1600 }),
+            other => Err(format!("不支持的版式: {}", other)),
+        }
+    }
+
+    fn per_page(&self) -> usize {
+        self.columns * self.rows
+    }
+}
+
+const MARGIN: f32 = 36.0;
+const HEADER_HEIGHT: f32 = 28.0;
+const GAP: f32 = 12.0;
+
+/// 嵌入字体：记录用到的字形，最后子集化写入
+struct EmbeddedFont<'a> {
+    data: &'a [u8],
+    face: ttf_parser::Face<'a>,
+    /// 字形 id → 对应字符（用于 ToUnicode，保证可复制文字）
+    glyphs: BTreeMap<u16, char>,
+}
+
+impl<'a> EmbeddedFont<'a> {
+    fn new(data: &'a [u8]) -> Result<Self, String> {
+        let face = ttf_parser::Face::parse(data, 0).map_err(|e| format!("解析字体失败: {}", e))?;
+        Ok(EmbeddedFont { data, face, glyphs: BTreeMap::new() })
+    }
+
+    fn glyph(&self, c: char) -> u16 {
+        self.face.glyph_index(c).map(|g| g.0).unwrap_or(0)
+    }
+
+    fn advance(&self, glyph: u16) -> f32 {
+        self.face.glyph_hor_advance(ttf_parser::GlyphId(glyph)).unwrap_or(0) as f32
+            / self.face.units_per_em() as f32
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        text.chars().map(|c| self.advance(self.glyph(c)) * size).sum()
+    }
+
+    /// 编码为 Identity-H 的双字节字形序列
+    fn encode(&mut self, text: &str) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(text.len() * 2);
+        for c in text.chars() {
+            let glyph = self.glyph(c);
+            self.glyphs.entry(glyph).or_insert(c);
+            bytes.extend(glyph.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// 按宽度折行，超出 max_lines 时末行以省略号结尾
+    fn wrap(&self, text: &str, size: f32, max_width: f32, max_lines: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        for paragraph in text.lines() {
+            let mut line = String::new();
+            let mut width = 0.0;
+            for c in paragraph.chars() {
+                let advance = self.advance(self.glyph(c)) * size;
+                if width + advance > max_width && !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                    width = 0.0;
+                }
+                line.push(c);
+                width += advance;
+            }
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        if lines.len() > max_lines {
+            lines.truncate(max_lines);
+            if let Some(last) = lines.last_mut() {
+                last.pop();
+                last.push('…');
+            }
+        }
+        lines
+    }
+
+    /// 写入 Type0 字体（CIDFontType2 / CFF 为 CIDFontType0）及子集字体文件
+    fn write(&self, pdf: &mut Pdf, refs: &mut RefAllocator, type0_id: Ref) -> Result<(), String> {
+        let (cid_id, descriptor_id, file_id, cmap_id) = (refs.next(), refs.next(), refs.next(), refs.next());
+        let is_cff = self.face.tables().cff.is_some();
+
+        let glyph_ids: Vec<u16> = self.glyphs.keys().copied().collect();
+        let subset = subsetter::subset(self.data, 0, subsetter::Profile::pdf(&glyph_ids))
+            .map_err(|e| format!("字体子集化失败: {}", e))?;
+
+        let ps_name = self.face.names().into_iter()
+            .find(|n| n.name_id == ttf_parser::name_id::POST_SCRIPT_NAME)
+            .and_then(|n| n.to_string())
+            .unwrap_or_else(|| "CJKFont".to_string());
+        let base_font = format!("SBOARD+{}", ps_name.replace(' ', ""));
+        let system_info = SystemInfo {
+            registry: Str(b"Adobe"),
+            ordering: Str(b"Identity"),
+            supplement: 0,
+        };
+
+        pdf.type0_font(type0_id)
+            .base_font(Name(base_font.as_bytes()))
+            .encoding_predefined(Name(b"Identity-H"))
+            .descendant_font(cid_id)
+            .to_unicode(cmap_id);
+
+        let mut cid = pdf.cid_font(cid_id);
+        cid.subtype(if is_cff { CidFontType::Type0 } else { CidFontType::Type2 })
+            .base_font(Name(base_font.as_bytes()))
+            .system_info(system_info)
+            .font_descriptor(descriptor_id)
+            .default_width(1000.0);
+        if !is_cff {
+            cid.cid_to_gid_map_predefined(Name(b"Identity"));
+        }
+        let mut widths = cid.widths();
+        for glyph in &glyph_ids {
+            widths.consecutive(*glyph, [self.advance(*glyph) * 1000.0]);
+        }
+        widths.finish();
+        cid.finish();
+
+        let scale = 1000.0 / self.face.units_per_em() as f32;
+        let bbox = self.face.global_bounding_box();
+        let mut descriptor = pdf.font_descriptor(descriptor_id);
+        descriptor
+            .name(Name(base_font.as_bytes()))
+            .flags(FontFlags::SYMBOLIC)
+            .bbox(Rect::new(
+                bbox.x_min as f32 * scale,
+                bbox.y_min as f32 * scale,
+                bbox.x_max as f32 * scale,
+                bbox.y_max as f32 * scale,
+            ))
+            .italic_angle(0.0)
+            .ascent(self.face.ascender() as f32 * scale)
+            .descent(self.face.descender() as f32 * scale)
+            .cap_height(self.face.capital_height().unwrap_or(self.face.ascender()) as f32 * scale)
+            .stem_v(80.0);
+        if is_cff {
+            descriptor.font_file3(file_id);
+        } else {
+            descriptor.font_file2(file_id);
+        }
+        descriptor.finish();
+
+        let compressed = compress(&subset);
+        let mut file = pdf.stream(file_id, &compressed);
+        file.filter(Filter::FlateDecode);
+        if is_cff {
+            file.pair(Name(b"Subtype"), Name(b"OpenType"));
+        }
+        file.finish();
+
+        let mut cmap = UnicodeCmap::new(Name(b"Custom"), system_info);
+        for (glyph, c) in self.glyphs.iter().filter(|(glyph, _)| **glyph != 0) {
+            cmap.pair(*glyph, *c);
+        }
+        pdf.cmap(cmap_id, &cmap.finish());
+
+        Ok(())
+    }
+}
+
+/// 顺序分配对象编号
+struct RefAllocator(i32);
+
+impl RefAllocator {
+    fn next(&mut self) -> Ref {
+        self.0 += 1;
+        Ref::new(self.0)
+    }
+}
+
+fn compress(data: &[u8]) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(data, 6)
+}
+
+/// 页面内容中的文字与图形
+struct PageWriter<'f, 'a> {
+    content: Content,
+    font: &'f mut EmbeddedFont<'a>,
+}
+
+impl PageWriter<'_, '_> {
+    fn text(&mut self, text: &str, x: f32, y: f32, size: f32, gray: f32) {
+        let encoded = self.font.encode(text);
+        self.content
+            .set_fill_rgb(gray, gray, gray)
+            .begin_text()
+            .set_font(Name(b"F1"), size)
+            .next_line(x, y)
+            .show(Str(&encoded))
+            .end_text();
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, fill: Option<f32>) {
+        self.content.set_line_width(0.5).set_stroke_rgb(0.7, 0.7, 0.7);
+        match fill {
+            Some(gray) => {
+                self.content.set_fill_rgb(gray, gray, gray).rect(x, y, width, height).fill_nonzero_and_stroke();
+            }
+            None => {
+                self.content.rect(x, y, width, height).stroke();
+            }
+        }
+    }
+}
+
+/// 生成分镜表 PDF，返回非致命问题列表
+pub fn write_storyboard_pdf(
+    output: &Path,
+    title: &str,
+    entries: &[SheetEntry],
+    layout: &str,
+    aspect_ratio: f64,
+    font_data: &[u8],
+) -> Result<Vec<String>, String> {
+    let layout = SheetLayout::parse(layout)?;
+    let mut font = EmbeddedFont::new(font_data)?;
+    let mut warnings = Vec::new();
+
+    let mut pdf = Pdf::new();
+    let mut refs = RefAllocator(0);
+    let catalog_id = refs.next();
+    let tree_id = refs.next();
+    let font_id = refs.next();
+
+    let page_count = entries.len().div_ceil(layout.per_page()).max(1);
+    let cell_width = (layout.page_width - 2.0 * MARGIN - GAP * (layout.columns - 1) as f32) / layout.columns as f32;
+    let grid_top = layout.page_height - MARGIN - HEADER_HEIGHT;
+    let cell_height = (grid_top - MARGIN - GAP * (layout.rows - 1) as f32) / layout.rows as f32;
+    let size = layout.font_size;
+    let line_height = size * 1.4;
+
+    let mut page_ids = Vec::new();
+    for (page_index, chunk) in entries.chunks(layout.per_page().max(1)).enumerate() {
+        let page_id = refs.next();
+        let content_id = refs.next();
+        page_ids.push(page_id);
+
+        let mut page = PageWriter { content: Content::new(), font: &mut font };
+        let mut images: Vec<(String, Ref, Vec<u8>, u32, u32)> = Vec::new();
+
+        // 页眉：项目名 + 页码
+        let header_y = layout.page_height - MARGIN - 12.0;
+        page.text(title, MARGIN, header_y, 11.0, 0.1);
+        let page_label = format!("第 {} / {} 页", page_index + 1, page_count);
+        let label_width = page.font.text_width(&page_label, 9.0);
+        page.text(&page_label, layout.page_width - MARGIN - label_width, header_y, 9.0, 0.4);
+        page.content
+            .set_line_width(0.5)
+            .set_stroke_rgb(0.6, 0.6, 0.6)
+            .move_to(MARGIN, grid_top + 6.0)
+            .line_to(layout.page_width - MARGIN, grid_top + 6.0)
+            .stroke();
+
+        for (slot, entry) in chunk.iter().enumerate() {
+            let sb = entry.storyboard;
+            let x = MARGIN + (slot % layout.columns) as f32 * (cell_width + GAP);
+            let top = grid_top - (slot / layout.columns) as f32 * (cell_height + GAP);
+
+            // 图片框：按画幅，高度不超过格子的 62%
+            let mut box_width = cell_width;
+            let mut box_height = cell_width / aspect_ratio as f32;
+            if box_height > cell_height * 0.62 {
+                box_height = cell_height * 0.62;
+                box_width = box_height * aspect_ratio as f32;
+            }
+            let box_x = x + (cell_width - box_width) / 2.0;
+            let box_y = top - box_height;
+
+            let is_empty = sb.image_status.as_deref() == Some("empty");
+            let thumbnail = match (&entry.image, is_empty) {
+                (Some(path), false) => match load_thumbnail(path, layout.image_edge).and_then(|img| {
+                    encode_jpeg(&img).map(|jpeg| (jpeg, img.width(), img.height()))
+                }) {
+                    Ok(thumbnail) => Some(thumbnail),
+                    Err(e) => {
+                        warnings.push(format!("{}: {}", sb.mirror_id, e));
+                        None
+                    }
+                },
+                _ => None,
+            };
+
+            match thumbnail {
+                Some((jpeg, width, height)) => {
+                    // 等比缩放居中
+                    let scale = (box_width / width as f32).min(box_height / height as f32);
+                    let (w, h) = (width as f32 * scale, height as f32 * scale);
+                    let name = format!("Im{}", images.len() + 1);
+                    page.rect(box_x, box_y, box_width, box_height, Some(0.0));
+                    page.content
+                        .save_state()
+                        .transform([w, 0.0, 0.0, h, box_x + (box_width - w) / 2.0, box_y + (box_height - h) / 2.0])
+                        .x_object(Name(name.as_bytes()))
+                        .restore_state();
+                    images.push((name, refs.next(), jpeg, width, height));
+                }
+                None => {
+                    page.rect(box_x, box_y, box_width, box_height, Some(0.93));
+                    let label = if is_empty { "未生成" } else { "无图片" };
+                    let label_size = size * 1.2;
+                    let label_width = page.font.text_width(label, label_size);
+                    page.text(
+                        label,
+                        box_x + (box_width - label_width) / 2.0,
+                        box_y + box_height / 2.0 - label_size / 3.0,
+                        label_size,
+                        0.55,
+                    );
+                }
+            }
+
+            // 文字：镜号 / 景别 / 镜头类型 / 时长，然后对白与备注
+            let mut cursor = box_y - line_height;
+            let bottom = top - cell_height;
+            let headline = [
+                Some(sb.mirror_id.clone()),
+                sb.shot_size.clone().filter(|s| !s.is_empty()),
+                sb.shot_type.clone().filter(|s| !s.is_empty()),
+                sb.duration.map(|d| format!("{}s", d)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("  |  ");
+            page.text(&headline, x, cursor, size + 1.0, 0.05);
+            cursor -= line_height * 1.1;
+
+            for (label, value) in [("对白", &sb.dialogue), ("备注", &sb.notes)] {
+                let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) else {
+                    continue;
+                };
+                let max_lines = ((cursor - bottom) / line_height).floor().max(0.0) as usize + 1;
+                if cursor < bottom {
+                    break;
+                }
+                let lines = page.font.wrap(&format!("{}：{}", label, value), size, cell_width, max_lines);
+                for line in lines {
+                    page.text(&line, x, cursor, size, 0.2);
+                    cursor -= line_height;
+                }
+            }
+        }
+
+        let compressed = compress(&page.content.finish());
+        pdf.stream(content_id, &compressed).filter(Filter::FlateDecode);
+
+        let mut page_obj = pdf.page(page_id);
+        page_obj
+            .media_box(Rect::new(0.0, 0.0, layout.page_width, layout.page_height))
+            .parent(tree_id)
+            .contents(content_id);
+        let mut resources = page_obj.resources();
+        resources.fonts().pair(Name(b"F1"), font_id);
+        let mut x_objects = resources.x_objects();
+        for (name, id, ..) in &images {
+            x_objects.pair(Name(name.as_bytes()), *id);
+        }
+        x_objects.finish();
+        resources.finish();
+        page_obj.finish();
+
+        for (_, id, jpeg, width, height) in &images {
+            let mut image = pdf.image_xobject(*id, jpeg);
+            image.filter(Filter::DctDecode);
+            image.width(*width as i32).height(*height as i32).bits_per_component(8);
+            image.color_space().device_rgb();
+        }
+    }
+
+    font.write(&mut pdf, &mut refs, font_id)?;
+    pdf.pages(tree_id).kids(page_ids.iter().copied()).count(page_ids.len() as i32);
+    pdf.catalog(catalog_id).pages(tree_id);
+    pdf.set_version(1, 7);
+
+    std::fs::write(output, pdf.finish()).map_err(|e| format!("写入 PDF 失败: {}", e))?;
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    #[ignore = "需要系统 TrueType 字体（DejaVu Sans / Arial），用 cargo test -- --ignored 运行"]
+    fn test_write_storyboard_pdf() {
+        // 用任意可用的 TrueType 字体验证 PDF 结构，不依赖中文字体
+        let font_path = ["/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf", "C:/Windows/Fonts/arial.ttf", "/Library/Fonts/Arial Unicode.ttf"]
+            .iter()
+            .map(Path::new)
+            .find(|p| p.exists())
+            .expect("找不到可用的 TrueType 字体");
+        let font_data = std::fs::read(font_path).unwrap();
+
+        let shots: Vec<Storyboard> = (1..=7).map(|i| serde_json::from_value(json!({
+            "sequence_number": i,
+            "mirror_id": format!("A{}", i),
+            "duration": 2.0,
+            "dialogue": "Hello there, this line is long enough to wrap across several lines in a small cell.",
+            "image_status": "empty",
+        })).unwrap()).collect();
+        let entries: Vec<SheetEntry> = shots.iter().map(|sb| SheetEntry { storyboard: sb, image: None }).collect();
+
+        let output = std::env::temp_dir().join(format!("storyboard_test_{}.pdf", std::process::id()));
+        let warnings = write_storyboard_pdf(&output, "Demo", &entries, "2x3", 16.0 / 9.0, &font_data).unwrap();
+        assert!(warnings.is_empty());
+
+        let bytes = std::fs::read(&output).unwrap();
+        assert!(bytes.starts_with(b"%PDF-1.7"));
+        let text = String::from_utf8_lossy(&bytes);
+        assert_eq!(text.matches("/Type /Page\n").count() + text.matches("/Type /Page ").count(), 2);
+        assert!(text.contains("/FontFile2"));
+
+        let _ = std::fs::remove_file(&output);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::commands::unix_timestamp;
+use crate::db::ProjectDatabase;
+use crate::models::StoryboardReview;
+use std::collections::HashSet;
+
+/// 审核状态：草稿、审核中、需修改、已通过、已锁定
+pub const REVIEW_STATUSES: [&str; 5] = ["draft", "in_review", "changes_requested", "approved", "locked"];
+
+/// 锁定状态：镜头内容不再被 AI 生成、导入或生图覆盖
+pub const LOCKED: &str = "locked";
+
+/// 已锁定的镜号
+pub fn locked_mirror_ids(db: &ProjectDatabase) -> rusqlite::Result<HashSet<String>> {
+    let mut stmt = db.conn().prepare("SELECT mirror_id FROM storyboards WHERE review_status = ?1")?;
+    let rows = stmt.query_map([LOCKED], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// 镜头已锁定时返回错误
+pub fn ensure_unlocked(db: &ProjectDatabase, mirror_id: &str) -> Result<(), String> {
+    let status: Option<String> = match db.conn().query_row(
+        "SELECT review_status FROM storyboards WHERE mirror_id = ?1",
+        [mirror_id],
+        |row| row.get(0),
+    ) {
+        Ok(status) => status,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(()),
+        Err(e) => return Err(format!("查询审核状态失败: {}", e)),
+    };
+    if status.as_deref() == Some(LOCKED) {
+        return Err(format!("分镜 {} 已锁定，请先解除锁定", mirror_id));
+    }
+    Ok(())
+}
+
+/// 批量设置审核状态并记录审核历史，返回更新的镜头数
+pub fn set_review_status(
+    db: &ProjectDatabase,
+    mirror_ids: &[String],
+    status: &str,
+    reviewer: Option<&str>,
+    comment: Option<&str>,
+) -> Result<usize, String> {
+    if !REVIEW_STATUSES.contains(&status) {
+        return Err(format!("无效的审核状态: {}", status));
+    }
+    let reviewer = reviewer.map(str::trim).filter(|r| !r.is_empty());
+    let comment = comment.map(str::trim).filter(|c| !c.is_empty());
+    let now = unix_timestamp()?;
+
+    let tx = db.conn().unchecked_transaction()
+        .map_err(|e| format!("开启事务失败: {}", e))?;
+    let mut updated = 0;
+    for mirror_id in mirror_ids {
+        let changed = db.conn().execute(
+            "UPDATE storyboards SET review_status = ?1, reviewed_by = ?2, reviewed_at = ?3 WHERE mirror_id = ?4",
+            rusqlite::params![status, reviewer, now, mirror_id],
+        ).map_err(|e| format!("更新审核状态失败: {}", e))?;
+        if changed == 0 {
+            continue;
+        }
+        db.conn().execute(
+            "INSERT INTO storyboard_reviews (mirror_id, status, reviewer, comment, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![mirror_id, status, reviewer, comment, now],
+        ).map_err(|e| format!("记录审核历史失败: {}", e))?;
+        updated += 1;
+    }
+    tx.commit().map_err(|e| format!("更新审核状态失败: {}", e))?;
+    Ok(updated)
+}
+
+/// 镜头的审核历史（最新的在前）
+pub fn review_history(db: &ProjectDatabase, mirror_id: &str) -> Result<Vec<StoryboardReview>, String> {
+    let mut stmt = db.conn().prepare(
+        "SELECT id, mirror_id, status, reviewer, comment, created_at FROM storyboard_reviews
+         WHERE mirror_id = ?1 ORDER BY created_at DESC, id DESC"
+    ).map_err(|e| format!("查询审核历史失败: {}", e))?;
+    let reviews = stmt.query_map([mirror_id], |row| {
+        Ok(StoryboardReview {
+            id: row.get(0)?,
+            mirror_id: row.get(1)?,
+            status: row.get(2)?,
+            reviewer: row.get(3)?,
+            comment: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    }).map_err(|e| format!("解析审核历史失败: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("收集审核历史失败: {}", e))?;
+    Ok(reviews)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::temp_project;
+
+    #[test]
+    fn test_review_status_and_lock() {
+        let dir = temp_project("review");
+        let db = ProjectDatabase::open(&dir).unwrap();
+        db.conn().execute(
+            "INSERT INTO storyboards (mirror_id, sequence_number) VALUES ('A1', 1), ('A2', 2)",
+            [],
+        ).unwrap();
+
+        let ids = vec!["A1".to_string(), "A2".to_string(), "A9".to_string()];
+        assert_eq!(set_review_status(&db, &ids, "approved", Some("导演"), None).unwrap(), 2);
+        assert!(set_review_status(&db, &ids, "done", None, None).is_err());
+        set_review_status(&db, &ids[..1], LOCKED, Some("导演"), Some("定稿")).unwrap();
+
+        assert!(ensure_unlocked(&db, "A1").is_err());
+        assert!(ensure_unlocked(&db, "A2").is_ok());
+        assert_eq!(locked_mirror_ids(&db).unwrap().len(), 1);
+
+        let history = review_history(&db, "A1").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].status, LOCKED);
+        assert_eq!(history[0].comment.as_deref(), Some("定稿"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_locked_shot_survives_regeneration() {
+        use crate::commands::{save_episode, save_generated_data};
+        use crate::models::{Storyboard, StoryboardSource};
+
+        let dir = temp_project("review_locked_save");
+        let folder = dir.to_string_lossy().to_string();
+        let episode_id = save_episode(folder.clone(), None, "第2集".to_string()).unwrap();
+        let db = ProjectDatabase::open(&dir).unwrap();
+        let first = db.default_sequence_id().unwrap();
+        let second: i64 = db.conn().query_row(
+            "SELECT id FROM sequences WHERE episode_id = ?1", [episode_id], |row| row.get(0),
+        ).unwrap();
+        db.conn().execute(
+            "INSERT INTO storyboards (mirror_id, sequence_number, sequence_id, description, dialogue)
+             VALUES ('A1', 1, ?1, '原描述', '小明：原台词')",
+            [first],
+        ).unwrap();
+        let document_id = db.insert_source_document("script.txt", "开场\n小明：新的台词", 0).unwrap();
+        db.set_storyboard_source(&StoryboardSource { mirror_id: "A1".to_string(), document_id, start_line: 0, end_line: 0 }).unwrap();
+        set_review_status(&db, &["A1".to_string()], LOCKED, None, None).unwrap();
+
+        // 带着其他序列的 ID 返回改过的内容：既不覆盖，也不另存为 SQ2-A1
+        let regenerated = Storyboard {
+            mirror_id: "A1".to_string(),
+            sequence_number: 5,
+            description: Some("新描述".to_string()),
+            dialogue: Some("小明：新的台词".to_string()),
+            sequence_id: Some(second),
+            ..Default::default()
+        };
+        let skipped = save_generated_data(folder.clone(), vec![regenerated], vec![], vec![], vec![], Some(document_id)).unwrap();
+        assert_eq!(skipped, vec!["A1".to_string()]);
+
+        let (description, dialogue, sequence_id, sequence_number): (String, String, i64, i64) = db.conn().query_row(
+            "SELECT description, dialogue, sequence_id, sequence_number FROM storyboards WHERE mirror_id = 'A1'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        ).unwrap();
+        assert_eq!((description.as_str(), dialogue.as_str(), sequence_id, sequence_number), ("原描述", "小明：原台词", first, 5));
+        let count: i64 = db.conn().query_row("SELECT COUNT(*) FROM storyboards", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+        let sources = db.get_storyboard_sources(document_id).unwrap();
+        assert_eq!((sources[0].start_line, sources[0].end_line), (0, 0));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_enqueue_skips_locked_shots() {
+        use crate::image_queue::enqueue_jobs;
+
+        let dir = temp_project("review_locked_queue");
+        let db = ProjectDatabase::open(&dir).unwrap();
+        db.conn().execute(
+            "INSERT INTO storyboards (mirror_id, sequence_number) VALUES ('A1', 1), ('A2', 2)",
+            [],
+        ).unwrap();
+        set_review_status(&db, &["A1".to_string()], LOCKED, None, None).unwrap();
+
+        let ids = vec!["A1".to_string(), "A2".to_string()];
+        assert_eq!(enqueue_jobs(&db, "api", &ids, &["first".to_string()], false).unwrap(), 1);
+        let queued: String = db.conn().query_row("SELECT mirror_id FROM image_jobs", [], |row| row.get(0)).unwrap();
+        assert_eq!(queued, "A2");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
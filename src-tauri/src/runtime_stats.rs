@@ -0,0 +1,179 @@
+use crate::animatic::DEFAULT_SHOT_DURATION;
+use crate::models::{RuntimeOptions, RuntimeStats, SequenceRuntime, ShortShot, ShotSizeCount, Storyboard};
+use crate::subtitles::parse_speaker;
+
+/// 默认中文语速（字/秒）
+pub const DEFAULT_CHARS_PER_SECOND: f64 = 4.0;
+/// 默认英文语速（词/秒）
+pub const DEFAULT_WORDS_PER_SECOND: f64 = 2.5;
+
+fn is_cjk_ideograph(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}')
+}
+
+/// 去掉括号中的表演提示，如 （低声）、(V.O.)
+fn strip_parentheticals(line: &str) -> String {
+    let mut depth = 0;
+    line.chars()
+        .filter(|c| {
+            match c {
+                '(' | '（' => depth += 1,
+                ')' | '）' => depth = (depth - 1).max(0),
+                _ => return depth == 0,
+            }
+            false
+        })
+        .collect()
+}
+
+/// 按语速估算说完对白所需的秒数：中文按字、英文按词计，说话人与括号提示不计
+pub fn estimate_dialogue_seconds(dialogue: &str, chars_per_second: f64, words_per_second: f64) -> f64 {
+    let mut chars = 0;
+    let mut words = 0;
+    for line in dialogue.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let line = match parse_speaker(line) {
+            Some(_) => line.split_once(['：', ':']).map(|(_, t)| t).unwrap_or(line),
+            None => line,
+        };
+        let line = strip_parentheticals(line);
+        chars += line.chars().filter(|c| is_cjk_ideograph(*c)).count();
+        words += line.split(|c: char| !c.is_ascii_alphanumeric() && c != '\'')
+            .filter(|w| w.chars().any(|c| c.is_ascii_alphanumeric()))
+            .count();
+    }
+    chars as f64 / chars_per_second + words as f64 / words_per_second
+}
+
+fn round_tenth(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// 统计分镜时长：sequences 为 (序列 ID, 显示名) 按项目顺序排列，size_order 为景别词表顺序
+pub fn compute_runtime_stats(
+    storyboards: &[Storyboard],
+    sequences: &[(i64, String)],
+    size_order: &[String],
+    options: &RuntimeOptions,
+) -> RuntimeStats {
+    let default_duration = options.default_duration.filter(|d| *d > 0.0).unwrap_or(DEFAULT_SHOT_DURATION);
+    let chars_per_second = options.chars_per_second.filter(|v| *v > 0.0).unwrap_or(DEFAULT_CHARS_PER_SECOND);
+    let words_per_second = options.words_per_second.filter(|v| *v > 0.0).unwrap_or(DEFAULT_WORDS_PER_SECOND);
+
+    let mut ordered: Vec<&Storyboard> = storyboards.iter().collect();
+    ordered.sort_by_key(|sb| sb.sequence_number);
+
+    let mut sequence_runtimes: Vec<SequenceRuntime> = Vec::new();
+    let mut shot_sizes: Vec<ShotSizeCount> = Vec::new();
+    let mut short_shots = Vec::new();
+    let mut total_duration = 0.0;
+    let mut untimed_shot_count = 0;
+    let mut dialogue_shot_count = 0;
+    let mut estimated_dialogue_duration = 0.0;
+
+    for sb in &ordered {
+        let duration = match sb.duration.filter(|d| *d > 0.0) {
+            Some(duration) => duration,
+            None => {
+                untimed_shot_count += 1;
+                default_duration
+            }
+        };
+        total_duration += duration;
+
+        let sequence = match sequence_runtimes.iter_mut().find(|s| s.sequence_id == sb.sequence_id) {
+            Some(sequence) => sequence,
+            None => {
+                let name = sequences.iter()
+                    .find(|(id, _)| Some(*id) == sb.sequence_id)
+                    .map(|(_, name)| name.clone())
+                    .unwrap_or_else(|| "未分配序列".to_string());
+                sequence_runtimes.push(SequenceRuntime { sequence_id: sb.sequence_id, name, shot_count: 0, duration: 0.0 });
+                sequence_runtimes.last_mut().unwrap()
+            }
+        };
+        sequence.shot_count += 1;
+        sequence.duration += duration;
+
+        let shot_size = sb.shot_size.as_deref().map(str::trim).unwrap_or("").to_string();
+        match shot_sizes.iter_mut().find(|s| s.shot_size == shot_size) {
+            Some(size) => {
+                size.count += 1;
+                size.duration += duration;
+            }
+            None => shot_sizes.push(ShotSizeCount { shot_size, count: 1, duration }),
+        }
+
+        let dialogue = sb.dialogue.as_deref().unwrap_or("");
+        let estimated = estimate_dialogue_seconds(dialogue, chars_per_second, words_per_second);
+        if estimated > 0.0 {
+            dialogue_shot_count += 1;
+            estimated_dialogue_duration += estimated;
+            if duration < round_tenth(estimated) {
+                short_shots.push(ShortShot {
+                    mirror_id: sb.mirror_id.clone(),
+                    duration,
+                    estimated_duration: round_tenth(estimated),
+                });
+            }
+        }
+    }
+
+    // 序列按项目顺序，景别按词表顺序，未收录的排在后面、未标注的排在最后
+    let sequence_rank = |id: Option<i64>| sequences.iter().position(|(s, _)| Some(*s) == id).unwrap_or(usize::MAX);
+    sequence_runtimes.sort_by_key(|s| sequence_rank(s.sequence_id));
+    let size_rank = |size: &str| match size_order.iter().position(|s| s == size) {
+        Some(rank) => rank,
+        None if size.is_empty() => usize::MAX,
+        None => usize::MAX - 1,
+    };
+    shot_sizes.sort_by_key(|s| size_rank(&s.shot_size));
+
+    RuntimeStats {
+        shot_count: ordered.len(),
+        untimed_shot_count,
+        total_duration,
+        average_shot_length: if ordered.is_empty() { 0.0 } else { total_duration / ordered.len() as f64 },
+        sequences: sequence_runtimes,
+        shot_sizes,
+        dialogue_shot_count,
+        estimated_dialogue_duration: round_tenth(estimated_dialogue_duration),
+        short_shots,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_stats() {
+        assert_eq!(estimate_dialogue_seconds("小明（低声）：我们走吧。", 4.0, 2.5), 1.0);
+        assert_eq!(estimate_dialogue_seconds("JOHN: Let's go, it's late now.", 4.0, 2.5), 2.0);
+
+        let shot = |mirror_id: &str, sequence_number, shot_size: &str, duration, dialogue: &str, sequence_id| Storyboard {
+            mirror_id: mirror_id.to_string(),
+            sequence_number,
+            shot_size: Some(shot_size.to_string()),
+            duration,
+            dialogue: Some(dialogue.to_string()),
+            sequence_id: Some(sequence_id),
+            ..Default::default()
+        };
+        let storyboards = vec![
+            shot("A1", 1, "全景", Some(4.0), "", 1),
+            shot("A2", 2, "中景", Some(1.0), "小红：今天的雨下得真大，我们还是改天再去吧。", 1),
+            shot("B1", 3, "中景", None, "", 2),
+        ];
+        let sequences = vec![(1, "第1集 / 开场".to_string()), (2, "第1集 / 雨夜".to_string())];
+        let sizes = vec!["全景".to_string(), "中景".to_string()];
+        let stats = compute_runtime_stats(&storyboards, &sequences, &sizes, &RuntimeOptions::default());
+
+        assert_eq!(stats.total_duration, 8.0);
+        assert_eq!(stats.untimed_shot_count, 1);
+        assert_eq!(stats.sequences[1].duration, 3.0);
+        assert_eq!(stats.shot_sizes[1].count, 2);
+        assert_eq!(stats.short_shots.len(), 1);
+        assert_eq!(stats.short_shots[0].mirror_id, "A2");
+        assert_eq!(stats.short_shots[0].estimated_duration, 4.3);
+    }
+}
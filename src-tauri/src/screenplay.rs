@@ -0,0 +1,245 @@
+use crate::models::{ScriptChunk, ScriptScene};
+
+/// 剧本元素类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementKind {
+    SceneHeading,
+    Action,
+    Character,
+    Parenthetical,
+    Dialogue,
+    Transition,
+}
+
+/// 剧本元素
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptElement {
+    pub kind: ElementKind,
+    pub text: String,
+    /// 场号（仅场景标题，如 Fountain 的 #12#）
+    pub number: Option<String>,
+}
+
+impl ScriptElement {
+    pub fn new(kind: ElementKind, text: impl Into<String>) -> Self {
+        ScriptElement { kind, text: text.into(), number: None }
+    }
+}
+
+/// 解析后的剧本
+#[derive(Debug, Clone, Default)]
+pub struct Screenplay {
+    pub title: Option<String>,
+    pub elements: Vec<ScriptElement>,
+}
+
+/// 默认分块长度（字符数），约为一次生成请求可处理的剧本量
+pub const DEFAULT_CHUNK_CHARS: usize = 3000;
+
+/// 场景标题中表示时间的词（位于末尾）
+const TIME_OF_DAY: &[&str] = &[
+    "DAY", "NIGHT", "MORNING", "EVENING", "AFTERNOON", "DAWN", "DUSK", "LATER", "CONTINUOUS", "MOMENTS LATER",
+    "日", "夜", "晨", "昏", "白天", "夜晚", "清晨", "早晨", "傍晚", "黄昏", "深夜", "午后", "中午",
+];
+
+/// 场景标题前缀（英文 INT./EXT.，中文 内景/外景）
+const HEADING_PREFIXES: &[&str] = &[
+    "INT./EXT.", "INT/EXT.", "INT/EXT", "EXT./INT.", "I/E.", "I/E", "INT.", "EXT.", "EST.", "INT ", "EXT ", "EST ",
+    "内/外景", "内外景", "内景", "外景", "内/外",
+];
+
+/// 去掉中文剧本常见的“12.”“12、”场号
+fn strip_leading_number(text: &str) -> &str {
+    let digits = text.trim_start_matches(|c: char| c.is_ascii_digit());
+    if digits.len() != text.len() {
+        digits.trim_start_matches(['.', '、', ' '])
+    } else {
+        text
+    }
+}
+
+/// 是否为场景标题（INT./EXT. 或 内景/外景 开头）
+pub fn is_scene_heading(line: &str) -> bool {
+    let upper = strip_leading_number(line.trim()).to_uppercase();
+    HEADING_PREFIXES.iter().any(|p| upper.starts_with(p) && upper.len() > p.len())
+}
+
+/// 从场景标题提取地点名：去掉场号、内外景前缀与时间
+pub fn scene_location(heading: &str) -> String {
+    let mut text = strip_leading_number(heading.trim());
+    let upper = text.to_uppercase();
+    if let Some(prefix) = HEADING_PREFIXES.iter().find(|p| upper.starts_with(*p)) {
+        text = text[prefix.len()..].trim();
+    }
+
+    // 英文以 " - " 分隔时间；中文常以空格分隔
+    if let Some((location, time)) = text.rsplit_once(" - ") {
+        if TIME_OF_DAY.iter().any(|t| time.trim().eq_ignore_ascii_case(t)) {
+            text = location;
+        }
+    }
+    if let Some((location, time)) = text.rsplit_once([' ', '　']) {
+        if TIME_OF_DAY.iter().any(|t| time.trim().eq_ignore_ascii_case(t)) {
+            text = location;
+        }
+    }
+    let location = text.trim().trim_end_matches(['-', '–', '—', ' ']).trim();
+    if location.is_empty() { heading.trim().to_string() } else { location.to_string() }
+}
+
+/// 规范化角色名：去掉 (V.O.)、(CONT'D) 等扩展及双人对白标记
+pub fn character_name(cue: &str) -> String {
+    let cue = cue.trim().trim_end_matches('^').trim();
+    let cue = match cue.find(['(', '（']) {
+        Some(pos) if pos > 0 => &cue[..pos],
+        _ => cue,
+    };
+    cue.trim().to_string()
+}
+
+impl Screenplay {
+    /// 按场景拆分；第一个场景标题之前的内容归入“开场”
+    pub fn scenes(&self) -> Vec<ScriptScene> {
+        let mut scenes: Vec<(Option<&ScriptElement>, Vec<&ScriptElement>)> = Vec::new();
+        for element in &self.elements {
+            if element.kind == ElementKind::SceneHeading {
+                scenes.push((Some(element), Vec::new()));
+            } else {
+                if scenes.is_empty() {
+                    scenes.push((None, Vec::new()));
+                }
+                if let Some((_, body)) = scenes.last_mut() {
+                    body.push(element);
+                }
+            }
+        }
+
+        scenes.into_iter().enumerate().map(|(index, (heading, body))| {
+            let mut characters: Vec<String> = Vec::new();
+            for element in body.iter().filter(|e| e.kind == ElementKind::Character) {
+                let name = character_name(&element.text);
+                if !name.is_empty() && !characters.contains(&name) {
+                    characters.push(name);
+                }
+            }
+            let heading_text = heading.map(|h| h.text.clone()).unwrap_or_else(|| "开场".to_string());
+            let mut elements: Vec<&ScriptElement> = heading.into_iter().collect();
+            elements.extend(body);
+            ScriptScene {
+                index,
+                number: heading.and_then(|h| h.number.clone()),
+                location: heading.map(|h| scene_location(&h.text)),
+                heading: heading_text,
+                characters,
+                text: render_elements(&elements),
+            }
+        }).collect()
+    }
+
+    /// 全部角色（按首次出场顺序）
+    pub fn characters(&self) -> Vec<String> {
+        let mut characters: Vec<String> = Vec::new();
+        for element in self.elements.iter().filter(|e| e.kind == ElementKind::Character) {
+            let name = character_name(&element.text);
+            if !name.is_empty() && !characters.contains(&name) {
+                characters.push(name);
+            }
+        }
+        characters
+    }
+}
+
+/// 渲染为便于模型阅读的纯文本剧本（对白以“角色：台词”呈现）
+pub fn render_elements(elements: &[&ScriptElement]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut speaker: Option<String> = None;
+    for element in elements {
+        match element.kind {
+            ElementKind::SceneHeading => {
+                speaker = None;
+                lines.push(String::new());
+                lines.push(element.text.clone());
+            }
+            ElementKind::Character => speaker = Some(character_name(&element.text)),
+            ElementKind::Parenthetical => {
+                let name = speaker.clone().unwrap_or_default();
+                lines.push(format!("{}{}", name, element.text));
+            }
+            ElementKind::Dialogue => match &speaker {
+                Some(name) => lines.push(format!("{}：{}", name, element.text)),
+                None => lines.push(element.text.clone()),
+            },
+            ElementKind::Action | ElementKind::Transition => {
+                speaker = None;
+                lines.push(element.text.clone());
+            }
+        }
+    }
+    lines.join("\n").trim().to_string()
+}
+
+/// 按场景切分为生成用的文本块：小场景合并到 max_chars，超长场景在段落处拆开
+pub fn chunk_scenes(scenes: &[ScriptScene], max_chars: usize) -> Vec<ScriptChunk> {
+    let max_chars = max_chars.max(200);
+    let mut chunks: Vec<ScriptChunk> = Vec::new();
+
+    let mut push = |scene: &ScriptScene, text: String, merge: bool| {
+        if merge {
+            if let Some(last) = chunks.last_mut() {
+                if last.text.chars().count() + text.chars().count() < max_chars {
+                    last.text.push_str("\n\n");
+                    last.text.push_str(&text);
+                    last.scene_end = scene.index;
+                    last.headings.push(scene.heading.clone());
+                    return;
+                }
+            }
+        }
+        chunks.push(ScriptChunk {
+            index: chunks.len(),
+            scene_start: scene.index,
+            scene_end: scene.index,
+            headings: vec![scene.heading.clone()],
+            text,
+        });
+    };
+
+    for scene in scenes {
+        if scene.text.chars().count() <= max_chars {
+            push(scene, scene.text.clone(), true);
+            continue;
+        }
+        // 超长场景：按行累积，每块不超过 max_chars
+        let mut part = String::new();
+        for line in scene.text.lines() {
+            if !part.is_empty() && part.chars().count() + line.chars().count() > max_chars {
+                push(scene, std::mem::take(&mut part), false);
+            }
+            if !part.is_empty() {
+                part.push('\n');
+            }
+            part.push_str(line);
+        }
+        if !part.is_empty() {
+            push(scene, part, false);
+        }
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scene_location_and_character_name() {
+        assert_eq!(scene_location("INT. COFFEE SHOP - DAY"), "COFFEE SHOP");
+        assert_eq!(scene_location("EXT. ROOFTOP"), "ROOFTOP");
+        assert_eq!(scene_location("3. 内景 客厅 夜"), "客厅");
+        assert_eq!(scene_location("外景 城市街道 - 黄昏"), "城市街道");
+        assert_eq!(character_name("JOHN (V.O.)"), "JOHN");
+        assert_eq!(character_name("MARY^"), "MARY");
+        assert_eq!(character_name("小明（画外音）"), "小明");
+    }
+}
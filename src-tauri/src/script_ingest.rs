@@ -0,0 +1,250 @@
+use crate::models::{IngestedScript, SourceParagraph};
+use crate::screenplay::is_scene_heading;
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+const W_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+
+/// 解析剧本文件，保留段落与标题结构
+pub fn ingest_script(file_name: &str, data: &[u8]) -> Result<IngestedScript, String> {
+    let format = file_name.rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let blocks = match format.as_str() {
+        "docx" => read_docx(data)?,
+        "pdf" => read_pdf(data)?,
+        "md" => read_lines(&decode_text(data), true),
+        "txt" | "fountain" => read_lines(&decode_text(data), false),
+        "doc" => return Err("不支持旧版 .doc 格式，请将文件另存为 .docx 后再导入".to_string()),
+        other => return Err(format!("不支持的剧本格式: {}", other)),
+    };
+
+    let paragraphs: Vec<SourceParagraph> = blocks.into_iter()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .enumerate()
+        .map(|(index, (level, text))| {
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let kind = if level.is_some() {
+                "heading"
+            } else if is_scene_heading(&text) {
+                "scene_heading"
+            } else {
+                "paragraph"
+            };
+            SourceParagraph { index, kind: kind.to_string(), level, text }
+        })
+        .collect();
+    if paragraphs.is_empty() {
+        return Err("未能从文件中提取到文字（扫描版 PDF 需先进行文字识别）".to_string());
+    }
+
+    // 每段一行，标题以 Markdown 形式保留层级
+    let text = paragraphs.iter()
+        .map(|p| match p.level {
+            Some(level) => format!("{} {}", "#".repeat(level as usize), p.text),
+            None => p.text.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(IngestedScript {
+        file_name: file_name.to_string(),
+        format,
+        paragraphs,
+        text,
+        document_id: None,
+    })
+}
+
+/// 文本解码：UTF-8 / 带 BOM 的 UTF-16，其余按 GBK
+fn decode_text(data: &[u8]) -> String {
+    if let Some((encoding, _)) = encoding_rs::Encoding::for_bom(data) {
+        return encoding.decode(data).0.into_owned();
+    }
+    match std::str::from_utf8(data) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::GBK.decode(data).0.into_owned(),
+    }
+}
+
+/// 纯文本：每个非空行为一段；Markdown 的 # 行为标题
+fn read_lines(text: &str, markdown: bool) -> Vec<(Option<u8>, String)> {
+    text.lines()
+        .map(|line| {
+            let trimmed = line.trim();
+            let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+            if markdown && (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+                (Some(hashes as u8), trimmed[hashes..].trim().to_string())
+            } else {
+                (None, trimmed.to_string())
+            }
+        })
+        .collect()
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{2e80}'..='\u{9fff}' | '\u{f900}'..='\u{faff}' | '\u{ff00}'..='\u{ffef}')
+}
+
+/// PDF 文字层：空行分段，段内折行合并（中文直接拼接，西文加空格）
+fn read_pdf(data: &[u8]) -> Result<Vec<(Option<u8>, String)>, String> {
+    // pdf-extract 遇到不规范的文件可能 panic
+    let pages = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(data))
+        .map_err(|_| "解析 PDF 失败: 文件结构不受支持".to_string())?
+        .map_err(|e| format!("解析 PDF 失败: {}", e))?;
+
+    let mut blocks = Vec::new();
+    for page in pages {
+        let mut current = String::new();
+        for line in page.lines().map(str::trim) {
+            if line.is_empty() {
+                if !current.is_empty() {
+                    blocks.push((None, std::mem::take(&mut current)));
+                }
+                continue;
+            }
+            // 剧本 PDF 中场景标题常与正文之间没有空行
+            if is_scene_heading(line) {
+                if !current.is_empty() {
+                    blocks.push((None, std::mem::take(&mut current)));
+                }
+                blocks.push((None, line.to_string()));
+                continue;
+            }
+            let join_directly = current.chars().last().is_some_and(is_cjk) || line.chars().next().is_some_and(is_cjk);
+            if !current.is_empty() && !join_directly {
+                current.push(' ');
+            }
+            current.push_str(line);
+        }
+        if !current.is_empty() {
+            blocks.push((None, current));
+        }
+    }
+    Ok(blocks)
+}
+
+fn is_w(node: &Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name && node.tag_name().namespace() == Some(W_NS)
+}
+
+fn w_val<'a>(node: &Node<'a, 'a>, child: &str) -> Option<&'a str> {
+    node.children().find(|n| is_w(n, child)).and_then(|n| n.attribute((W_NS, "val")))
+}
+
+/// 样式名推断标题层级：Title、heading 1、标题 1 或样式中的大纲级别
+fn heading_level(name: &str, outline: Option<&str>) -> Option<u8> {
+    let lower = name.to_lowercase();
+    if lower == "title" || lower == "标题" {
+        return Some(1);
+    }
+    for prefix in ["heading", "标题"] {
+        if let Some(level) = lower.strip_prefix(prefix).and_then(|l| l.trim().parse::<u8>().ok()) {
+            return Some(level.clamp(1, 6));
+        }
+    }
+    outline.and_then(|o| o.parse::<u8>().ok()).filter(|o| *o < 9).map(|o| (o + 1).min(6))
+}
+
+/// DOCX：读取 word/document.xml 中的段落，结合 styles.xml 识别标题
+fn read_docx(data: &[u8]) -> Result<Vec<(Option<u8>, String)>, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .map_err(|e| format!("解析 DOCX 失败: {}", e))?;
+    let mut read_entry = |name: &str| -> Option<String> {
+        let mut entry = archive.by_name(name).ok()?;
+        let mut xml = String::new();
+        entry.read_to_string(&mut xml).ok()?;
+        Some(xml)
+    };
+    let document_xml = read_entry("word/document.xml")
+        .ok_or_else(|| "解析 DOCX 失败: 缺少 word/document.xml".to_string())?;
+    let styles_xml = read_entry("word/styles.xml");
+
+    // 样式 ID → 标题层级
+    let mut style_levels: HashMap<String, u8> = HashMap::new();
+    if let Some(styles_xml) = &styles_xml {
+        let styles = Document::parse(styles_xml).map_err(|e| format!("解析 DOCX 样式失败: {}", e))?;
+        for style in styles.descendants().filter(|n| is_w(n, "style")) {
+            let Some(id) = style.attribute((W_NS, "styleId")) else { continue };
+            let name = w_val(&style, "name").unwrap_or(id);
+            let outline = style.children().find(|n| is_w(n, "pPr")).and_then(|p| w_val(&p, "outlineLvl"));
+            if let Some(level) = heading_level(name, outline) {
+                style_levels.insert(id.to_string(), level);
+            }
+        }
+    }
+
+    let document = Document::parse(&document_xml).map_err(|e| format!("解析 DOCX 失败: {}", e))?;
+    let mut blocks = Vec::new();
+    // 表格中的段落按文档顺序读取；文本框内的嵌套段落归入外层段落
+    for paragraph in document.descendants().filter(|n| is_w(n, "p")) {
+        if paragraph.ancestors().skip(1).any(|a| is_w(&a, "p")) {
+            continue;
+        }
+        let mut text = String::new();
+        for node in paragraph.descendants() {
+            if is_w(&node, "t") {
+                text.push_str(node.text().unwrap_or(""));
+            } else if is_w(&node, "tab") || is_w(&node, "br") || is_w(&node, "cr") {
+                text.push(' ');
+            }
+        }
+
+        let properties = paragraph.children().find(|n| is_w(n, "pPr"));
+        let level = properties.and_then(|p| {
+            w_val(&p, "pStyle")
+                .and_then(|id| style_levels.get(id).copied().or_else(|| heading_level(id, None)))
+                .or_else(|| w_val(&p, "outlineLvl").and_then(|o| heading_level("", Some(o))))
+        });
+        blocks.push((level, text));
+    }
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_ingest_docx_and_text() {
+        let document = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="{}"><w:body>
+<w:p><w:pPr><w:pStyle w:val="1"/></w:pPr><w:r><w:t>第一集</w:t></w:r></w:p>
+<w:p><w:r><w:t>1. 内景 客厅 夜</w:t></w:r></w:p>
+<w:p><w:r><w:t xml:space="preserve">小明推门</w:t></w:r><w:r><w:t>进来。</w:t></w:r></w:p>
+<w:p/>
+</w:body></w:document>"#,
+            W_NS
+        );
+        let styles = format!(
+            r#"<w:styles xmlns:w="{}"><w:style w:type="paragraph" w:styleId="1"><w:name w:val="heading 1"/></w:style></w:styles>"#,
+            W_NS
+        );
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buffer);
+            let options = zip::write::SimpleFileOptions::default();
+            zip.start_file("word/document.xml", options).unwrap();
+            zip.write_all(document.as_bytes()).unwrap();
+            zip.start_file("word/styles.xml", options).unwrap();
+            zip.write_all(styles.as_bytes()).unwrap();
+            zip.finish().unwrap();
+        }
+
+        let script = ingest_script("剧本.docx", buffer.get_ref()).unwrap();
+        assert_eq!(script.paragraphs.len(), 3);
+        assert_eq!(script.paragraphs[0].level, Some(1));
+        assert_eq!(script.paragraphs[1].kind, "scene_heading");
+        assert_eq!(script.text, "# 第一集\n1. 内景 客厅 夜\n小明推门进来。");
+
+        let (gbk, _, _) = encoding_rs::GBK.encode("## 序章\n\n外景 街道 日\n");
+        let script = ingest_script("notes.md", &gbk).unwrap();
+        assert_eq!(script.paragraphs[0].level, Some(2));
+        assert_eq!(script.paragraphs[1].kind, "scene_heading");
+        assert!(ingest_script("old.doc", b"").is_err());
+    }
+}
@@ -0,0 +1,448 @@
+use crate::commands::{
+    call_ai_api_with_custom_system, get_characters, get_props, get_scenes, save_generated_data, unix_timestamp,
+};
+use crate::db::ProjectDatabase;
+use crate::fountain::parse_fountain;
+use crate::models::{ApiConfig, Character, GenerationChunk, GenerationChunkEvent, Prop, Scene, Storyboard};
+use crate::screenplay::chunk_scenes;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tauri::{AppHandle, Emitter};
+
+/// 分块生成的运行时状态（分块本身持久化在项目库 generation_chunks 表中）
+#[derive(Default)]
+pub struct ScriptPipelineState {
+    /// 正在生成的项目目录 → 停止标记
+    running: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+impl ScriptPipelineState {
+    /// 启动项目分块生成；已在运行时返回 false
+    pub fn start(&self, app: AppHandle, folder_path: String, api_config: ApiConfig) -> Result<bool, String> {
+        let stop = {
+            let mut running = self.running.lock().map_err(|e| e.to_string())?;
+            if running.contains_key(&folder_path) {
+                return Ok(false);
+            }
+            let stop = Arc::new(AtomicBool::new(false));
+            running.insert(folder_path.clone(), stop.clone());
+            stop
+        };
+
+        let running = self.running.clone();
+        thread::spawn(move || {
+            run_pipeline(&app, &folder_path, &api_config, &stop);
+            if let Ok(mut running) = running.lock() {
+                running.remove(&folder_path);
+            }
+            let _ = app.emit("script-generation-finished", chunk_event(&folder_path, None, "finished", None));
+        });
+        Ok(true)
+    }
+
+    /// 请求停止（当前分块完成后停止，剩余分块保持 pending）
+    pub fn stop(&self, folder_path: &str) -> Result<bool, String> {
+        let running = self.running.lock().map_err(|e| e.to_string())?;
+        Ok(match running.get(folder_path) {
+            Some(stop) => {
+                stop.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        })
+    }
+
+    /// 项目是否正在分块生成
+    pub fn is_running(&self, folder_path: &str) -> bool {
+        self.running
+            .lock()
+            .map(|running| running.contains_key(folder_path))
+            .unwrap_or(false)
+    }
+}
+
+/// 第 index 块的镜号前缀：A … Z, AA, AB …
+pub fn chunk_prefix(index: usize) -> String {
+    let mut n = index + 1;
+    let mut prefix = Vec::new();
+    while n > 0 {
+        n -= 1;
+        prefix.push((b'A' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    prefix.iter().rev().collect()
+}
+
+/// 镜号的字母前缀（B12-1 → B）
+fn mirror_prefix(mirror_id: &str) -> &str {
+    let end = mirror_id.find(|c: char| !c.is_ascii_uppercase()).unwrap_or(mirror_id.len());
+    &mirror_id[..end]
+}
+
+/// 按场景切分源剧本并重建分块任务（会清除上一次的分块记录）
+pub fn plan_chunks(db: &ProjectDatabase, document_id: i64, text: &str, max_chars: usize) -> rusqlite::Result<usize> {
+    // 导入的源文档每段一行，补空行后才能按 Fountain 规则识别场景标题
+    let fountain = text.lines().collect::<Vec<_>>().join("\n\n");
+    let scenes = parse_fountain(&fountain).scenes();
+    let chunks = chunk_scenes(&scenes, max_chars);
+
+    let now = unix_timestamp().unwrap_or_default();
+    db.conn().execute("DELETE FROM generation_chunks", [])?;
+    for chunk in &chunks {
+        db.conn().execute(
+            "INSERT INTO generation_chunks (chunk_index, prefix, headings, text, status, updated_at, document_id)
+             VALUES (?1, ?2, ?3, ?4, 'pending', ?5, ?6)",
+            rusqlite::params![
+                chunk.index as i64,
+                chunk_prefix(chunk.index),
+                serde_json::to_string(&chunk.headings).unwrap_or_default(),
+                chunk.text,
+                now,
+                document_id,
+            ],
+        )?;
+    }
+    Ok(chunks.len())
+}
+
+/// 读取全部分块
+pub fn list_chunks(db: &ProjectDatabase) -> rusqlite::Result<Vec<GenerationChunk>> {
+    let mut stmt = db.conn().prepare(
+        "SELECT chunk_index, prefix, headings, text, status, error, storyboard_count, attempts, updated_at, document_id
+         FROM generation_chunks ORDER BY chunk_index",
+    )?;
+    let chunks = stmt.query_map([], chunk_from_row)?.collect();
+    chunks
+}
+
+fn chunk_from_row(row: &rusqlite::Row) -> rusqlite::Result<GenerationChunk> {
+    let headings: String = row.get(2)?;
+    Ok(GenerationChunk {
+        chunk_index: row.get(0)?,
+        prefix: row.get(1)?,
+        headings: serde_json::from_str(&headings).unwrap_or_default(),
+        text: row.get(3)?,
+        status: row.get(4)?,
+        error: row.get(5)?,
+        storyboard_count: row.get(6)?,
+        attempts: row.get(7)?,
+        updated_at: row.get(8)?,
+        document_id: row.get(9)?,
+    })
+}
+
+/// 续跑前把失败与中断（running）的分块恢复为 pending
+pub fn reset_unfinished_chunks(db: &ProjectDatabase) -> rusqlite::Result<usize> {
+    db.conn().execute(
+        "UPDATE generation_chunks SET status = 'pending', updated_at = ?1 WHERE status IN ('failed', 'running')",
+        [unix_timestamp().unwrap_or_default()],
+    )
+}
+
+/// 领取下一个 pending 分块（按顺序，保证资产列表逐块累积）
+fn claim_next_chunk(db: &ProjectDatabase) -> rusqlite::Result<Option<GenerationChunk>> {
+    let mut stmt = db.conn().prepare(
+        "UPDATE generation_chunks SET status = 'running', attempts = attempts + 1, error = NULL, updated_at = ?1
+         WHERE chunk_index = (SELECT chunk_index FROM generation_chunks WHERE status = 'pending' ORDER BY chunk_index LIMIT 1)
+         RETURNING chunk_index, prefix, headings, text, status, error, storyboard_count, attempts, updated_at, document_id",
+    )?;
+    let mut rows = stmt.query_map([unix_timestamp().unwrap_or_default()], chunk_from_row)?;
+    rows.next().transpose()
+}
+
+fn finish_chunk(db: &ProjectDatabase, chunk_index: i64, result: &Result<usize, String>) -> rusqlite::Result<()> {
+    let (status, count, error) = match result {
+        Ok(count) => ("done", *count as i64, None),
+        Err(e) => ("failed", 0, Some(e.as_str())),
+    };
+    db.conn().execute(
+        "UPDATE generation_chunks SET status = ?1, storyboard_count = ?2, error = ?3, updated_at = ?4 WHERE chunk_index = ?5",
+        rusqlite::params![status, count, error, unix_timestamp().unwrap_or_default(), chunk_index],
+    )?;
+    Ok(())
+}
+
+/// 依次处理 pending 分块；单块失败不影响后续分块，可稍后续跑
+fn run_pipeline(app: &AppHandle, folder_path: &str, api_config: &ApiConfig, stop: &AtomicBool) {
+    let path = PathBuf::from(folder_path);
+    while !stop.load(Ordering::SeqCst) {
+        let chunk = match ProjectDatabase::open(&path).and_then(|db| claim_next_chunk(&db)) {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("领取剧本分块失败: {}", e);
+                break;
+            }
+        };
+        let _ = app.emit("script-chunk-started", chunk_event(folder_path, Some(&chunk), "running", None));
+
+        let result = generate_chunk(folder_path, api_config, &chunk);
+        if let Err(e) = ProjectDatabase::open(&path).and_then(|db| finish_chunk(&db, chunk.chunk_index, &result)) {
+            eprintln!("更新剧本分块状态失败: {}", e);
+        }
+
+        match result {
+            Ok(_) => {
+                let _ = app.emit("script-chunk-completed", chunk_event(folder_path, Some(&chunk), "done", None));
+            }
+            Err(e) => {
+                let _ = app.emit("script-chunk-failed", chunk_event(folder_path, Some(&chunk), "failed", Some(e)));
+            }
+        }
+    }
+}
+
+/// 组装分块请求：镜号前缀规则 + 已有资产列表 + 本块剧本
+fn chunk_message(chunk: &GenerationChunk, total: i64, characters: &[Character], scenes: &[Scene], props: &[Prop]) -> String {
+    fn asset_line(label: &str, assets: Vec<(&str, Option<&str>)>) -> String {
+        if assets.is_empty() {
+            return format!("{}：无", label);
+        }
+        let items: Vec<String> = assets.into_iter().map(|(name, description)| {
+            let brief: String = description.unwrap_or("").chars().take(40).collect();
+            if brief.is_empty() { name.to_string() } else { format!("{}（{}）", name, brief) }
+        }).collect();
+        format!("{}：{}", label, items.join("、"))
+    }
+
+    let p = &chunk.prefix;
+    format!(
+        "【长剧本分块生成】第 {}/{} 块，包含场景：{}\n\
+         【镜号规则】本块所有分镜的 mirror_id 必须使用前缀 {}：{}1, {}2, {}3…，不要使用其他前缀\n\
+         【已有资产】以下资产已在前面的分块中建立，请直接用 #名称 引用，不要重复输出；characters / scenes / props 只返回本块新出现的资产\n\
+         {}\n{}\n{}\n\n【剧本】\n{}",
+        chunk.chunk_index + 1,
+        total,
+        chunk.headings.join("；"),
+        p, p, p, p,
+        asset_line("角色", characters.iter().map(|c| (c.name.as_str(), c.description.as_deref())).collect()),
+        asset_line("场景", scenes.iter().map(|s| (s.name.as_str(), s.description.as_deref())).collect()),
+        asset_line("道具", props.iter().map(|p| (p.name.as_str(), p.description.as_deref())).collect()),
+        chunk.text
+    )
+}
+
+/// 从模型回复中取出 JSON（兼容 ```json 代码块与前后说明文字）
+fn extract_json(response: &str) -> Option<&str> {
+    let body = match response.find("```json") {
+        Some(pos) => &response[pos + 7..],
+        None => response,
+    };
+    let start = body.find('{')?;
+    let end = body.rfind('}')?;
+    (end > start).then(|| &body[start..=end])
+}
+
+fn json_list<T: DeserializeOwned>(value: &serde_json::Value, key: &str) -> Result<Vec<T>, String> {
+    match value.get(key) {
+        Some(list) => serde_json::from_value(list.clone()).map_err(|e| format!("解析 {} 失败: {}", key, e)),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// 生成单个分块并合并进项目，返回本块分镜数
+fn generate_chunk(folder_path: &str, api_config: &ApiConfig, chunk: &GenerationChunk) -> Result<usize, String> {
+    let path = PathBuf::from(folder_path);
+    let total = ProjectDatabase::open(&path)
+        .and_then(|db| db.conn().query_row("SELECT COUNT(*) FROM generation_chunks", [], |row| row.get(0)))
+        .map_err(|e| format!("读取剧本分块失败: {}", e))?;
+    let characters = get_characters(folder_path.to_string())?;
+    let scenes = get_scenes(folder_path.to_string())?;
+    let props = get_props(folder_path.to_string())?;
+
+    let message = chunk_message(chunk, total, &characters, &scenes, &props);
+    let response = call_ai_api_with_custom_system(api_config.clone(), message, None, None)?;
+    let json = extract_json(&response).ok_or_else(|| "AI 返回内容中没有 JSON".to_string())?;
+    let value: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| format!("解析 AI 返回的 JSON 失败: {}", e))?;
+
+    let mut storyboards: Vec<Storyboard> = json_list(&value, "storyboards")?;
+    if storyboards.is_empty() {
+        return Err("AI 未返回分镜".to_string());
+    }
+    // 分块结果写入项目默认序列；序号先排在项目末尾，合并后在序列内统一重排
+    let (sequence_id, last_number) = ProjectDatabase::open(&path)
+        .and_then(|db| {
+            let sequence_id = db.default_sequence_id()?;
+            let last: i64 = db.conn().query_row(
+                "SELECT COALESCE(MAX(sequence_number), 0) FROM storyboards", [], |row| row.get(0),
+            )?;
+            Ok((sequence_id, last))
+        })
+        .map_err(|e| format!("读取默认序列失败: {}", e))?;
+    // 镜号须使用本块前缀，否则整块按顺序重新编号
+    let renumber = storyboards.iter()
+        .any(|sb| mirror_prefix(&sb.mirror_id) != chunk.prefix || sb.mirror_id.len() == chunk.prefix.len());
+    for (i, sb) in storyboards.iter_mut().enumerate() {
+        if renumber {
+            sb.mirror_id = format!("{}{}", chunk.prefix, i + 1);
+        }
+        sb.sequence_id = Some(sequence_id);
+        sb.sequence_number = last_number + i as i64 + 1;
+    }
+    let count = storyboards.len();
+
+    // 只补充新资产，已有资产保持前面分块确定的设定
+    let new_characters: Vec<Character> = json_list::<Character>(&value, "characters")?
+        .into_iter().filter(|c| !characters.iter().any(|e| e.name == c.name)).collect();
+    let new_scenes: Vec<Scene> = json_list::<Scene>(&value, "scenes")?
+        .into_iter().filter(|s| !scenes.iter().any(|e| e.name == s.name)).collect();
+    let new_props: Vec<Prop> = json_list::<Prop>(&value, "props")?
+        .into_iter().filter(|p| !props.iter().any(|e| e.name == p.name)).collect();
+
+    // 在分块所属的源剧本中定位镜头
+    save_generated_data(folder_path.to_string(), storyboards, new_characters, new_scenes, new_props, chunk.document_id)?;
+    let db = ProjectDatabase::open(&path).map_err(|e| format!("打开数据库失败: {}", e))?;
+    renumber_storyboards(&db, sequence_id).map_err(|e| format!("更新分镜序号失败: {}", e))?;
+    Ok(count)
+}
+
+/// 合并后按分块顺序重排指定序列中的分镜序号：只改动该序列的镜头，沿用它们已占用的序号位置，
+/// 镜号先去掉序列前缀（SQ2-B3 → B3）再匹配分块前缀；非分块生成的分镜排在最后，保持原有相对顺序
+fn renumber_storyboards(db: &ProjectDatabase, sequence_id: i64) -> rusqlite::Result<()> {
+    let chunk_prefixes: Vec<String> = list_chunks(db)?.into_iter().map(|c| c.prefix).collect();
+    let sequence_prefix: String = db.conn().query_row(
+        "SELECT prefix FROM sequences WHERE id = ?1",
+        [sequence_id],
+        |row| row.get(0),
+    )?;
+    let sequence_prefix = format!("{}-", sequence_prefix);
+    let mut rows: Vec<(String, i64)> = {
+        let mut stmt = db.conn().prepare("SELECT mirror_id, sequence_number FROM storyboards WHERE sequence_id = ?1")?;
+        let rows = stmt.query_map([sequence_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    let mut slots: Vec<i64> = rows.iter().map(|(_, sequence)| *sequence).collect();
+    slots.sort();
+    for i in 1..slots.len() {
+        slots[i] = slots[i].max(slots[i - 1] + 1);
+    }
+    rows.sort_by_key(|(mirror_id, sequence)| {
+        let local = mirror_id.strip_prefix(&sequence_prefix).unwrap_or(mirror_id);
+        let rank = chunk_prefixes.iter().position(|p| p == mirror_prefix(local)).unwrap_or(usize::MAX);
+        (rank, *sequence)
+    });
+    for ((mirror_id, _), slot) in rows.iter().zip(slots) {
+        db.conn().execute(
+            "UPDATE storyboards SET sequence_number = ?1 WHERE mirror_id = ?2",
+            rusqlite::params![slot, mirror_id],
+        )?;
+    }
+    Ok(())
+}
+
+/// 组装分块事件
+fn chunk_event(folder_path: &str, chunk: Option<&GenerationChunk>, status: &str, error: Option<String>) -> GenerationChunkEvent {
+    let (completed, failed, total) = ProjectDatabase::open(&PathBuf::from(folder_path))
+        .and_then(|db| {
+            db.conn().query_row(
+                "SELECT COALESCE(SUM(status = 'done'), 0), COALESCE(SUM(status = 'failed'), 0), COUNT(*)
+                 FROM generation_chunks",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+        })
+        .unwrap_or((0, 0, 0));
+
+    GenerationChunkEvent {
+        folder_path: folder_path.to_string(),
+        chunk_index: chunk.map(|c| c.chunk_index),
+        prefix: chunk.map(|c| c.prefix.clone()),
+        status: status.to_string(),
+        error,
+        completed,
+        failed,
+        total,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::temp_project;
+
+    #[test]
+    fn test_plan_and_resume_chunks() {
+        assert_eq!(chunk_prefix(0), "A");
+        assert_eq!(chunk_prefix(25), "Z");
+        assert_eq!(chunk_prefix(26), "AA");
+        assert_eq!(mirror_prefix("AB12-1"), "AB");
+        assert_eq!(extract_json("好的：\n```json\n{\"storyboards\": []}\n```"), Some("{\"storyboards\": []}"));
+
+        let dir = temp_project("script_pipeline");
+        let db = ProjectDatabase::open(&dir).unwrap();
+        let scene = "小明在雨中奔跑，衣服湿透，路灯一盏盏掠过，他终于停在一扇门前。".repeat(4);
+        let text = format!("1. 内景 客厅 夜\n{}\n2. 外景 街道 夜\n{}\n3. 内景 车内 夜\n{}", scene, scene, scene);
+        assert_eq!(plan_chunks(&db, 7, &text, 200).unwrap(), 3);
+
+        let first = claim_next_chunk(&db).unwrap().unwrap();
+        assert_eq!((first.chunk_index, first.prefix.as_str()), (0, "A"));
+        assert_eq!(first.document_id, Some(7));
+        assert_eq!(first.headings, vec!["1. 内景 客厅 夜"]);
+        finish_chunk(&db, 0, &Err("超时".to_string())).unwrap();
+        let second = claim_next_chunk(&db).unwrap().unwrap();
+        assert_eq!(second.prefix, "B");
+        finish_chunk(&db, 1, &Ok(4)).unwrap();
+
+        // 续跑：失败的分块重新排队，已完成的不再处理
+        assert_eq!(reset_unfinished_chunks(&db).unwrap(), 1);
+        assert_eq!(claim_next_chunk(&db).unwrap().unwrap().chunk_index, 0);
+        assert_eq!(claim_next_chunk(&db).unwrap().unwrap().chunk_index, 2);
+        assert!(claim_next_chunk(&db).unwrap().is_none());
+
+        let sequence_id = db.default_sequence_id().unwrap();
+        db.conn().execute(
+            "INSERT INTO storyboards (mirror_id, sequence_number, sequence_id) VALUES ('B1', 1, ?1), ('X9', 2, ?1), ('A2', 3, ?1), ('A1', 4, ?1)",
+            [sequence_id],
+        ).unwrap();
+        db.conn().execute("UPDATE storyboards SET sequence_number = 1 WHERE mirror_id = 'A1'", []).unwrap();
+        renumber_storyboards(&db, sequence_id).unwrap();
+        let order: Vec<String> = db.conn().prepare("SELECT mirror_id FROM storyboards ORDER BY sequence_number").unwrap()
+            .query_map([], |row| row.get(0)).unwrap().collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(order, vec!["A1", "A2", "B1", "X9"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_renumber_with_sequence_prefixes() {
+        use crate::commands::save_episode;
+
+        let dir = temp_project("script_pipeline_sequences");
+        let folder = dir.to_string_lossy().to_string();
+        let episode_id = save_episode(folder, None, "第2集".to_string()).unwrap();
+        let db = ProjectDatabase::open(&dir).unwrap();
+        let first = db.default_sequence_id().unwrap();
+        let second: i64 = db.conn().query_row(
+            "SELECT id FROM sequences WHERE episode_id = ?1", [episode_id], |row| row.get(0),
+        ).unwrap();
+        let scene = "小明在雨中奔跑，衣服湿透，路灯一盏盏掠过，他终于停在一扇门前。".repeat(4);
+        let text = format!("1. 内景 客厅 夜\n{}\n2. 外景 街道 夜\n{}", scene, scene);
+        assert_eq!(plan_chunks(&db, 1, &text, 200).unwrap(), 2);
+
+        // 第2集的镜头占用序号 2、4，第1集分块 B 先于 A 写入
+        db.conn().execute(
+            "INSERT INTO storyboards (mirror_id, sequence_number, sequence_id) VALUES
+             ('SQ1-B1', 1, ?1), ('SQ2-A1', 2, ?2), ('SQ1-A1', 3, ?1), ('SQ2-A2', 4, ?2), ('SQ1-A2', 5, ?1)",
+            [first, second],
+        ).unwrap();
+        renumber_storyboards(&db, first).unwrap();
+
+        let order: Vec<(String, i64)> = db.conn().prepare("SELECT mirror_id, sequence_number FROM storyboards ORDER BY mirror_id").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap().collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(order, vec![
+            ("SQ1-A1".to_string(), 1),
+            ("SQ1-A2".to_string(), 3),
+            ("SQ1-B1".to_string(), 5),
+            ("SQ2-A1".to_string(), 2),
+            ("SQ2-A2".to_string(), 4),
+        ]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::models::{Storyboard, StoryboardSource};
+use crate::screenplay::is_scene_heading;
+use crate::subtitles::parse_speaker;
+use std::collections::HashSet;
+
+/// 描述匹配只在上一镜位置之后的这么多行内查找，避免跳到很远处的相似段落
+const DESCRIPTION_WINDOW: usize = 40;
+
+/// 只保留文字与数字并转小写，忽略标点与空白差异
+fn normalize(text: &str) -> Vec<char> {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+fn bigrams(chars: &[char]) -> HashSet<(char, char)> {
+    chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+/// 查询文本与某行的相似度：互相包含为 1，否则为查询二元组在该行中出现的比例
+fn similarity(query: &[char], line: &[char]) -> f64 {
+    if query.len() < 2 || line.len() < 2 {
+        return 0.0;
+    }
+    let contains = |haystack: &[char], needle: &[char]| haystack.windows(needle.len()).any(|w| w == needle);
+    if (query.len() >= 4 && contains(line, query)) || (line.len() >= 4 && contains(query, line)) {
+        return 1.0;
+    }
+    let query_grams = bigrams(query);
+    let line_grams = bigrams(line);
+    query_grams.intersection(&line_grams).count() as f64 / query_grams.len() as f64
+}
+
+/// 是否为文档标题行（导入时以 # 标记）
+fn is_document_heading(line: &str) -> bool {
+    line.trim_start().starts_with('#')
+}
+
+/// 按分镜顺序在源文档中定位每个镜头：优先匹配 source_text 与对白，其次匹配画面描述；
+/// 返回的位置只包含直接匹配到的行，后续镜头不会定位到前一镜之前
+pub fn align_storyboards(lines: &[&str], storyboards: &[Storyboard], document_id: i64) -> Vec<StoryboardSource> {
+    let normalized: Vec<Vec<char>> = lines.iter().map(|l| normalize(l)).collect();
+    let mut ordered: Vec<&Storyboard> = storyboards.iter().collect();
+    ordered.sort_by_key(|sb| sb.sequence_number);
+
+    let mut cursor = 0;
+    let mut sources = Vec::new();
+    for sb in ordered {
+        // 强匹配：原文摘录与每句对白，须在游标之后找到
+        let mut queries: Vec<Vec<char>> = Vec::new();
+        for text in [sb.source_text.as_deref(), sb.dialogue.as_deref()].into_iter().flatten() {
+            for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                let line = match parse_speaker(line) {
+                    Some(_) => line.split_once(['：', ':']).map(|(_, t)| t).unwrap_or(line),
+                    None => line,
+                };
+                queries.push(normalize(line));
+            }
+        }
+        let mut matched: Vec<usize> = queries.iter()
+            .filter_map(|query| (cursor..lines.len()).find(|&i| similarity(query, &normalized[i]) >= 0.6))
+            .collect();
+
+        // 弱匹配：画面描述（去掉 #资产 引用）在窗口内取最相似的一行
+        if matched.is_empty() {
+            let description: String = sb.description.as_deref().unwrap_or("")
+                .split_whitespace()
+                .map(|w| w.trim_start_matches('#'))
+                .collect();
+            let query = normalize(&description);
+            let end = (cursor + DESCRIPTION_WINDOW).min(lines.len());
+            let best = (cursor..end)
+                .map(|i| (i, similarity(&query, &normalized[i])))
+                .filter(|(_, score)| *score >= 0.35)
+                .fold(None, |best: Option<(usize, f64)>, (i, score)| match best {
+                    Some((_, best_score)) if best_score >= score => best,
+                    _ => Some((i, score)),
+                });
+            matched.extend(best.map(|(i, _)| i));
+        }
+
+        if let (Some(&start), Some(&end)) = (matched.iter().min(), matched.iter().max()) {
+            cursor = start;
+            sources.push(StoryboardSource {
+                mirror_id: sb.mirror_id.clone(),
+                document_id,
+                start_line: start,
+                end_line: end,
+            });
+        }
+    }
+    sources
+}
+
+/// 将每个镜头的范围向后延伸到下一个镜头开始处，遇到场景标题或文档标题即停止，
+/// 使一场戏中未被直接引用的动作描写也算作已覆盖
+pub fn extend_sources(lines: &[&str], sources: &[StoryboardSource]) -> Vec<StoryboardSource> {
+    let mut extended: Vec<StoryboardSource> = sources.to_vec();
+    extended.sort_by_key(|s| (s.start_line, s.end_line));
+    let starts: Vec<usize> = extended.iter().map(|s| s.start_line).collect();
+
+    for source in extended.iter_mut() {
+        let next_start = starts.iter().copied().find(|&s| s > source.end_line).unwrap_or(lines.len());
+        let mut end = source.end_line;
+        while end + 1 < next_start {
+            let line = lines[end + 1];
+            if is_scene_heading(line) || is_document_heading(line) {
+                break;
+            }
+            end += 1;
+        }
+        source.end_line = end;
+    }
+    extended
+}
+
+/// 没有任何镜头覆盖的连续段落（空行与文档标题不计，场景标题随本场内容判断）
+pub fn uncovered_ranges(lines: &[&str], sources: &[StoryboardSource]) -> Vec<(usize, usize)> {
+    let mut covered = vec![false; lines.len()];
+    for source in extend_sources(lines, sources) {
+        for flag in covered.iter_mut().take(source.end_line + 1).skip(source.start_line) {
+            *flag = true;
+        }
+    }
+    // 场景标题随本场内容一起算作已覆盖
+    for i in (0..lines.len()).rev() {
+        if is_scene_heading(lines[i]) {
+            covered[i] = (i + 1..lines.len())
+                .take_while(|&j| !is_scene_heading(lines[j]) && !is_document_heading(lines[j]))
+                .any(|j| covered[j]);
+        }
+    }
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() || is_document_heading(line) {
+            continue;
+        }
+        if covered[i] {
+            ranges.extend(current.take());
+            continue;
+        }
+        current = match current {
+            Some((start, _)) => Some((start, i)),
+            None => Some((i, i)),
+        };
+    }
+    ranges.extend(current);
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_align_and_uncovered() {
+        let text = "# 第一集\n1. 内景 客厅 夜\n小明推门进来，环顾四周。\n小明：有人吗？\n屋里一片寂静。\n2. 外景 街道 夜\n雨越下越大。\n3. 内景 车内 夜\n小红发动汽车。";
+        let lines: Vec<&str> = text.lines().collect();
+        let storyboards = vec![
+            Storyboard {
+                sequence_number: 1,
+                mirror_id: "A1".to_string(),
+                description: Some("#小明 推门进来 环顾四周".to_string()),
+                ..Default::default()
+            },
+            Storyboard {
+                sequence_number: 2,
+                mirror_id: "A2".to_string(),
+                dialogue: Some("小明：有人吗?".to_string()),
+                ..Default::default()
+            },
+            Storyboard {
+                sequence_number: 3,
+                mirror_id: "A3".to_string(),
+                source_text: Some("小红发动汽车".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        let sources = align_storyboards(&lines, &storyboards, 1);
+        let spans: Vec<(&str, usize, usize)> = sources.iter()
+            .map(|s| (s.mirror_id.as_str(), s.start_line, s.end_line))
+            .collect();
+        assert_eq!(spans, vec![("A1", 2, 2), ("A2", 3, 3), ("A3", 8, 8)]);
+
+        let extended = extend_sources(&lines, &sources);
+        assert_eq!(extended[1].end_line, 4);
+        // 第 2 场（街道）整场没有镜头
+        assert_eq!(uncovered_ranges(&lines, &sources), vec![(5, 6)]);
+    }
+}
@@ -0,0 +1,352 @@
+use crate::models::{Character, ImportColumn, ImportSheet, Prop, Scene, Storyboard};
+use calamine::{open_workbook_auto, Reader};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// 预览时返回的样例行数
+const SAMPLE_ROWS: usize = 5;
+
+/// 分镜字段及可识别的表头（中英文、含导出的 Excel 表头）
+const STORYBOARD_FIELDS: &[(&str, &[&str])] = &[
+    ("sequence_number", &["序号", "编号", "顺序", "sequence_number", "sequence", "seq", "no", "#", "index"]),
+    ("mirror_id", &["镜号", "镜头号", "镜头编号", "分镜号", "mirror_id", "shot", "shot_id", "shotno", "shotnumber"]),
+    ("shot_size", &["景别", "shot_size", "size", "framing"]),
+    ("shot_type", &["镜头类型", "镜头", "拍摄方式", "shot_type", "type", "camera"]),
+    ("duration", &["时长", "时长(秒)", "时长(s)", "秒数", "duration", "duration(s)", "length", "time"]),
+    ("dialogue", &["对白", "台词", "对话", "dialogue", "dialog", "line", "lines"]),
+    ("description", &["画面描述", "画面", "描述", "画面内容", "内容", "description", "action", "visual"]),
+    ("notes", &["备注", "说明", "notes", "note", "remarks", "comment", "comments"]),
+    ("image_prompt_zh", &["首帧提示词(中)", "提示词(中)", "中文提示词", "提示词", "image_prompt_zh", "prompt_cn", "prompt_zh"]),
+    ("image_prompt_en", &["首帧提示词(英)", "提示词(英)", "英文提示词", "image_prompt_en", "prompt_en", "prompt"]),
+    ("image_prompt_tail_zh", &["尾帧提示词(中)", "image_prompt_tail_zh"]),
+    ("image_prompt_tail_en", &["尾帧提示词(英)", "image_prompt_tail_en"]),
+    ("video_prompt_zh", &["视频提示词(中)", "视频提示词", "video_prompt_zh"]),
+    ("video_prompt_en", &["视频提示词(英)", "video_prompt_en", "video_prompt"]),
+];
+
+/// 资产字段（角色 / 场景 / 道具通用）
+const ASSET_FIELDS: &[(&str, &[&str])] = &[
+    ("name", &["名称", "名字", "角色", "角色名", "场景", "场景名", "道具", "道具名", "name", "character", "scene", "prop"]),
+    ("description", &["描述", "介绍", "设定", "description"]),
+    ("image_prompt_zh", &["提示词(中)", "中文提示词", "提示词", "image_prompt_zh", "prompt_cn", "prompt_zh"]),
+    ("image_prompt_en", &["提示词(英)", "英文提示词", "image_prompt_en", "prompt_en", "prompt"]),
+    ("notes", &["备注", "说明", "notes", "note", "remarks"]),
+];
+
+/// 工作表：(名称, 全部行)
+pub type Table = (String, Vec<Vec<String>>);
+
+/// 从表格中解析出的数据
+#[derive(Default)]
+pub struct ImportedData {
+    pub storyboards: Vec<Storyboard>,
+    pub characters: Vec<Character>,
+    pub scenes: Vec<Scene>,
+    pub props: Vec<Prop>,
+    /// 校验问题，非空时不应写入项目
+    pub issues: Vec<String>,
+}
+
+/// 读取表格文件：xlsx/xls/ods 读取全部工作表，csv 作为单个工作表
+pub fn read_tables(path: &Path) -> Result<Vec<Table>, String> {
+    let extension = path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    if extension == "csv" {
+        let name = path.file_stem().and_then(|n| n.to_str()).unwrap_or("csv").to_string();
+        let bytes = std::fs::read(path).map_err(|e| format!("读取文件失败: {}", e))?;
+        return Ok(vec![(name, parse_csv(&bytes)?)]);
+    }
+
+    let mut workbook = open_workbook_auto(path).map_err(|e| format!("打开表格失败: {}", e))?;
+    let mut tables = Vec::new();
+    for name in workbook.sheet_names() {
+        let range = workbook.worksheet_range(&name)
+            .map_err(|e| format!("读取工作表 {} 失败: {}", name, e))?;
+        let rows: Vec<Vec<String>> = range.rows()
+            .map(|row| row.iter().map(|cell| cell.to_string().trim().to_string()).collect())
+            .collect();
+        tables.push((name, rows));
+    }
+    Ok(tables)
+}
+
+/// 解析 CSV：支持 UTF-8（含 BOM），非 UTF-8 时按 GBK 解码（Excel 中文版默认编码）
+fn parse_csv(bytes: &[u8]) -> Result<Vec<Vec<String>>, String> {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::GBK.decode(bytes).0.into_owned(),
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    reader.records()
+        .map(|record| {
+            record
+                .map(|r| r.iter().map(|cell| cell.trim().to_string()).collect())
+                .map_err(|e| format!("解析 CSV 失败: {}", e))
+        })
+        .collect()
+}
+
+/// 规范化表头：小写，去掉空白、下划线、连字符，全角括号转半角
+fn normalize_header(header: &str) -> String {
+    header.chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '_' | '-'))
+        .map(|c| match c {
+            '（' => '(',
+            '）' => ')',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+fn fields_for(kind: &str) -> &'static [(&'static str, &'static [&'static str])] {
+    if kind == "storyboard" { STORYBOARD_FIELDS } else { ASSET_FIELDS }
+}
+
+/// 按表头匹配字段，每个字段只匹配第一列
+fn map_columns(kind: &str, headers: &[String]) -> Vec<ImportColumn> {
+    let mut used = HashSet::new();
+    headers.iter().enumerate().map(|(index, header)| {
+        let normalized = normalize_header(header);
+        let field = fields_for(kind).iter()
+            .find(|(field, aliases)| {
+                !used.contains(field) && aliases.iter().any(|alias| normalize_header(alias) == normalized)
+            })
+            .map(|(field, _)| *field);
+        if let Some(field) = field {
+            used.insert(field);
+        }
+        ImportColumn { index, header: header.clone(), field: field.map(str::to_string) }
+    }).collect()
+}
+
+/// 按工作表名或表头判断数据类型
+fn detect_kind(sheet_name: &str, headers: &[String]) -> Option<String> {
+    let name = normalize_header(sheet_name);
+    let by_name = [
+        ("storyboard", ["分镜", "镜头", "storyboard", "shot"]),
+        ("character", ["角色", "人物", "character", "cast"]),
+        ("scene", ["场景", "scene", "location", "地点"]),
+        ("prop", ["道具", "prop", "物品", "item"]),
+    ];
+    if let Some((kind, _)) = by_name.iter().find(|(_, keys)| keys.iter().any(|k| name.contains(k))) {
+        return Some(kind.to_string());
+    }
+    // 单表（如 CSV）：有镜号列即视为分镜
+    map_columns("storyboard", headers).iter()
+        .any(|c| c.field.as_deref() == Some("mirror_id"))
+        .then(|| "storyboard".to_string())
+}
+
+/// 生成各工作表的自动映射（首个非空行作为表头）
+pub fn preview_sheets(tables: &[Table]) -> Vec<ImportSheet> {
+    tables.iter().map(|(name, rows)| {
+        let (headers, body) = split_header(rows);
+        let kind = detect_kind(name, &headers);
+        let columns = match &kind {
+            Some(kind) => map_columns(kind, &headers),
+            None => headers.iter().enumerate()
+                .map(|(index, header)| ImportColumn { index, header: header.clone(), field: None })
+                .collect(),
+        };
+        ImportSheet {
+            name: name.clone(),
+            kind,
+            columns,
+            row_count: body.len(),
+            sample_rows: body.iter().take(SAMPLE_ROWS).map(|(_, row)| (*row).clone()).collect(),
+        }
+    }).collect()
+}
+
+/// 拆分表头与数据行，跳过开头的空行与数据中的空行；数据行附带其在表格中的行号（从 1 开始）
+fn split_header(rows: &[Vec<String>]) -> (Vec<String>, Vec<(usize, &Vec<String>)>) {
+    let mut non_empty = rows.iter()
+        .enumerate()
+        .filter(|(_, row)| row.iter().any(|c| !c.is_empty()))
+        .map(|(index, row)| (index + 1, row));
+    let headers = non_empty.next().map(|(_, row)| row.clone()).unwrap_or_default();
+    (headers, non_empty.collect())
+}
+
+/// 按映射解析并校验数据；已有分镜未映射或为空的字段保留原值
+pub fn parse_sheets(
+    tables: &[Table],
+    sheets: &[ImportSheet],
+    existing: &[Storyboard],
+) -> ImportedData {
+    let mut data = ImportedData::default();
+    let existing: HashMap<&str, &Storyboard> = existing.iter().map(|sb| (sb.mirror_id.as_str(), sb)).collect();
+    let mut next_sequence = existing.values().map(|sb| sb.sequence_number).max().unwrap_or(0) + 1;
+    let mut seen_mirror_ids = HashSet::new();
+
+    for sheet in sheets {
+        let Some(kind) = sheet.kind.as_deref() else {
+            continue;
+        };
+        let Some((_, rows)) = tables.iter().find(|(name, _)| *name == sheet.name) else {
+            data.issues.push(format!("找不到工作表: {}", sheet.name));
+            continue;
+        };
+        let (_, body) = split_header(rows);
+        let columns: Vec<(usize, &str)> = sheet.columns.iter()
+            .filter_map(|c| c.field.as_deref().map(|f| (c.index, f)))
+            .collect();
+
+        for (line, row) in body {
+            let location = format!("{} 第 {} 行", sheet.name, line);
+            let values: HashMap<&str, &str> = columns.iter()
+                .filter_map(|(index, field)| {
+                    row.get(*index).filter(|v| !v.is_empty()).map(|v| (*field, v.as_str()))
+                })
+                .collect();
+            let text = |field: &str| values.get(field).map(|v| v.to_string());
+
+            if kind == "storyboard" {
+                let Some(mirror_id) = text("mirror_id") else {
+                    data.issues.push(format!("{}: 缺少镜号", location));
+                    continue;
+                };
+                if !seen_mirror_ids.insert(mirror_id.clone()) {
+                    data.issues.push(format!("{}: 镜号 {} 重复", location, mirror_id));
+                    continue;
+                }
+
+                let mut sb = existing.get(mirror_id.as_str())
+                    .map(|sb| (*sb).clone())
+                    .unwrap_or_else(|| Storyboard {
+                        mirror_id: mirror_id.clone(),
+                        sequence_number: 0,
+                        ..Default::default()
+                    });
+                match values.get("sequence_number").map(|v| v.parse::<f64>()) {
+                    Some(Ok(n)) if n.fract() == 0.0 && n > 0.0 => sb.sequence_number = n as i64,
+                    Some(_) => {
+                        data.issues.push(format!("{}: 序号不是正整数", location));
+                        continue;
+                    }
+                    None if sb.sequence_number == 0 => {
+                        sb.sequence_number = next_sequence;
+                        next_sequence += 1;
+                    }
+                    None => {}
+                }
+                if let Some(duration) = values.get("duration") {
+                    let seconds = duration.trim_end_matches(['s', 'S', '秒']).trim();
+                    match seconds.parse::<f64>() {
+                        Ok(d) if d >= 0.0 => sb.duration = Some(d),
+                        _ => {
+                            data.issues.push(format!("{}: 时长 \"{}\" 不是有效数字", location, duration));
+                            continue;
+                        }
+                    }
+                }
+                let targets = [
+                    ("shot_size", &mut sb.shot_size),
+                    ("shot_type", &mut sb.shot_type),
+                    ("dialogue", &mut sb.dialogue),
+                    ("description", &mut sb.description),
+                    ("notes", &mut sb.notes),
+                    ("image_prompt_zh", &mut sb.image_prompt_zh),
+                    ("image_prompt_en", &mut sb.image_prompt_en),
+                    ("image_prompt_tail_zh", &mut sb.image_prompt_tail_zh),
+                    ("image_prompt_tail_en", &mut sb.image_prompt_tail_en),
+                    ("video_prompt_zh", &mut sb.video_prompt_zh),
+                    ("video_prompt_en", &mut sb.video_prompt_en),
+                ];
+                for (field, target) in targets {
+                    if let Some(value) = text(field) {
+                        *target = Some(value);
+                    }
+                }
+                data.storyboards.push(sb);
+            } else {
+                let Some(name) = text("name") else {
+                    data.issues.push(format!("{}: 缺少名称", location));
+                    continue;
+                };
+                let (description, image_prompt_zh, image_prompt_en, notes) =
+                    (text("description"), text("image_prompt_zh"), text("image_prompt_en"), text("notes"));
+                match kind {
+                    "character" => data.characters.push(Character { name, description, image_prompt_zh, image_prompt_en, notes, reference_image_path: None }),
+                    "scene" => data.scenes.push(Scene { name, description, image_prompt_zh, image_prompt_en, notes, reference_image_path: None }),
+                    "prop" => data.props.push(Prop { name, description, image_prompt_zh, image_prompt_en, notes, reference_image_path: None }),
+                    other => {
+                        data.issues.push(format!("{}: 未知的数据类型 {}", sheet.name, other));
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_and_parse_csv() {
+        let csv = "\u{FEFF}镜号,景别,时长（秒）,台词,Notes\nA1,近景,2.5,你好,\n,,,,\nA2,远景,3s,,夜景\n,特写,1,,\n";
+        let tables = vec![("shots".to_string(), parse_csv(csv.as_bytes()).unwrap())];
+        let sheets = preview_sheets(&tables);
+
+        assert_eq!(sheets[0].kind.as_deref(), Some("storyboard"));
+        let fields: Vec<_> = sheets[0].columns.iter().map(|c| c.field.as_deref()).collect();
+        assert_eq!(fields, vec![Some("mirror_id"), Some("shot_size"), Some("duration"), Some("dialogue"), Some("notes")]);
+        assert_eq!(sheets[0].row_count, 3);
+
+        let existing = Storyboard {
+            sequence_number: 7,
+            mirror_id: "A2".to_string(),
+            description: Some("原有描述".to_string()),
+            ..Default::default()
+        };
+        let data = parse_sheets(&tables, &sheets, &[existing]);
+        assert_eq!(data.issues, vec!["shots 第 5 行: 缺少镜号".to_string()]);
+        assert_eq!(data.storyboards[0].sequence_number, 8);
+        assert_eq!(data.storyboards[1].sequence_number, 7);
+        assert_eq!(data.storyboards[1].duration, Some(3.0));
+        assert_eq!(data.storyboards[1].description.as_deref(), Some("原有描述"));
+    }
+
+    #[test]
+    fn test_partial_asset_import_keeps_fields() {
+        use crate::commands::{get_characters, import_spreadsheet};
+
+        let dir = crate::db::tests::temp_project("partial_asset_import");
+        let folder = dir.to_string_lossy().to_string();
+        let import = |csv: &str| {
+            let path = dir.join("characters.csv");
+            std::fs::write(&path, csv).unwrap();
+            import_spreadsheet(folder.clone(), path.to_string_lossy().to_string(), None).unwrap();
+        };
+
+        import("名称,描述,提示词,备注\n小明,主角,少年,旧备注\n");
+        // 只含名称与备注两列：未导入的字段保留原值
+        import("名称,备注\n小明,新备注\n");
+
+        let character = get_characters(folder).unwrap().remove(0);
+        assert_eq!(character.description.as_deref(), Some("主角"));
+        assert_eq!(character.image_prompt_zh.as_deref(), Some("少年"));
+        assert_eq!(character.notes.as_deref(), Some("新备注"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_gbk_csv() {
+        let (bytes, _, _) = encoding_rs::GBK.encode("名称,描述\n小明,主角\n");
+        let rows = parse_csv(&bytes).unwrap();
+        assert_eq!(rows[1], vec!["小明".to_string(), "主角".to_string()]);
+    }
+}
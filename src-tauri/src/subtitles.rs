@@ -0,0 +1,162 @@
+use crate::models::Storyboard;
+
+/// 一条字幕
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleCue {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// 字幕生成选项
+pub struct CueOptions {
+    pub default_duration: f64,
+    /// 合并同一说话人的连续镜头
+    pub merge_same_speaker: bool,
+    /// 跳过没有对白的镜头；不跳过时以镜号作为字幕占位，便于对照动态分镜
+    pub skip_empty: bool,
+}
+
+/// 从对白中解析说话人（“角色：台词” 或 “Name: line”）；纯数字或时间（如 “10:30 到了”）不算说话人
+pub fn parse_speaker(dialogue: &str) -> Option<&str> {
+    let (speaker, line) = dialogue.split_once(['：', ':'])?;
+    let speaker = speaker.trim();
+    let valid = !speaker.is_empty()
+        && !line.trim().is_empty()
+        && speaker.chars().count() <= 20
+        && speaker.chars().any(char::is_alphabetic)
+        && !speaker.contains(['\n', '。', '，', '.', ',']);
+    valid.then_some(speaker)
+}
+
+/// 按 sequence_number 顺序累计时间码生成字幕
+pub fn build_cues(storyboards: &[Storyboard], options: &CueOptions) -> Vec<SubtitleCue> {
+    let mut ordered: Vec<&Storyboard> = storyboards.iter().collect();
+    ordered.sort_by_key(|sb| sb.sequence_number);
+
+    let mut cues: Vec<SubtitleCue> = Vec::new();
+    // 上一条字幕的说话人，仅在紧邻的上一镜有对白时有效
+    let mut last_speaker: Option<String> = None;
+    let mut time = 0.0;
+
+    for sb in ordered {
+        let duration = sb.duration.filter(|d| *d > 0.0).unwrap_or(options.default_duration);
+        let (start, end) = (time, time + duration);
+        time = end;
+
+        let dialogue = sb.dialogue.as_deref().map(str::trim).unwrap_or("");
+        if dialogue.is_empty() {
+            last_speaker = None;
+            if !options.skip_empty {
+                cues.push(SubtitleCue { start, end, text: format!("[{}]", sb.mirror_id) });
+            }
+            continue;
+        }
+
+        let speaker = parse_speaker(dialogue).map(str::to_string);
+        if options.merge_same_speaker && speaker.is_some() && speaker == last_speaker {
+            if let Some(cue) = cues.last_mut() {
+                cue.end = end;
+                cue.text.push('\n');
+                cue.text.push_str(dialogue);
+                continue;
+            }
+        }
+
+        cues.push(SubtitleCue { start, end, text: dialogue.to_string() });
+        last_speaker = speaker;
+    }
+
+    cues
+}
+
+/// 格式化时间码 HH:MM:SS,mmm（SRT 用逗号，WebVTT 用句点）
+fn format_timestamp(seconds: f64, separator: char) -> String {
+    let total_ms = (seconds * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        total_ms / 3_600_000,
+        total_ms / 60_000 % 60,
+        total_ms / 1000 % 60,
+        separator,
+        total_ms % 1000
+    )
+}
+
+/// 输出 SRT
+pub fn to_srt(cues: &[SubtitleCue]) -> String {
+    cues.iter()
+        .enumerate()
+        .map(|(i, cue)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                i + 1,
+                format_timestamp(cue.start, ','),
+                format_timestamp(cue.end, ','),
+                cue.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 输出 WebVTT
+pub fn to_vtt(cues: &[SubtitleCue]) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+    for cue in cues {
+        vtt.push_str(&format!(
+            "\n{} --> {}\n{}\n",
+            format_timestamp(cue.start, '.'),
+            format_timestamp(cue.end, '.'),
+            cue.text
+        ));
+    }
+    vtt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn shot(sequence_number: i64, duration: f64, dialogue: &str) -> Storyboard {
+        serde_json::from_value(json!({
+            "sequence_number": sequence_number,
+            "mirror_id": format!("A{}", sequence_number),
+            "duration": duration,
+            "dialogue": dialogue,
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_build_cues() {
+        let shots = vec![
+            shot(3, 1.5, "小明：再见"),
+            shot(1, 2.0, "小明：你好"),
+            shot(2, 1.0, "小明：今天天气不错"),
+            shot(4, 2.0, ""),
+        ];
+        let mut options = CueOptions { default_duration: 3.0, merge_same_speaker: true, skip_empty: true };
+
+        let cues = build_cues(&shots, &options);
+        assert_eq!(cues.len(), 1);
+        assert_eq!((cues[0].start, cues[0].end), (0.0, 4.5));
+        assert_eq!(to_srt(&cues), "1\n00:00:00,000 --> 00:00:04,500\n小明：你好\n小明：今天天气不错\n小明：再见\n");
+
+        options.merge_same_speaker = false;
+        options.skip_empty = false;
+        let cues = build_cues(&shots, &options);
+        assert_eq!(cues.len(), 4);
+        assert_eq!(cues[3].text, "[A4]");
+        assert!(to_vtt(&cues).starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:02.000\n"));
+    }
+
+    #[test]
+    fn test_parse_speaker() {
+        assert_eq!(parse_speaker("小明：你好"), Some("小明"));
+        assert_eq!(parse_speaker("JOHN: Let's go."), Some("JOHN"));
+        assert_eq!(parse_speaker("10:30 到了"), None);
+        assert_eq!(parse_speaker("2024：新的一年"), None);
+        assert_eq!(parse_speaker("小明："), None);
+    }
+}
@@ -0,0 +1,29 @@
+use image::{DynamicImage, ImageFormat};
+use std::io::Cursor;
+use std::path::Path;
+
+/// 读取图片并缩放到最长边不超过 max_edge（原图更小时保持不变）
+pub fn load_thumbnail(path: &Path, max_edge: u32) -> Result<DynamicImage, String> {
+    let image = image::open(path).map_err(|e| format!("读取图片失败 {}: {}", path.display(), e))?;
+    if image.width() <= max_edge && image.height() <= max_edge {
+        return Ok(image);
+    }
+    Ok(image.thumbnail(max_edge, max_edge))
+}
+
+/// 编码为 PNG
+pub fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut buffer = Cursor::new(Vec::new());
+    image.write_to(&mut buffer, ImageFormat::Png)
+        .map_err(|e| format!("编码图片失败: {}", e))?;
+    Ok(buffer.into_inner())
+}
+
+/// 编码为 JPEG（去掉透明通道）
+pub fn encode_jpeg(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut buffer = Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(image.to_rgb8())
+        .write_to(&mut buffer, ImageFormat::Jpeg)
+        .map_err(|e| format!("编码图片失败: {}", e))?;
+    Ok(buffer.into_inner())
+}
@@ -0,0 +1,303 @@
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// 时间线上的一个镜头
+pub struct TimelineClip {
+    pub name: String,
+    /// 媒体文件完整路径，None 表示没有可用的图片或视频
+    pub media: Option<PathBuf>,
+    /// 媒体是否为视频（否则为静帧图片）
+    pub is_video: bool,
+    /// 时长（帧）
+    pub frames: u64,
+    /// 标记：(名称, 内容)，来自备注与对白
+    pub markers: Vec<(String, String)>,
+}
+
+/// 帧率，23.976 / 29.97 / 59.94 按 NTSC 的 1001 分母处理
+#[derive(Debug, Clone, Copy)]
+pub struct FrameRate {
+    pub fps: f64,
+}
+
+impl FrameRate {
+    /// 时间码使用的整数帧率
+    pub fn timecode_base(&self) -> u64 {
+        self.fps.round().max(1.0) as u64
+    }
+
+    /// 单帧时长的有理数表示 (分子, 分母)
+    pub fn frame_duration(&self) -> (u64, u64) {
+        let base = self.timecode_base();
+        if (self.fps - self.fps.round()).abs() > 0.001 {
+            (1001, base * 1000)
+        } else {
+            (1, base)
+        }
+    }
+
+    /// 秒数转换为帧数（至少 1 帧）
+    pub fn frames(&self, seconds: f64) -> u64 {
+        (seconds * self.fps).round().max(1.0) as u64
+    }
+
+    /// 帧数转换为 HH:MM:SS:FF 时间码（非丢帧）
+    pub fn timecode(&self, frames: u64) -> String {
+        let base = self.timecode_base();
+        let seconds = frames / base;
+        format!(
+            "{:02}:{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60,
+            frames % base
+        )
+    }
+
+    /// 帧数转换为 FCPXML 的有理数时间，如 72/24s
+    fn rational(&self, frames: u64) -> String {
+        if frames == 0 {
+            return "0s".to_string();
+        }
+        let (numer, denom) = self.frame_duration();
+        format!("{}/{}s", frames * numer, denom)
+    }
+}
+
+/// 本地路径转换为 file:// URL（按 RFC 3986 转义）
+pub fn file_url(path: &Path) -> String {
+    let raw = path.to_string_lossy().replace('\\', "/");
+    let mut url = String::from(if raw.starts_with('/') { "file://" } else { "file:///" });
+    for byte in raw.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' | b':' => url.push(byte as char),
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+/// XML 属性转义
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#10;")
+}
+
+/// EDL 注释只能单行
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// CMX3600 EDL：记录时间从 01:00:00:00 开始，标记写为 LOC 定位点
+pub fn to_edl(title: &str, clips: &[TimelineClip], rate: FrameRate) -> String {
+    let mut edl = format!("TITLE: {}\nFCM: NON-DROP FRAME\n", single_line(title));
+    let mut record = rate.timecode_base() * 3600;
+
+    for (index, clip) in clips.iter().enumerate() {
+        let reel = if clip.media.is_some() { "AX" } else { "BL" };
+        edl.push_str(&format!(
+            "\n{:03}  {:<8} V     C        {} {} {} {}\n",
+            index + 1,
+            reel,
+            rate.timecode(0),
+            rate.timecode(clip.frames),
+            rate.timecode(record),
+            rate.timecode(record + clip.frames),
+        ));
+        edl.push_str(&format!("* FROM CLIP NAME: {}\n", single_line(&clip.name)));
+        if let Some(media) = &clip.media {
+            edl.push_str(&format!("* SOURCE FILE: {}\n", media.to_string_lossy()));
+        }
+        for (name, text) in &clip.markers {
+            edl.push_str(&format!("* LOC: {} BLUE {}: {}\n", rate.timecode(record), name, single_line(text)));
+        }
+        record += clip.frames;
+    }
+    edl
+}
+
+/// FCPXML 1.9：视频用 asset-clip，静帧用 video，无媒体用 gap
+pub fn to_fcpxml(title: &str, clips: &[TimelineClip], rate: FrameRate, width: u32, height: u32) -> String {
+    let title = xml_escape(title);
+    let total: u64 = clips.iter().map(|c| c.frames).sum();
+    let (numer, denom) = rate.frame_duration();
+
+    let mut resources = format!(
+        "    <format id=\"r1\" frameDuration=\"{}/{}s\" width=\"{}\" height=\"{}\"/>\n",
+        numer, denom, width, height
+    );
+    let mut spine = String::new();
+    let mut offset = 0;
+
+    for (index, clip) in clips.iter().enumerate() {
+        let name = xml_escape(&clip.name);
+        let markers: String = clip.markers.iter().map(|(marker, text)| {
+            format!(
+                "              <marker start=\"0s\" duration=\"{}\" value=\"{}: {}\"/>\n",
+                rate.rational(1), xml_escape(marker), xml_escape(text)
+            )
+        }).collect();
+        let timing = format!(
+            "offset=\"{}\" duration=\"{}\" start=\"0s\"",
+            rate.rational(offset), rate.rational(clip.frames)
+        );
+
+        let (element, open) = match &clip.media {
+            Some(media) => {
+                let asset_id = format!("r{}", index + 2);
+                let (asset_duration, element) = if clip.is_video {
+                    (rate.rational(clip.frames), "asset-clip")
+                } else {
+                    ("0s".to_string(), "video")
+                };
+                resources.push_str(&format!(
+                    "    <asset id=\"{}\" name=\"{}\" start=\"0s\" duration=\"{}\" hasVideo=\"1\" format=\"r1\">\n      <media-rep kind=\"original-media\" src=\"{}\"/>\n    </asset>\n",
+                    asset_id, name, asset_duration, xml_escape(&file_url(media))
+                ));
+                (element, format!("<{} ref=\"{}\" name=\"{}\" {}", element, asset_id, name, timing))
+            }
+            None => ("gap", format!("<gap name=\"{}\" {}", name, timing)),
+        };
+
+        if markers.is_empty() {
+            spine.push_str(&format!("            {}/>\n", open));
+        } else {
+            spine.push_str(&format!("            {}>\n{}            </{}>\n", open, markers, element));
+        }
+        offset += clip.frames;
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE fcpxml>\n<fcpxml version=\"1.9\">\n  <resources>\n{}  </resources>\n  <library>\n    <event name=\"{}\">\n      <project name=\"{}\">\n        <sequence format=\"r1\" duration=\"{}\" tcStart=\"0s\" tcFormat=\"NDF\">\n          <spine>\n{}          </spine>\n        </sequence>\n      </project>\n    </event>\n  </library>\n</fcpxml>\n",
+        resources,
+        title,
+        title,
+        rate.rational(total),
+        spine,
+    )
+}
+
+/// OpenTimelineIO 的 RationalTime / TimeRange
+fn otio_time(frames: u64, rate: FrameRate) -> Value {
+    json!({ "OTIO_SCHEMA": "RationalTime.1", "rate": rate.fps, "value": frames as f64 })
+}
+
+fn otio_range(start: u64, duration: u64, rate: FrameRate) -> Value {
+    json!({
+        "OTIO_SCHEMA": "TimeRange.1",
+        "start_time": otio_time(start, rate),
+        "duration": otio_time(duration, rate),
+    })
+}
+
+/// OpenTimelineIO JSON（.otio）
+pub fn to_otio(title: &str, clips: &[TimelineClip], rate: FrameRate) -> Value {
+    let children: Vec<Value> = clips.iter().map(|clip| {
+        let media_reference = match &clip.media {
+            Some(media) => json!({
+                "OTIO_SCHEMA": "ExternalReference.1",
+                "name": clip.name,
+                "target_url": file_url(media),
+                "available_range": if clip.is_video { otio_range(0, clip.frames, rate) } else { Value::Null },
+                "metadata": {},
+            }),
+            None => json!({
+                "OTIO_SCHEMA": "MissingReference.1",
+                "name": clip.name,
+                "available_range": Value::Null,
+                "metadata": {},
+            }),
+        };
+        let markers: Vec<Value> = clip.markers.iter().map(|(name, text)| json!({
+            "OTIO_SCHEMA": "Marker.2",
+            "name": name,
+            "color": "BLUE",
+            "comment": text,
+            "marked_range": otio_range(0, 0, rate),
+            "metadata": {},
+        })).collect();
+
+        json!({
+            "OTIO_SCHEMA": "Clip.1",
+            "name": clip.name,
+            "source_range": otio_range(0, clip.frames, rate),
+            "media_reference": media_reference,
+            "markers": markers,
+            "effects": [],
+            "metadata": {},
+        })
+    }).collect();
+
+    json!({
+        "OTIO_SCHEMA": "Timeline.1",
+        "name": title,
+        "global_start_time": Value::Null,
+        "metadata": {},
+        "tracks": {
+            "OTIO_SCHEMA": "Stack.1",
+            "name": "tracks",
+            "source_range": Value::Null,
+            "effects": [],
+            "markers": [],
+            "metadata": {},
+            "children": [{
+                "OTIO_SCHEMA": "Track.1",
+                "name": "V1",
+                "kind": "Video",
+                "source_range": Value::Null,
+                "effects": [],
+                "markers": [],
+                "metadata": {},
+                "children": children,
+            }],
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clips() -> Vec<TimelineClip> {
+        vec![
+            TimelineClip {
+                name: "A1".to_string(),
+                media: Some(PathBuf::from("/tmp/my shot.png")),
+                is_video: false,
+                frames: 72,
+                markers: vec![("备注".to_string(), "推镜".to_string())],
+            },
+            TimelineClip { name: "A2".to_string(), media: None, is_video: false, frames: 36, markers: Vec::new() },
+        ]
+    }
+
+    #[test]
+    fn test_timecode_and_rates() {
+        let rate = FrameRate { fps: 24.0 };
+        assert_eq!(rate.timecode(24 * 3600 + 25), "01:00:01:01");
+        assert_eq!(rate.frame_duration(), (1, 24));
+        assert_eq!(FrameRate { fps: 23.976 }.frame_duration(), (1001, 24000));
+        assert_eq!(file_url(Path::new("/tmp/my shot.png")), "file:///tmp/my%20shot.png");
+    }
+
+    #[test]
+    fn test_timeline_formats() {
+        let rate = FrameRate { fps: 24.0 };
+        let edl = to_edl("demo", &clips(), rate);
+        assert!(edl.contains("001  AX       V     C        00:00:00:00 00:00:03:00 01:00:00:00 01:00:03:00"));
+        assert!(edl.contains("002  BL       V     C        00:00:00:00 00:00:01:12 01:00:03:00 01:00:04:12"));
+        assert!(edl.contains("* LOC: 01:00:00:00 BLUE 备注: 推镜"));
+
+        let fcpxml = to_fcpxml("demo", &clips(), rate, 1920, 1080);
+        assert!(fcpxml.contains("<video ref=\"r2\" name=\"A1\" offset=\"0s\" duration=\"72/24s\" start=\"0s\">"));
+        assert!(fcpxml.contains("<gap name=\"A2\" offset=\"72/24s\" duration=\"36/24s\" start=\"0s\"/>"));
+
+        let otio = to_otio("demo", &clips(), rate);
+        let track = &otio["tracks"]["children"][0]["children"];
+        assert_eq!(track[0]["source_range"]["duration"]["value"], json!(72.0));
+        assert_eq!(track[1]["media_reference"]["OTIO_SCHEMA"], json!("MissingReference.1"));
+    }
+}
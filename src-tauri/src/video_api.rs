@@ -0,0 +1,96 @@
+use crate::async_task::{submit_task, wait_for_task, TaskStatus};
+use crate::models::{ApiConfig, AsyncTaskConfig};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 正在生成视频的分镜数（项目目录 → 数量），切换分镜版本前需为 0
+#[derive(Default)]
+pub struct VideoGenerationState {
+    running: Mutex<HashMap<String, usize>>,
+}
+
+impl VideoGenerationState {
+    /// 登记一次视频生成，返回的守卫在释放时注销
+    pub fn begin(&self, folder_path: &str) -> VideoGenerationGuard<'_> {
+        if let Ok(mut running) = self.running.lock() {
+            *running.entry(folder_path.to_string()).or_default() += 1;
+        }
+        VideoGenerationGuard { state: self, folder_path: folder_path.to_string() }
+    }
+
+    /// 项目是否有视频正在生成
+    pub fn is_running(&self, folder_path: &str) -> bool {
+        self.running
+            .lock()
+            .map(|running| running.get(folder_path).is_some_and(|count| *count > 0))
+            .unwrap_or(false)
+    }
+}
+
+/// 视频生成登记守卫
+pub struct VideoGenerationGuard<'a> {
+    state: &'a VideoGenerationState,
+    folder_path: String,
+}
+
+impl Drop for VideoGenerationGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut running) = self.state.running.lock() {
+            if let Some(count) = running.get_mut(&self.folder_path) {
+                *count -= 1;
+                if *count == 0 {
+                    running.remove(&self.folder_path);
+                }
+            }
+        }
+    }
+}
+
+/// 视频生成请求参数
+pub struct VideoRequest<'a> {
+    pub prompt: &'a str,
+    /// 首帧图片（data URL）
+    pub first_frame: Option<String>,
+    /// 尾帧图片（data URL）
+    pub last_frame: Option<String>,
+    pub duration: Option<f64>,
+}
+
+/// 视频接口的异步任务配置：优先使用 API 配置中的适配，否则使用内置格式
+pub fn video_task_config(api_config: &ApiConfig) -> AsyncTaskConfig {
+    api_config.async_task.clone().unwrap_or_else(AsyncTaskConfig::default_video)
+}
+
+/// 提交视频生成任务，返回任务 id
+pub fn submit_video_task(api_config: &ApiConfig, request: &VideoRequest) -> Result<String, String> {
+    let mut body = json!({
+        "model": api_config.model.as_deref().unwrap_or(""),
+        "prompt": request.prompt,
+    });
+    if let Some(first) = &request.first_frame {
+        body["first_frame_image"] = json!(first);
+    }
+    if let Some(last) = &request.last_frame {
+        body["last_frame_image"] = json!(last);
+    }
+    if let Some(duration) = request.duration.filter(|d| *d > 0.0) {
+        body["duration"] = json!(duration.round().max(1.0) as i64);
+    }
+
+    submit_task(api_config, &video_task_config(api_config), "/v1/video/generations", body)
+        .map_err(|e| format!("视频生成请求失败: {}", e))
+}
+
+/// 视频任务默认超时（秒），可由 API 配置的 async_task 覆盖
+const VIDEO_TIMEOUT_SECS: u64 = 15 * 60;
+
+/// 轮询视频任务直到完成、失败或超时；仍在生成时调用 on_running
+pub fn wait_for_video_task(
+    api_config: &ApiConfig,
+    task_id: &str,
+    on_running: impl FnMut(&str),
+) -> Result<TaskStatus, String> {
+    wait_for_task(api_config, &video_task_config(api_config), task_id, VIDEO_TIMEOUT_SECS, on_running)
+        .map_err(|e| format!("视频生成失败: {}", e))
+}
@@ -0,0 +1,315 @@
+use crate::db::ProjectDatabase;
+use crate::models::VocabularyTerm;
+use crate::review::LOCKED;
+
+/// 受控词表的类别（对应 storyboards 中的列）
+pub const CATEGORIES: [&str; 5] = ["shot_size", "shot_type", "camera_movement", "camera_angle", "transition"];
+
+/// 内置景别：规范值（中文）、英文名、别名
+const DEFAULT_SHOT_SIZES: &[(&str, &str, &[&str])] = &[
+    ("大远景", "Extreme Long Shot", &["ELS", "EWS", "XLS", "extreme wide shot", "极远景"]),
+    ("远景", "Long Shot", &["LS", "WS", "wide shot", "wide"]),
+    ("全景", "Full Shot", &["FS", "full", "全身"]),
+    ("中全景", "Medium Long Shot", &["MLS", "MFS", "medium full shot", "cowboy shot", "中远景", "七分身"]),
+    ("中景", "Medium Shot", &["MS", "mid shot", "medium", "半身"]),
+    ("中近景", "Medium Close-Up", &["MCU", "medium closeup"]),
+    ("近景", "Close Shot", &["CS", "胸部以上"]),
+    ("特写", "Close-Up", &["CU", "closeup", "close up", "面部特写"]),
+    ("大特写", "Extreme Close-Up", &["ECU", "XCU", "BCU", "extreme closeup", "极特写", "细节特写"]),
+];
+
+/// 内置镜头类型（拍摄对象与构图方式；运镜只记在 camera_movement 中）
+const DEFAULT_SHOT_TYPES: &[(&str, &str, &[&str])] = &[
+    ("单人", "Single", &["single shot", "单人镜头"]),
+    ("双人", "Two Shot", &["2 shot", "two-shot", "双人镜头"]),
+    ("群像", "Group Shot", &["group", "crowd shot", "多人镜头"]),
+    ("过肩", "Over the Shoulder", &["OTS", "OS", "over shoulder", "过肩镜头"]),
+    ("反打", "Reverse Shot", &["reverse", "reverse angle", "正反打"]),
+    ("主观", "POV", &["point of view", "主观镜头", "第一人称"]),
+    ("空镜", "Establishing Shot", &["establishing", "empty shot", "空镜头", "环境镜头"]),
+    ("插入", "Insert", &["insert shot", "cutaway", "插入镜头", "插入特写"]),
+    ("航拍", "Aerial", &["drone", "aerial shot", "无人机", "俯瞰航拍"]),
+];
+
+/// 旧版镜头类型中的运镜值 → 运镜方式与方向（迁移到 camera_movement / camera_direction）
+pub const MOVEMENT_SHOT_TYPES: &[(&str, &str, &str)] = &[
+    ("固定", "静止", ""),
+    ("推", "推拉", "推进"),
+    ("拉", "推拉", "拉远"),
+    ("摇", "横摇", ""),
+    ("移", "横移", ""),
+    ("跟", "跟拍", ""),
+    ("升降", "升降", ""),
+    ("手持", "手持", ""),
+    ("环绕", "环绕", ""),
+    ("变焦", "变焦", ""),
+];
+
+/// 内置运镜方式（方向另见 camera_direction）
+const DEFAULT_CAMERA_MOVEMENTS: &[(&str, &str, &[&str])] = &[
+    ("静止", "Static", &["fixed", "locked off", "none", "无", "固定", "固定镜头"]),
+    ("横摇", "Pan", &["panning", "摇", "摇摄", "摇镜头"]),
+    ("俯仰", "Tilt", &["tilting", "直摇", "上下摇"]),
+    ("推拉", "Dolly", &["dolly in", "dolly out", "push in", "pull out", "推", "拉", "推轨", "推镜头", "拉镜头", "推进", "拉远"]),
+    ("横移", "Truck", &["trucking", "移", "平移", "移镜头", "移动"]),
+    ("升降", "Crane", &["crane shot", "boom", "jib", "pedestal", "升", "降", "升降镜头"]),
+    ("手持", "Handheld", &["hand held", "肩扛", "steadicam", "斯坦尼康", "手持镜头"]),
+    ("变焦", "Zoom", &["zoom in", "zoom out", "变焦推", "变焦拉"]),
+    ("跟拍", "Tracking", &["follow", "tracking shot", "跟", "跟随", "跟镜头"]),
+    ("环绕", "Orbit", &["arc", "arc shot", "360", "绕拍", "环绕镜头"]),
+];
+
+/// 内置拍摄角度
+const DEFAULT_CAMERA_ANGLES: &[(&str, &str, &[&str])] = &[
+    ("平视", "Eye Level", &["eye", "eye-level shot", "水平"]),
+    ("俯拍", "High Angle", &["high", "俯视", "俯角"]),
+    ("仰拍", "Low Angle", &["low", "仰视", "仰角"]),
+    ("顶拍", "Bird's Eye View", &["top down", "overhead", "鸟瞰", "垂直俯拍"]),
+    ("虫视", "Worm's Eye View", &["worms eye", "极低角度"]),
+    ("倾斜", "Dutch Angle", &["dutch", "dutch tilt", "canted", "荷兰角", "斜角"]),
+];
+
+/// 内置转场
+const DEFAULT_TRANSITIONS: &[(&str, &str, &[&str])] = &[
+    ("切", "Cut", &["cut to", "hard cut", "直切", "硬切"]),
+    ("叠化", "Dissolve", &["cross dissolve", "溶解", "交叉叠化"]),
+    ("淡入", "Fade In", &["fade from black", "渐显"]),
+    ("淡出", "Fade Out", &["fade to black", "渐隐"]),
+    ("划像", "Wipe", &["擦除"]),
+    ("匹配剪辑", "Match Cut", &["match", "匹配切"]),
+    ("跳切", "Jump Cut", &["jump"]),
+];
+
+/// 内置词条
+pub fn default_terms() -> Vec<VocabularyTerm> {
+    let mut terms = Vec::new();
+    for (category, defaults) in [
+        ("shot_size", DEFAULT_SHOT_SIZES),
+        ("shot_type", DEFAULT_SHOT_TYPES),
+        ("camera_movement", DEFAULT_CAMERA_MOVEMENTS),
+        ("camera_angle", DEFAULT_CAMERA_ANGLES),
+        ("transition", DEFAULT_TRANSITIONS),
+    ] {
+        for (i, (value, label_en, aliases)) in defaults.iter().enumerate() {
+            terms.push(VocabularyTerm {
+                id: None,
+                category: category.to_string(),
+                value: value.to_string(),
+                label_zh: value.to_string(),
+                label_en: label_en.to_string(),
+                aliases: aliases.iter().map(|a| a.to_string()).collect(),
+                sort_order: i as i64,
+            });
+        }
+    }
+    terms
+}
+
+/// 匹配键：只保留文字与数字并转小写（忽略空格、连字符、标点差异）
+fn match_key(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// 项目词表（内置词条 + 项目自定义词条）
+pub struct Vocabulary {
+    terms: Vec<VocabularyTerm>,
+}
+
+impl Vocabulary {
+    /// 读取项目词表
+    pub fn load(db: &ProjectDatabase) -> Result<Self, String> {
+        Ok(Self { terms: list_terms(db, None)? })
+    }
+
+    /// 查找与输入匹配的词条：依次比较规范值、中英文名与别名
+    pub fn lookup(&self, category: &str, text: &str) -> Option<&VocabularyTerm> {
+        let key = match_key(text);
+        if key.is_empty() {
+            return None;
+        }
+        self.terms.iter()
+            .filter(|t| t.category == category)
+            .find(|t| {
+                [&t.value, &t.label_zh, &t.label_en].into_iter()
+                    .chain(t.aliases.iter())
+                    .any(|candidate| match_key(candidate) == key)
+            })
+    }
+
+    /// 显示名称：已收录的取英文或中文名，未收录的返回原值
+    pub fn label(&self, category: &str, text: &str, english: bool) -> String {
+        match self.lookup(category, text) {
+            Some(term) if english && !term.label_en.is_empty() => term.label_en.clone(),
+            Some(term) if !term.label_zh.is_empty() => term.label_zh.clone(),
+            _ => text.trim().to_string(),
+        }
+    }
+
+    /// 规范化取值：能识别的返回规范值，无法识别的原样保留（去掉首尾空白）
+    pub fn normalize(&self, category: &str, text: Option<String>) -> Option<String> {
+        let text = text?;
+        match self.lookup(category, &text) {
+            Some(term) => Some(term.value.clone()),
+            None => Some(text.trim().to_string()),
+        }
+    }
+}
+
+/// 列出词条，category 为空时返回全部类别
+pub fn list_terms(db: &ProjectDatabase, category: Option<&str>) -> Result<Vec<VocabularyTerm>, String> {
+    let mut stmt = db.conn().prepare(
+        "SELECT id, category, value, label_zh, label_en, aliases, sort_order FROM vocabulary_terms
+         WHERE ?1 IS NULL OR category = ?1
+         ORDER BY category, sort_order, id"
+    ).map_err(|e| format!("查询词表失败: {}", e))?;
+    let terms = stmt.query_map([category], |row| {
+        let aliases: String = row.get(5)?;
+        Ok(VocabularyTerm {
+            id: Some(row.get(0)?),
+            category: row.get(1)?,
+            value: row.get(2)?,
+            label_zh: row.get(3)?,
+            label_en: row.get(4)?,
+            aliases: serde_json::from_str(&aliases).unwrap_or_default(),
+            sort_order: row.get(6)?,
+        })
+    }).map_err(|e| format!("解析词表失败: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("收集词表失败: {}", e))?;
+    Ok(terms)
+}
+
+/// 新增或修改词条；规范值修改时同步更新已使用旧值的分镜（已锁定的除外）
+pub fn save_term(db: &ProjectDatabase, term: &VocabularyTerm) -> Result<i64, String> {
+    if !CATEGORIES.contains(&term.category.as_str()) {
+        return Err(format!("不支持的词表类别: {}", term.category));
+    }
+    let value = term.value.trim();
+    if value.is_empty() {
+        return Err("词条规范值不能为空".to_string());
+    }
+    let aliases: Vec<&str> = term.aliases.iter().map(|a| a.trim()).filter(|a| !a.is_empty()).collect();
+    let aliases = serde_json::to_string(&aliases).map_err(|e| format!("序列化别名失败: {}", e))?;
+
+    // 同一类别下的匹配键不能与其他词条冲突
+    let vocabulary = Vocabulary::load(db)?;
+    for candidate in [value, term.label_zh.as_str(), term.label_en.as_str()].into_iter().chain(term.aliases.iter().map(String::as_str)) {
+        if let Some(existing) = vocabulary.lookup(&term.category, candidate) {
+            if existing.id != term.id {
+                return Err(format!("“{}”已属于词条“{}”", candidate.trim(), existing.value));
+            }
+        }
+    }
+
+    match term.id {
+        Some(id) => {
+            let old_value: String = db.conn().query_row(
+                "SELECT value FROM vocabulary_terms WHERE id = ?1 AND category = ?2",
+                rusqlite::params![id, term.category],
+                |row| row.get(0),
+            ).map_err(|_| format!("词条 {} 不存在", id))?;
+            db.conn().execute(
+                "UPDATE vocabulary_terms SET value = ?1, label_zh = ?2, label_en = ?3, aliases = ?4, sort_order = ?5 WHERE id = ?6",
+                rusqlite::params![value, term.label_zh.trim(), term.label_en.trim(), aliases, term.sort_order, id],
+            ).map_err(|e| format!("保存词条失败: {}", e))?;
+            if old_value != value {
+                db.conn().execute(
+                    &format!("UPDATE storyboards SET {0} = ?1 WHERE {0} = ?2 AND review_status != ?3", term.category),
+                    [value, old_value.as_str(), LOCKED],
+                ).map_err(|e| format!("更新分镜失败: {}", e))?;
+            }
+            Ok(id)
+        }
+        None => {
+            db.conn().execute(
+                "INSERT INTO vocabulary_terms (category, value, label_zh, label_en, aliases, sort_order)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![term.category, value, term.label_zh.trim(), term.label_en.trim(), aliases, term.sort_order],
+            ).map_err(|e| format!("保存词条失败: {}", e))?;
+            Ok(db.conn().last_insert_rowid())
+        }
+    }
+}
+
+/// 删除词条（已使用该值的分镜保持原值）
+pub fn delete_term(db: &ProjectDatabase, id: i64) -> Result<(), String> {
+    db.conn().execute("DELETE FROM vocabulary_terms WHERE id = ?1", [id])
+        .map_err(|e| format!("删除词条失败: {}", e))?;
+    Ok(())
+}
+
+/// 按词表规范化项目中已有分镜的各受控字段（已锁定的除外），返回修改的分镜数
+pub fn normalize_storyboards(db: &ProjectDatabase) -> Result<usize, String> {
+    let vocabulary = Vocabulary::load(db)?;
+    let mut stmt = db.conn()
+        .prepare(&format!("SELECT mirror_id, {} FROM storyboards WHERE review_status != ?1", CATEGORIES.join(", ")))
+        .map_err(|e| format!("查询分镜失败: {}", e))?;
+    let rows = stmt.query_map([LOCKED], |row| {
+        let values = (1..=CATEGORIES.len())
+            .map(|i| row.get::<_, Option<String>>(i))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((row.get::<_, String>(0)?, values))
+    }).map_err(|e| format!("查询分镜失败: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("查询分镜失败: {}", e))?;
+
+    let mut changed = 0;
+    for (mirror_id, values) in rows {
+        let normalized: Vec<Option<String>> = CATEGORIES.iter().zip(&values)
+            .map(|(category, value)| vocabulary.normalize(category, value.clone()))
+            .collect();
+        if normalized != values {
+            let assignments = CATEGORIES.iter().enumerate()
+                .map(|(i, category)| format!("{} = ?{}", category, i + 1))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let mut params: Vec<Option<String>> = normalized;
+            params.push(Some(mirror_id));
+            db.conn().execute(
+                &format!("UPDATE storyboards SET {} WHERE mirror_id = ?{}", assignments, CATEGORIES.len() + 1),
+                rusqlite::params_from_iter(params),
+            ).map_err(|e| format!("更新分镜失败: {}", e))?;
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::temp_project;
+
+    #[test]
+    fn test_normalize_terms() {
+        let dir = temp_project("vocabulary");
+        let db = ProjectDatabase::open(&dir).unwrap();
+        let vocabulary = Vocabulary::load(&db).unwrap();
+        for raw in ["中景", "Medium shot", "MS", " medium-shot "] {
+            assert_eq!(vocabulary.normalize("shot_size", Some(raw.to_string())).as_deref(), Some("中景"));
+        }
+        assert_eq!(vocabulary.normalize("shot_type", Some("over shoulder".to_string())).as_deref(), Some("过肩"));
+        assert_eq!(vocabulary.normalize("camera_movement", Some("固定镜头".to_string())).as_deref(), Some("静止"));
+        assert_eq!(vocabulary.normalize("shot_size", Some("鸟瞰 ".to_string())).as_deref(), Some("鸟瞰"));
+        assert_eq!(vocabulary.normalize("camera_movement", Some("dolly in".to_string())).as_deref(), Some("推拉"));
+        assert_eq!(vocabulary.label("camera_angle", "仰视", true), "Low Angle");
+
+        // 项目自定义词条，别名不能与已有词条冲突
+        let mut custom = VocabularyTerm {
+            id: None,
+            category: "shot_size".to_string(),
+            value: "鸟瞰".to_string(),
+            label_zh: "鸟瞰".to_string(),
+            label_en: "Bird's Eye".to_string(),
+            aliases: vec!["MS".to_string()],
+            sort_order: 99,
+        };
+        assert!(save_term(&db, &custom).is_err());
+        custom.aliases = vec!["top shot".to_string()];
+        save_term(&db, &custom).unwrap();
+        let vocabulary = Vocabulary::load(&db).unwrap();
+        assert_eq!(vocabulary.normalize("shot_size", Some("Top Shot".to_string())).as_deref(), Some("鸟瞰"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::models::{Character, Prop, Scene, Storyboard};
+use crate::thumbnails::{encode_png, load_thumbnail};
+use rust_xlsxwriter::{Color, Format, FormatAlign, FormatBorder, Image, Workbook, Worksheet, XlsxError};
+use std::path::{Path, PathBuf};
+
+/// 分镜表列：(表头, 列宽)
+pub const STORYBOARD_HEADERS: &[(&str, f64)] = &[
+    ("序号", 6.0),
+    ("镜号", 8.0),
+    ("首帧", 24.0),
+    ("尾帧", 24.0),
+    ("景别", 8.0),
+    ("镜头类型", 10.0),
+    ("时长(秒)", 8.0),
+    ("对白", 30.0),
+    ("画面描述", 40.0),
+    ("备注", 20.0),
+    ("首帧提示词(中)", 40.0),
+    ("首帧提示词(英)", 40.0),
+    ("尾帧提示词(中)", 40.0),
+    ("尾帧提示词(英)", 40.0),
+    ("视频提示词(中)", 40.0),
+    ("视频提示词(英)", 40.0),
+];
+
+/// 资产表列（角色 / 场景 / 道具通用）
+pub const ASSET_HEADERS: &[(&str, f64)] = &[
+    ("名称", 14.0),
+    ("参考图", 24.0),
+    ("描述", 40.0),
+    ("提示词(中)", 40.0),
+    ("提示词(英)", 40.0),
+    ("备注", 20.0),
+];
+
+/// 缩略图所在行的行高（磅）与缩略图最长边（像素）
+const IMAGE_ROW_HEIGHT: f64 = 96.0;
+const THUMBNAIL_EDGE: u32 = 360;
+
+/// 资产行：(名称, 参考图, 描述, 提示词中, 提示词英, 备注)
+type AssetRow<'a> = (&'a str, Option<&'a String>, &'a Option<String>, &'a Option<String>, &'a Option<String>, &'a Option<String>);
+
+/// 工作簿写入器
+pub struct ProjectWorkbook {
+    pub images_dir: PathBuf,
+    pub references_dir: PathBuf,
+    /// 是否嵌入缩略图
+    pub include_images: bool,
+    /// 写入过程中的非致命问题（如图片读取失败）
+    pub warnings: Vec<String>,
+}
+
+impl ProjectWorkbook {
+    /// 写入分镜、角色、场景、道具四个工作表
+    pub fn write(
+        &mut self,
+        output: &Path,
+        storyboards: &[Storyboard],
+        characters: &[Character],
+        scenes: &[Scene],
+        props: &[Prop],
+    ) -> Result<(), String> {
+        self.build(output, storyboards, characters, scenes, props)
+            .map_err(|e| format!("写入 Excel 失败: {}", e))
+    }
+
+    fn build(
+        &mut self,
+        output: &Path,
+        storyboards: &[Storyboard],
+        characters: &[Character],
+        scenes: &[Scene],
+        props: &[Prop],
+    ) -> Result<(), XlsxError> {
+        let header = Format::new()
+            .set_bold()
+            .set_background_color(Color::RGB(0xD9E1F2))
+            .set_border(FormatBorder::Thin)
+            .set_align(FormatAlign::Center)
+            .set_align(FormatAlign::VerticalCenter)
+            .set_text_wrap();
+        let cell = Format::new()
+            .set_border(FormatBorder::Thin)
+            .set_align(FormatAlign::Top)
+            .set_text_wrap();
+
+        let mut workbook = Workbook::new();
+
+        let sheet = workbook.add_worksheet();
+        sheet.set_name("分镜")?;
+        write_header(sheet, STORYBOARD_HEADERS, &header)?;
+        for (index, sb) in storyboards.iter().enumerate() {
+            let row = index as u32 + 1;
+            sheet.write_number_with_format(row, 0, sb.sequence_number as f64, &cell)?;
+            sheet.write_string_with_format(row, 1, &sb.mirror_id, &cell)?;
+            sheet.write_string_with_format(row, 2, "", &cell)?;
+            sheet.write_string_with_format(row, 3, "", &cell)?;
+            write_text(sheet, row, 4, &sb.shot_size, &cell)?;
+            write_text(sheet, row, 5, &sb.shot_type, &cell)?;
+            match sb.duration {
+                Some(duration) => sheet.write_number_with_format(row, 6, duration, &cell)?,
+                None => sheet.write_string_with_format(row, 6, "", &cell)?,
+            };
+            let texts = [
+                &sb.dialogue, &sb.description, &sb.notes,
+                &sb.image_prompt_zh, &sb.image_prompt_en,
+                &sb.image_prompt_tail_zh, &sb.image_prompt_tail_en,
+                &sb.video_prompt_zh, &sb.video_prompt_en,
+            ];
+            for (offset, text) in texts.into_iter().enumerate() {
+                write_text(sheet, row, 7 + offset as u16, text, &cell)?;
+            }
+
+            if self.include_images {
+                sheet.set_row_height(row, IMAGE_ROW_HEIGHT)?;
+                for (col, file) in [(2, &sb.image_first_path), (3, &sb.image_last_path)] {
+                    if let Some(file) = file {
+                        let path = self.images_dir.join(file);
+                        self.insert_thumbnail(sheet, row, col, &path, &sb.mirror_id)?;
+                    }
+                }
+            }
+        }
+
+        let asset_sheets: [(&str, Vec<AssetRow>); 3] = [
+            ("角色", characters.iter().map(|a| (a.name.as_str(), a.reference_image_path.as_ref(), &a.description, &a.image_prompt_zh, &a.image_prompt_en, &a.notes)).collect()),
+            ("场景", scenes.iter().map(|a| (a.name.as_str(), a.reference_image_path.as_ref(), &a.description, &a.image_prompt_zh, &a.image_prompt_en, &a.notes)).collect()),
+            ("道具", props.iter().map(|a| (a.name.as_str(), a.reference_image_path.as_ref(), &a.description, &a.image_prompt_zh, &a.image_prompt_en, &a.notes)).collect()),
+        ];
+        for (name, rows) in asset_sheets {
+            let sheet = workbook.add_worksheet();
+            sheet.set_name(name)?;
+            write_header(sheet, ASSET_HEADERS, &header)?;
+            for (index, (asset_name, reference, description, prompt_zh, prompt_en, notes)) in rows.into_iter().enumerate() {
+                let row = index as u32 + 1;
+                sheet.write_string_with_format(row, 0, asset_name, &cell)?;
+                sheet.write_string_with_format(row, 1, "", &cell)?;
+                for (offset, text) in [description, prompt_zh, prompt_en, notes].into_iter().enumerate() {
+                    write_text(sheet, row, 2 + offset as u16, text, &cell)?;
+                }
+                if let (true, Some(reference)) = (self.include_images, reference) {
+                    sheet.set_row_height(row, IMAGE_ROW_HEIGHT)?;
+                    let path = self.references_dir.join(reference);
+                    self.insert_thumbnail(sheet, row, 1, &path, asset_name)?;
+                }
+            }
+        }
+
+        workbook.save(output)
+    }
+
+    /// 嵌入缩略图；图片缺失或无法解码时记录警告并跳过
+    fn insert_thumbnail(
+        &mut self,
+        sheet: &mut Worksheet,
+        row: u32,
+        col: u16,
+        path: &Path,
+        label: &str,
+    ) -> Result<(), XlsxError> {
+        if !path.exists() {
+            self.warnings.push(format!("{} 的图片不存在: {}", label, path.display()));
+            return Ok(());
+        }
+        let image = load_thumbnail(path, THUMBNAIL_EDGE)
+            .and_then(|thumbnail| encode_png(&thumbnail))
+            .and_then(|png| Image::new_from_buffer(&png).map_err(|e| e.to_string()));
+        match image {
+            Ok(image) => {
+                sheet.insert_image_fit_to_cell(row, col, &image, true)?;
+            }
+            Err(e) => self.warnings.push(format!("{}: {}", label, e)),
+        }
+        Ok(())
+    }
+}
+
+/// 写入表头、设置列宽并冻结首行
+fn write_header(sheet: &mut Worksheet, headers: &[(&str, f64)], format: &Format) -> Result<(), XlsxError> {
+    for (col, (title, width)) in headers.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *title, format)?;
+        sheet.set_column_width(col as u16, *width)?;
+    }
+    sheet.set_row_height(0, 24)?;
+    sheet.set_freeze_panes(1, 0)?;
+    Ok(())
+}
+
+fn write_text(sheet: &mut Worksheet, row: u32, col: u16, text: &Option<String>, format: &Format) -> Result<(), XlsxError> {
+    sheet.write_string_with_format(row, col, text.as_deref().unwrap_or(""), format)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_write_workbook() {
+        let dir = std::env::temp_dir().join(format!("storyboard_test_xlsx_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        image::RgbImage::new(32, 18).save(dir.join("a1.png")).unwrap();
+
+        let storyboard: Storyboard = serde_json::from_value(json!({
+            "sequence_number": 1,
+            "mirror_id": "A1",
+            "duration": 2.0,
+            "dialogue": "你好",
+            "image_first_path": "a1.png",
+            "image_last_path": "missing.png",
+        })).unwrap();
+        let character: Character = serde_json::from_value(json!({ "name": "小明" })).unwrap();
+
+        let mut workbook = ProjectWorkbook {
+            images_dir: dir.clone(),
+            references_dir: dir.clone(),
+            include_images: true,
+            warnings: Vec::new(),
+        };
+        let output = dir.join("out.xlsx");
+        workbook.write(&output, &[storyboard], &[character], &[], &[]).unwrap();
+
+        assert!(std::fs::read(&output).unwrap().starts_with(b"PK"));
+        assert_eq!(workbook.warnings.len(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
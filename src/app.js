@@ -1000,36 +1000,27 @@ async function handleFileUpload(event) {
 
     const fileExt = file.name.split('.').pop().toLowerCase();
 
-    // 处理 Word 文档 (.docx)
-    if (fileExt === 'docx') {
-        const reader = new FileReader();
-        reader.onload = async (e) => {
-            try {
-                const arrayBuffer = e.target.result;
-                const result = await mammoth.extractRawText({ arrayBuffer: arrayBuffer });
-                const content = result.value;
-
-                // 将内容填入输入框
-                chatInput.value = content;
-
-                addChatMessage('assistant', `已读取 DOCX 文件 (${file.size} 字节)，提取了 ${content.length} 个字符。内容已填入输入框，点击发送按钮开始生成分镜。`);
-            } catch (error) {
-                console.error('解析 Word 文档失败:', error);
-                addChatMessage('assistant', '解析 Word 文档失败，请确保文件格式正确。');
-            }
-            event.target.value = '';
-        };
-        reader.onerror = () => {
-            addChatMessage('assistant', '读取文件失败，请重试。');
-            event.target.value = '';
-        };
-        reader.readAsArrayBuffer(file);
-        return;
-    }
+    // 剧本文件交由后端解析（保留段落与标题结构）；已打开项目时原文件与提取的文本保存到项目
+    // 文件内容以二进制 IPC 直接传给后端，避免大文件（PDF）序列化为 JSON 数组
+    if (['docx', 'doc', 'pdf', 'txt', 'md', 'fountain'].includes(fileExt)) {
+        try {
+            const buffer = await file.arrayBuffer();
+            const result = await invoke('upload_script', new Uint8Array(buffer), {
+                headers: {
+                    'x-file-name': encodeURIComponent(file.name),
+                    'x-folder-path': encodeURIComponent(state.currentProject || '')
+                }
+            });
 
-    // 处理老格式 Word 文档 (.doc)
-    if (fileExt === 'doc') {
-        addChatMessage('assistant', '不支持旧版 .doc 格式，请将文件另存为 .docx 格式后再试。');
+            // 将内容填入输入框
+            chatInput.value = result.text;
+
+            const saved = result.document_id != null ? '，原文件已保存到项目' : '';
+            addChatMessage('assistant', `已读取 ${result.format.toUpperCase()} 文件 (${file.size} 字节)，提取了 ${result.paragraphs.length} 段、${result.text.length} 个字符${saved}。内容已填入输入框，点击发送按钮开始生成分镜。`);
+        } catch (error) {
+            console.error('解析剧本文件失败:', error);
+            addChatMessage('assistant', '解析剧本文件失败: ' + error);
+        }
         event.target.value = '';
         return;
     }